@@ -68,5 +68,6 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     //return vec4<f32>(l, 0, 0, 1);
     //return vec4<f32>(in.position, 1);
     let diffuseTexCol = textureSample(t_diffuse, s_diffuse, in.tex_coords);
-    return diffuseTexCol * in.colour;
+    // alpha comes from the particle colour alone, so only particles asking to be translucent are (e.g. ghost cars)
+    return vec4<f32>(diffuseTexCol.rgb * in.colour.rgb, in.colour.a);
 }
\ No newline at end of file
@@ -2,6 +2,7 @@
 
 use crate::core::math::vec2::Vec2;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Aabb2d {
     pub min: Vec2,
     pub max: Vec2,
@@ -24,4 +25,30 @@ impl Aabb2d {
             max,
         }
     }
-}
\ No newline at end of file
+
+    /// Increase the size of the bounding box by the given amount on each side
+    pub fn grow(&self, amount: Vec2) -> Self {
+        Self {
+            min: self.min - amount,
+            max: self.max + amount,
+        }
+    }
+
+    /// The smallest bounding box containing both
+    pub fn merge(&self, other: &Aabb2d) -> Self {
+        Self {
+            min: Vec2::min(self.min, other.min),
+            max: Vec2::max(self.max, other.max),
+        }
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x &&
+        point.y >= self.min.y && point.y <= self.max.y
+    }
+
+    pub fn intersects(&self, other: &Aabb2d) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x &&
+        self.min.y <= other.max.y && self.max.y >= other.min.y
+    }
+}
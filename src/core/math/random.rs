@@ -1,7 +1,7 @@
 use rand_seeder::Seeder;
 use rand_pcg::Pcg64;
 
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use now::DateTimeNow;
 
 /// Random number generator
@@ -10,21 +10,34 @@ pub struct Random {
 
 impl Random {
     pub fn seed_from_beginning_of_week() -> Pcg64 {
-        let time = Utc::now(); 
-        let beginning_of_week = time.beginning_of_week();
-        let rng: Pcg64 = Seeder::from(beginning_of_week).into_rng();
-        rng
+        Self::seed_from_week_of(Utc::now().date_naive())
     }
 
     pub fn seed_from_beginning_of_day() -> Pcg64 {
-        let time = Utc::now(); 
-        let beginning_of_day = time.beginning_of_day();
-        let rng: Pcg64 = Seeder::from(beginning_of_day).into_rng();
-        rng
+        Self::seed_from_date(Utc::now().date_naive())
     }
 
     pub fn seed_from_now() -> Pcg64 {
         let rng: Pcg64 = Seeder::from(Utc::now()).into_rng();
         rng
     }
+
+    /// Same as seed_from_beginning_of_day, on the given day
+    pub fn seed_from_date(date: NaiveDate) -> Pcg64 {
+        let beginning_of_day = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let rng: Pcg64 = Seeder::from(beginning_of_day).into_rng();
+        rng
+    }
+
+    /// Same as seed_from_beginning_of_week, in the week containing the given day
+    pub fn seed_from_week_of(date: NaiveDate) -> Pcg64 {
+        let beginning_of_week = date.and_hms_opt(0, 0, 0).unwrap().and_utc().beginning_of_week();
+        let rng: Pcg64 = Seeder::from(beginning_of_week).into_rng();
+        rng
+    }
+
+    pub fn seed_from_str(seed: &str) -> Pcg64 {
+        let rng: Pcg64 = Seeder::from(seed).into_rng();
+        rng
+    }
 }
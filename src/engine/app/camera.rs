@@ -27,21 +27,7 @@ pub struct Camera {
 
 impl Camera {
     pub fn new(device: &wgpu::Device, aspect: f32) -> Self {
-        // We are using the RHS coordinate system.
-        // Use your right hand and point the thumb along the x-axis, index fingers up along the Y Axis,
-        // so Z is middle finger points towards you.
-        let mut camera = Self {
-            eye: (0.0, 5.0, 15.0).into(), // the position of the camera
-            target: (0.0, 0.0, 0.0).into(),
-            up: cgmath::Vector3::unit_y(),
-            aspect: aspect, //config.width as f32 / config.height as f32,
-            fovy: 45.0,
-            znear: 0.1,
-            zfar: 100.0,
-
-            camera_uniform: None,
-            camera_buffer: None,
-        };
+        let mut camera = Self::headless(aspect);
 
         let mut camera_uniform = CameraUniform::new();
         camera_uniform.update_view_proj(&camera.build_view_projection_matrix());
@@ -57,6 +43,25 @@ impl Camera {
 
         camera
     }
+
+    /// A camera without the GPU side, for running the game without a window (e.g. the level checker)
+    pub fn headless(aspect: f32) -> Self {
+        // We are using the RHS coordinate system.
+        // Use your right hand and point the thumb along the x-axis, index fingers up along the Y Axis,
+        // so Z is middle finger points towards you.
+        Self {
+            eye: (0.0, 5.0, 15.0).into(), // the position of the camera
+            target: (0.0, 0.0, 0.0).into(),
+            up: cgmath::Vector3::unit_y(),
+            aspect: aspect, //config.width as f32 / config.height as f32,
+            fovy: 45.0,
+            znear: 0.1,
+            zfar: 100.0,
+
+            camera_uniform: None,
+            camera_buffer: None,
+        }
+    }
     pub fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
         let view = cgmath::Matrix4::look_at_rh(self.eye, self.target, self.up);
         let proj = cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar);
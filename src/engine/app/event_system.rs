@@ -1,9 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Write};
 use winit::event::{ElementState, MouseButton, WindowEvent, KeyEvent};
 use winit::keyboard::{KeyCode, PhysicalKey};
 
+use crate::engine::app::gamepad::{Gamepad, GamepadAxisType, GamepadButtonType};
+use crate::engine::app::input_action::{analog_value, InputAction, InputBindings};
+
 /// Serializable game event that wraps the relevant parts of WindowEvent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GameEvent {
@@ -22,6 +26,28 @@ pub enum GameEvent {
         x: f32,
         y: f32,
     },
+    GamepadButton {
+        button: GamepadButtonType,
+        state: ElementStateType,
+    },
+    GamepadButtonValue {
+        button: GamepadButtonType,
+        value: f32, // how far an analog button, like a trigger, is pressed
+    },
+    GamepadAxis {
+        axis: GamepadAxisType,
+        value: f32,
+    },
+    // queued after the key, button or axis event it was bound to
+    Action {
+        action: InputAction,
+        state: ElementStateType,
+    },
+    // how far an analog input bound to the action is pushed, 0 to 1
+    ActionValue {
+        action: InputAction,
+        value: f32,
+    },
 }
 
 /// Serializable mouse button type
@@ -47,23 +73,67 @@ pub enum ElementStateType {
 pub enum KeyCodeType {
     Escape,
     Space,
+    Enter,
+    Tab,
+    Backspace,
     ShiftLeft,
+    ShiftRight,
+    ControlLeft,
+    ControlRight,
+    AltLeft,
+    AltRight,
     ArrowLeft,
     ArrowRight,
     ArrowUp,
     ArrowDown,
     KeyA,
+    KeyB,
+    KeyC,
     KeyD,
-    KeyW,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
     KeyS,
-    KeyZ,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
     KeyX,
+    KeyY,
+    KeyZ,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
     F9,
     F10,
     F11,
     F12,
-    KeyR,
-    // Add more as needed
     Unknown,
 }
 
@@ -72,27 +142,80 @@ impl From<KeyCode> for KeyCodeType {
         match code {
             KeyCode::Escape => KeyCodeType::Escape,
             KeyCode::Space => KeyCodeType::Space,
+            KeyCode::Enter => KeyCodeType::Enter,
+            KeyCode::Tab => KeyCodeType::Tab,
+            KeyCode::Backspace => KeyCodeType::Backspace,
             KeyCode::ShiftLeft => KeyCodeType::ShiftLeft,
+            KeyCode::ShiftRight => KeyCodeType::ShiftRight,
+            KeyCode::ControlLeft => KeyCodeType::ControlLeft,
+            KeyCode::ControlRight => KeyCodeType::ControlRight,
+            KeyCode::AltLeft => KeyCodeType::AltLeft,
+            KeyCode::AltRight => KeyCodeType::AltRight,
             KeyCode::ArrowLeft => KeyCodeType::ArrowLeft,
             KeyCode::ArrowRight => KeyCodeType::ArrowRight,
             KeyCode::ArrowUp => KeyCodeType::ArrowUp,
             KeyCode::ArrowDown => KeyCodeType::ArrowDown,
             KeyCode::KeyA => KeyCodeType::KeyA,
+            KeyCode::KeyB => KeyCodeType::KeyB,
+            KeyCode::KeyC => KeyCodeType::KeyC,
             KeyCode::KeyD => KeyCodeType::KeyD,
-            KeyCode::KeyW => KeyCodeType::KeyW,
+            KeyCode::KeyE => KeyCodeType::KeyE,
+            KeyCode::KeyF => KeyCodeType::KeyF,
+            KeyCode::KeyG => KeyCodeType::KeyG,
+            KeyCode::KeyH => KeyCodeType::KeyH,
+            KeyCode::KeyI => KeyCodeType::KeyI,
+            KeyCode::KeyJ => KeyCodeType::KeyJ,
+            KeyCode::KeyK => KeyCodeType::KeyK,
+            KeyCode::KeyL => KeyCodeType::KeyL,
+            KeyCode::KeyM => KeyCodeType::KeyM,
+            KeyCode::KeyN => KeyCodeType::KeyN,
+            KeyCode::KeyO => KeyCodeType::KeyO,
+            KeyCode::KeyP => KeyCodeType::KeyP,
+            KeyCode::KeyQ => KeyCodeType::KeyQ,
+            KeyCode::KeyR => KeyCodeType::KeyR,
             KeyCode::KeyS => KeyCodeType::KeyS,
-            KeyCode::KeyZ => KeyCodeType::KeyZ,
+            KeyCode::KeyT => KeyCodeType::KeyT,
+            KeyCode::KeyU => KeyCodeType::KeyU,
+            KeyCode::KeyV => KeyCodeType::KeyV,
+            KeyCode::KeyW => KeyCodeType::KeyW,
             KeyCode::KeyX => KeyCodeType::KeyX,
+            KeyCode::KeyY => KeyCodeType::KeyY,
+            KeyCode::KeyZ => KeyCodeType::KeyZ,
+            KeyCode::Digit0 => KeyCodeType::Digit0,
+            KeyCode::Digit1 => KeyCodeType::Digit1,
+            KeyCode::Digit2 => KeyCodeType::Digit2,
+            KeyCode::Digit3 => KeyCodeType::Digit3,
+            KeyCode::Digit4 => KeyCodeType::Digit4,
+            KeyCode::Digit5 => KeyCodeType::Digit5,
+            KeyCode::Digit6 => KeyCodeType::Digit6,
+            KeyCode::Digit7 => KeyCodeType::Digit7,
+            KeyCode::Digit8 => KeyCodeType::Digit8,
+            KeyCode::Digit9 => KeyCodeType::Digit9,
+            KeyCode::F1 => KeyCodeType::F1,
+            KeyCode::F2 => KeyCodeType::F2,
+            KeyCode::F3 => KeyCodeType::F3,
+            KeyCode::F4 => KeyCodeType::F4,
+            KeyCode::F5 => KeyCodeType::F5,
+            KeyCode::F6 => KeyCodeType::F6,
+            KeyCode::F7 => KeyCodeType::F7,
+            KeyCode::F8 => KeyCodeType::F8,
             KeyCode::F9 => KeyCodeType::F9,
             KeyCode::F10 => KeyCodeType::F10,
             KeyCode::F11 => KeyCodeType::F11,
             KeyCode::F12 => KeyCodeType::F12,
-            KeyCode::KeyR => KeyCodeType::KeyR,
             _ => KeyCodeType::Unknown,
         }
     }
 }
 
+impl KeyCodeType {
+    /// Short name to show the player, e.g. "X" for KeyX
+    pub fn label(&self) -> String {
+        let name = format!("{:?}", self);
+        name.strip_prefix("Key").or(name.strip_prefix("Digit")).unwrap_or(&name).to_owned()
+    }
+}
+
 /// Event paired with a frame number for recording/replay
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FramedEvent {
@@ -101,9 +224,50 @@ pub struct FramedEvent {
 }
 
 /// Recording of a game session
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventRecording {
     pub events: Vec<FramedEvent>,
+    #[serde(default)]
+    pub seed: Option<String>, // share code of the level played, so a replay can rebuild it
+    #[serde(default)]
+    pub level_file: Option<String>, // name of the level file played instead of the seed's level, if there was one
+    #[serde(default)]
+    pub time: Option<f32>, // finish time of the run, if it was finished
+    #[serde(default)]
+    pub split_times: Vec<Option<f32>>, // time the run passed each checkpoint
+    #[serde(default)]
+    pub respawn_at_checkpoint: bool, // the car was put back at checkpoints, which a replay needs to do too
+}
+
+impl EventRecording {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let mut recording: Self = serde_json::from_str(&json)?;
+        recording.upgrade_key_events();
+        Ok(recording)
+    }
+
+    /// Recordings used to store raw key presses, from when the car was driven with z and x and nothing else was bound
+    fn upgrade_key_events(&mut self) {
+        for framed_event in self.events.iter_mut() {
+            if let GameEvent::KeyboardInput { key_code, state } = framed_event.event {
+                let action = match key_code {
+                    KeyCodeType::KeyX => Some(InputAction::Accelerate),
+                    KeyCodeType::KeyZ => Some(InputAction::Reverse),
+                    _ => None,
+                };
+                if let Some(action) = action {
+                    framed_event.event = GameEvent::Action { action, state };
+                }
+            }
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        let mut file = fs::File::create(path)?;
+        file.write_all(json.as_bytes())
+    }
 }
 
 pub struct EventSystem {
@@ -117,11 +281,19 @@ pub struct EventSystem {
     recording: bool,
     recorded_events: Vec<FramedEvent>,
     current_frame: u128,
+    recording_seed: Option<String>,
     
     // Replay state
     replaying: bool,
     replay_events: Vec<FramedEvent>,
     replay_index: usize,
+    replay_seed: Option<String>,
+    replay_respawn_at_checkpoint: bool,
+
+    // Input
+    bindings: InputBindings,
+    gamepad: Gamepad,
+    axis_values: HashMap<GamepadAxisType, f32>, // last value of each axis, to tell when it crosses AXIS_THRESHOLD
 }
 
 impl EventSystem {
@@ -131,12 +303,27 @@ impl EventSystem {
             recording: false,
             recorded_events: vec![],
             current_frame: 0,
+            recording_seed: None,
             replaying: false,
             replay_events: vec![],
             replay_index: 0,
+            replay_seed: None,
+            replay_respawn_at_checkpoint: false,
+            bindings: InputBindings::default(),
+            gamepad: Gamepad::new(),
+            axis_values: HashMap::new(),
         }
     }
 
+    /// Set which keys and buttons do what, e.g. from the Settings
+    pub fn set_bindings(&mut self, bindings: InputBindings) {
+        self.bindings = bindings;
+    }
+
+    pub fn bindings(&self) -> &InputBindings {
+        &self.bindings
+    }
+
     /// Update the current frame number
     pub fn set_frame(&mut self, frame: u128) {
         self.current_frame = frame;
@@ -160,15 +347,26 @@ impl EventSystem {
         self.recording
     }
 
+    /// Set the seed saved with the recording
+    pub fn set_recording_seed(&mut self, seed: Option<String>) {
+        self.recording_seed = seed;
+    }
+
+    /// The events recorded so far
+    pub fn recording(&self) -> EventRecording {
+        EventRecording {
+            events: self.recorded_events.clone(),
+            seed: self.recording_seed.clone(),
+            level_file: None,
+            time: None,
+            split_times: vec![],
+            respawn_at_checkpoint: false,
+        }
+    }
+
     /// Export recorded events to a JSON file
     pub fn export_recording(&self, path: &str) -> io::Result<()> {
-        let recording = EventRecording {
-            events: self.recorded_events.clone(),
-        };
-        
-        let json = serde_json::to_string_pretty(&recording)?;
-        let mut file = fs::File::create(path)?;
-        file.write_all(json.as_bytes())?;
+        self.recording().save(path)?;
         
         println!("Exported {} events to {}", self.recorded_events.len(), path);
         Ok(())
@@ -176,11 +374,12 @@ impl EventSystem {
 
     /// Load events from a JSON file
     pub fn load_replay(&mut self, path: &str) -> io::Result<()> {
-        let json = fs::read_to_string(path)?;
-        let recording: EventRecording = serde_json::from_str(&json)?;
+        let recording = EventRecording::load(path)?;
         
         self.replay_events = recording.events;
         self.replay_index = 0;
+        self.replay_seed = recording.seed;
+        self.replay_respawn_at_checkpoint = recording.respawn_at_checkpoint;
         
         println!("Loaded {} events from {}", self.replay_events.len(), path);
         Ok(())
@@ -209,6 +408,16 @@ impl EventSystem {
         self.replaying
     }
 
+    /// The seed saved with the loaded replay, if it has one
+    pub fn replay_seed(&self) -> Option<&str> {
+        self.replay_seed.as_deref()
+    }
+
+    /// Was the loaded replay recorded with the car put back at checkpoints?
+    pub fn replay_respawn_at_checkpoint(&self) -> bool {
+        self.replay_respawn_at_checkpoint
+    }
+
     pub fn handle_window_event(&mut self, event: &WindowEvent, _scale_factor: f64) {
         if let Some(game_event) = self.window_event_to_game_event(event) {
             self.queue_event(game_event);
@@ -272,10 +481,12 @@ impl EventSystem {
     }
 
     pub fn queue_event(&mut self, event: GameEvent) {
-        // Record the event if recording is active (only mouse, keyboard and cursor events)
+        let action_events = self.action_events(&event);
+
+        // Record the event if recording is active (only mouse, cursor and action events, keys and buttons are recorded as the actions they are bound to)
         if self.recording {
             match &event {
-                GameEvent::MouseInput { .. } | GameEvent::KeyboardInput { .. } | GameEvent::CursorMoved { .. } => {
+                GameEvent::MouseInput { .. } | GameEvent::CursorMoved { .. } | GameEvent::Action { .. } | GameEvent::ActionValue { .. } => {
                     self.recorded_events.push(FramedEvent {
                         frame: self.current_frame,
                         event: event.clone(),
@@ -287,6 +498,40 @@ impl EventSystem {
 
         // Queue all events for processing
         self.events.push(event);
+
+        for action_event in action_events {
+            self.queue_event(action_event);
+        }
+    }
+
+    /// The action events for the actions a key, button or axis event is bound to
+    fn action_events(&mut self, event: &GameEvent) -> Vec<GameEvent> {
+        match *event {
+            GameEvent::KeyboardInput { key_code, state } => self.bindings.actions_for_key(key_code).map(|action| GameEvent::Action { action, state }).collect(),
+            GameEvent::GamepadButton { button, state } => self.bindings.actions_for_button(button).map(|action| GameEvent::Action { action, state }).collect(),
+            GameEvent::GamepadButtonValue { button, value } => {
+                let value = analog_value(value);
+                self.bindings.actions_for_button(button).map(|action| GameEvent::ActionValue { action, value }).collect()
+            }
+            GameEvent::GamepadAxis { axis, value } => {
+                // each way an axis is bound acts like an analog button, and is pressed while the stick is pushed far enough that way
+                let last_value = self.axis_values.insert(axis, value).unwrap_or(0.0);
+                let mut events = vec![];
+                for (direction, action) in self.bindings.gamepad_axes.iter().filter(|(direction, _)| direction.axis == axis) {
+                    let action = *action;
+                    match (direction.is_pressed(last_value), direction.is_pressed(value)) {
+                        (false, true) => events.push(GameEvent::Action { action, state: ElementStateType::Pressed }),
+                        (true, false) => events.push(GameEvent::Action { action, state: ElementStateType::Released }),
+                        _ => {}
+                    }
+                    if direction.analog_value(last_value) != direction.analog_value(value) {
+                        events.push(GameEvent::ActionValue { action, value: direction.analog_value(value) });
+                    }
+                }
+                events
+            }
+            _ => vec![],
+        }
     }
 
     /// Get replay events for the current frame and inject them into the event queue
@@ -321,6 +566,10 @@ impl EventSystem {
     }
 
     pub fn process_events(&mut self) {
+        for event in self.gamepad.poll() {
+            self.queue_event(event);
+        }
+
         // In replay mode, inject replay events first
         if self.replaying {
             self.inject_replay_events();
@@ -331,3 +580,71 @@ impl EventSystem {
         self.events.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_actions() {
+        let mut event_system = EventSystem::new();
+        event_system.set_bindings(InputBindings::default());
+        event_system.start_recording();
+        event_system.set_frame(3);
+
+        event_system.queue_event(GameEvent::KeyboardInput { key_code: KeyCodeType::KeyX, state: ElementStateType::Pressed });
+        event_system.queue_event(GameEvent::GamepadAxis { axis: GamepadAxisType::LeftStickX, value: -0.8 });
+        event_system.queue_event(GameEvent::GamepadAxis { axis: GamepadAxisType::LeftStickX, value: -0.9 });
+        event_system.queue_event(GameEvent::GamepadAxis { axis: GamepadAxisType::LeftStickX, value: 0.0 });
+
+        // the key is processed but only the actions are kept in the recording, the stick presses and releases once along with its analog values
+        let actions: Vec<_> = event_system.recording().events.iter().map(|framed_event| match framed_event.event {
+            GameEvent::Action { action, state } => (framed_event.frame, action, if matches!(state, ElementStateType::Pressed) { 1.0 } else { 0.0 }),
+            GameEvent::ActionValue { action, value } => (framed_event.frame, action, value),
+            _ => panic!("{:?} recorded", framed_event.event),
+        }).collect();
+        assert_eq!(actions, vec![
+            (3, InputAction::Accelerate, 1.0),
+            (3, InputAction::Reverse, 1.0),
+            (3, InputAction::Reverse, 0.8),
+            (3, InputAction::Reverse, 0.9),
+            (3, InputAction::Reverse, 0.0),
+            (3, InputAction::Reverse, 0.0),
+        ]);
+        assert!(matches!(event_system.events[0], GameEvent::KeyboardInput { .. }));
+    }
+
+    #[test]
+    fn test_upgrade_key_events() {
+        let key = |key_code| FramedEvent { frame: 1, event: GameEvent::KeyboardInput { key_code, state: ElementStateType::Pressed } };
+        let mut recording = EventRecording {
+            events: vec![key(KeyCodeType::KeyZ), key(KeyCodeType::Escape)],
+            seed: None,
+            level_file: None,
+            time: None,
+            split_times: vec![],
+            respawn_at_checkpoint: false,
+        };
+        recording.upgrade_key_events();
+        assert!(matches!(recording.events[0].event, GameEvent::Action { action: InputAction::Reverse, .. }));
+        assert!(matches!(recording.events[1].event, GameEvent::KeyboardInput { key_code: KeyCodeType::Escape, .. }));
+    }
+
+    #[test]
+    fn test_replay_respawn_at_checkpoint() {
+        let mut event_system = EventSystem::new();
+        event_system.start_recording();
+        let mut recording = event_system.recording();
+        recording.respawn_at_checkpoint = true;
+
+        let path = std::env::temp_dir().join("planck_test_replay_respawn.json");
+        let path = path.to_str().unwrap();
+        recording.save(path).unwrap();
+
+        // the replay puts the car back at checkpoints whatever the local settings say
+        let mut replay_system = EventSystem::new();
+        replay_system.load_replay(path).unwrap();
+        assert!(replay_system.replay_respawn_at_checkpoint());
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,131 @@
+#[cfg(feature = "gamepad")]
+use gilrs::{Axis, Button, EventType, Gilrs};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gamepad")]
+use crate::engine::app::event_system::ElementStateType;
+use crate::engine::app::event_system::GameEvent;
+
+/// Serializable gamepad button, named by position (South is A on an xbox pad, cross on a playstation one)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButtonType {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    Unknown,
+}
+
+#[cfg(feature = "gamepad")]
+impl From<Button> for GamepadButtonType {
+    fn from(button: Button) -> Self {
+        match button {
+            Button::South => GamepadButtonType::South,
+            Button::East => GamepadButtonType::East,
+            Button::North => GamepadButtonType::North,
+            Button::West => GamepadButtonType::West,
+            Button::LeftTrigger => GamepadButtonType::LeftBumper,
+            Button::RightTrigger => GamepadButtonType::RightBumper,
+            Button::LeftTrigger2 => GamepadButtonType::LeftTrigger,
+            Button::RightTrigger2 => GamepadButtonType::RightTrigger,
+            Button::Select => GamepadButtonType::Select,
+            Button::Start => GamepadButtonType::Start,
+            Button::DPadUp => GamepadButtonType::DPadUp,
+            Button::DPadDown => GamepadButtonType::DPadDown,
+            Button::DPadLeft => GamepadButtonType::DPadLeft,
+            Button::DPadRight => GamepadButtonType::DPadRight,
+            _ => GamepadButtonType::Unknown,
+        }
+    }
+}
+
+/// Serializable gamepad axis, from -1 to 1 with up and right positive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxisType {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    Unknown,
+}
+
+#[cfg(feature = "gamepad")]
+impl From<Axis> for GamepadAxisType {
+    fn from(axis: Axis) -> Self {
+        match axis {
+            Axis::LeftStickX => GamepadAxisType::LeftStickX,
+            Axis::LeftStickY => GamepadAxisType::LeftStickY,
+            Axis::RightStickX => GamepadAxisType::RightStickX,
+            Axis::RightStickY => GamepadAxisType::RightStickY,
+            _ => GamepadAxisType::Unknown,
+        }
+    }
+}
+
+/// Polls any connected gamepads, turning their input into GameEvents. Input from all pads is merged.
+/// Without the gamepad feature this never has any events.
+pub struct Gamepad {
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<Gilrs>, // None if gamepads aren't supported on this platform
+}
+
+impl Gamepad {
+    #[cfg(not(feature = "gamepad"))]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn poll(&mut self) -> Vec<GameEvent> {
+        vec![]
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn new() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                eprintln!("Gamepads unavailable: {}", e);
+                None
+            }
+        };
+        Self { gilrs }
+    }
+
+    /// Events since the last poll
+    #[cfg(feature = "gamepad")]
+    pub fn poll(&mut self) -> Vec<GameEvent> {
+        let mut events = vec![];
+        let Some(gilrs) = &mut self.gilrs else {
+            return events;
+        };
+
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) => events.push(GameEvent::GamepadButton { button: button.into(), state: ElementStateType::Pressed }),
+                EventType::ButtonReleased(button, _) => events.push(GameEvent::GamepadButton { button: button.into(), state: ElementStateType::Released }),
+                // the other buttons are only ever fully pressed or not
+                EventType::ButtonChanged(button @ (Button::LeftTrigger2 | Button::RightTrigger2), value, _) => events.push(GameEvent::GamepadButtonValue { button: button.into(), value }),
+                EventType::AxisChanged(axis, value, _) => events.push(GameEvent::GamepadAxis { axis: axis.into(), value }),
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+impl Default for Gamepad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+
+use crate::engine::app::{event_system::KeyCodeType, gamepad::{GamepadAxisType, GamepadButtonType}};
+
+// how far a stick has to be pushed to count as pressed
+pub const AXIS_THRESHOLD: f32 = 0.5;
+
+// analog values closer to rest than this are taken as zero, so a stick that doesn't quite centre doesn't creep the car along
+pub const ANALOG_DEAD_ZONE: f32 = 0.1;
+
+/// What the player wants to do, whichever key or button they used to do it.
+/// Input is turned into these by the InputBindings, and recordings store them so they still replay after rebinding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    Accelerate, // turn the wheels clockwise, driving right
+    Reverse,
+    Brake,
+    Restart,
+    Pause, // also backs out of menus
+    MenuUp,
+    MenuDown,
+    MenuSelect,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 8] = [
+        InputAction::Accelerate,
+        InputAction::Reverse,
+        InputAction::Brake,
+        InputAction::Restart,
+        InputAction::Pause,
+        InputAction::MenuUp,
+        InputAction::MenuDown,
+        InputAction::MenuSelect,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputAction::Accelerate => "Accelerate",
+            InputAction::Reverse => "Reverse",
+            InputAction::Brake => "Brake",
+            InputAction::Restart => "Restart",
+            InputAction::Pause => "Pause",
+            InputAction::MenuUp => "Menu Up",
+            InputAction::MenuDown => "Menu Down",
+            InputAction::MenuSelect => "Menu Select",
+        }
+    }
+}
+
+/// One way along a gamepad axis, pressed while the stick is pushed past AXIS_THRESHOLD
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AxisDirection {
+    pub axis: GamepadAxisType,
+    pub positive: bool,
+}
+
+impl AxisDirection {
+    pub fn new(axis: GamepadAxisType, positive: bool) -> Self {
+        Self { axis, positive }
+    }
+
+    pub fn is_pressed(&self, value: f32) -> bool {
+        self.analog_value(value) > AXIS_THRESHOLD
+    }
+
+    /// How far the axis is pushed this way, 0 to 1
+    pub fn analog_value(&self, value: f32) -> f32 {
+        analog_value(if self.positive { value } else { -value })
+    }
+}
+
+/// Clamp to 0 to 1 and apply the dead zone
+pub fn analog_value(value: f32) -> f32 {
+    if value < ANALOG_DEAD_ZONE {
+        return 0.0;
+    }
+    value.min(1.0)
+}
+
+/// Which keys, gamepad buttons and stick directions do each action. An input can be bound to more than one action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputBindings {
+    pub keys: Vec<(KeyCodeType, InputAction)>,
+    pub gamepad_buttons: Vec<(GamepadButtonType, InputAction)>,
+    pub gamepad_axes: Vec<(AxisDirection, InputAction)>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            keys: vec![
+                (KeyCodeType::KeyX, InputAction::Accelerate),
+                (KeyCodeType::KeyZ, InputAction::Reverse),
+                (KeyCodeType::KeyC, InputAction::Brake),
+                (KeyCodeType::KeyR, InputAction::Restart),
+                (KeyCodeType::Escape, InputAction::Pause),
+                (KeyCodeType::ArrowUp, InputAction::MenuUp),
+                (KeyCodeType::KeyW, InputAction::MenuUp),
+                (KeyCodeType::ArrowDown, InputAction::MenuDown),
+                (KeyCodeType::KeyS, InputAction::MenuDown),
+                (KeyCodeType::Enter, InputAction::MenuSelect),
+                (KeyCodeType::Space, InputAction::MenuSelect),
+            ],
+            gamepad_buttons: vec![
+                (GamepadButtonType::RightTrigger, InputAction::Accelerate),
+                (GamepadButtonType::LeftTrigger, InputAction::Reverse),
+                (GamepadButtonType::West, InputAction::Brake),
+                (GamepadButtonType::North, InputAction::Restart),
+                (GamepadButtonType::Start, InputAction::Pause),
+                (GamepadButtonType::East, InputAction::Pause),
+                (GamepadButtonType::DPadUp, InputAction::MenuUp),
+                (GamepadButtonType::DPadDown, InputAction::MenuDown),
+                (GamepadButtonType::South, InputAction::MenuSelect),
+            ],
+            gamepad_axes: vec![
+                (AxisDirection::new(GamepadAxisType::LeftStickX, true), InputAction::Accelerate),
+                (AxisDirection::new(GamepadAxisType::LeftStickX, false), InputAction::Reverse),
+                (AxisDirection::new(GamepadAxisType::LeftStickY, true), InputAction::MenuUp),
+                (AxisDirection::new(GamepadAxisType::LeftStickY, false), InputAction::MenuDown),
+            ],
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn actions_for_key(&self, key: KeyCodeType) -> impl Iterator<Item = InputAction> + '_ {
+        self.keys.iter().filter(move |(k, _)| *k == key).map(|(_, action)| *action)
+    }
+
+    pub fn actions_for_button(&self, button: GamepadButtonType) -> impl Iterator<Item = InputAction> + '_ {
+        self.gamepad_buttons.iter().filter(move |(b, _)| *b == button).map(|(_, action)| *action)
+    }
+
+    pub fn keys_for(&self, action: InputAction) -> Vec<KeyCodeType> {
+        self.keys.iter().filter(|(_, a)| *a == action).map(|(key, _)| *key).collect()
+    }
+
+    pub fn buttons_for(&self, action: InputAction) -> Vec<GamepadButtonType> {
+        self.gamepad_buttons.iter().filter(|(_, a)| *a == action).map(|(button, _)| *button).collect()
+    }
+
+    /// Make key the only key for the action, taking it off anything else it was bound to
+    pub fn rebind_key(&mut self, action: InputAction, key: KeyCodeType) {
+        self.keys.retain(|(k, a)| *a != action && *k != key);
+        self.keys.push((key, action));
+    }
+
+    /// Make button the only gamepad button for the action, taking it off anything else it was bound to
+    pub fn rebind_button(&mut self, action: InputAction, button: GamepadButtonType) {
+        self.gamepad_buttons.retain(|(b, a)| *a != action && *b != button);
+        self.gamepad_buttons.push((button, action));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebind() {
+        let mut bindings = InputBindings::default();
+        assert_eq!(bindings.actions_for_key(KeyCodeType::KeyX).collect::<Vec<_>>(), vec![InputAction::Accelerate]);
+
+        // taking another action's key leaves that action without it
+        bindings.rebind_key(InputAction::Accelerate, KeyCodeType::KeyZ);
+        assert_eq!(bindings.keys_for(InputAction::Accelerate), vec![KeyCodeType::KeyZ]);
+        assert!(bindings.keys_for(InputAction::Reverse).is_empty());
+        assert_eq!(bindings.actions_for_key(KeyCodeType::KeyX).count(), 0);
+
+        // actions with more than one key end up with just the new one
+        bindings.rebind_button(InputAction::Pause, GamepadButtonType::Select);
+        assert_eq!(bindings.buttons_for(InputAction::Pause), vec![GamepadButtonType::Select]);
+    }
+
+    #[test]
+    fn test_axis_direction() {
+        let left = AxisDirection::new(GamepadAxisType::LeftStickX, false);
+        assert!(left.is_pressed(-0.9));
+        assert!(!left.is_pressed(-0.2));
+        assert!(!left.is_pressed(0.9));
+        assert_eq!(left.analog_value(-0.05), 0.0);
+        assert_eq!(left.analog_value(-0.3), 0.3);
+        assert_eq!(left.analog_value(-1.2), 1.0);
+    }
+}
\ No newline at end of file
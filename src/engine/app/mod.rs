@@ -3,6 +3,8 @@ pub mod camera;
 pub mod graphics_helper;
 pub mod window_helper;
 pub mod event_system;
+pub mod gamepad;
+pub mod input_action;
 pub mod ui_helper;
 pub mod context;
 pub mod game_loop;
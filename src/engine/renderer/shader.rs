@@ -20,6 +20,7 @@ pub struct ShaderBuilder<'a> {
     device: &'a wgpu::Device,
     mappings: HashMap<BindGroupType, u32>,
     bind_groups_map: HashMap<u32, (BindGroupLayout, BindGroup)>,
+    blend: wgpu::BlendState,
 }
 
 impl<'a> ShaderBuilder<'a> {
@@ -45,6 +46,7 @@ impl<'a> ShaderBuilder<'a> {
             shader_module,
             mappings,
             bind_groups_map: HashMap::new(),
+            blend: wgpu::BlendState::REPLACE,
         }
     }
 
@@ -138,6 +140,12 @@ impl<'a> ShaderBuilder<'a> {
         self
     }
 
+    /// How the fragment colour is combined with what is already drawn, e.g. wgpu::BlendState::ALPHA_BLENDING for translucency
+    pub fn blend(&mut self, blend: wgpu::BlendState) -> &mut Self {
+        self.blend = blend;
+        self
+    }
+
     pub fn camera(&mut self, camera: &Camera) -> &mut Self {
         let group_index = *self.mappings.get(&BindGroupType::Camera).expect("Shader does not have a 'camera' bind group");
 
@@ -228,10 +236,7 @@ impl<'a> ShaderBuilder<'a> {
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent::REPLACE,
-                        alpha: wgpu::BlendComponent::REPLACE,
-                    }),
+                    blend: Some(self.blend),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
                 compilation_options: Default::default(),
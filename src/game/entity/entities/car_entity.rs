@@ -1,8 +1,99 @@
-use crate::{core::math::{unit_conversions::cm_to_m, vec2::Vec2, vec4::Vec4}, engine::app::event_system::KeyCodeType, game::{entity::{entities::finish_entity::FinishEntitySystem, entity_system::UpdateContext}}, simulation::{constraints::{spring_constraint::SpringConstraint, volume_constraint::VolumeConstraint}, particles::{particle::Particle, particle_manipulator::ParticleManipulator, particle_vec::{ParticleHandle, ParticleVec}, shape_builder::{adjacent_sticks::AdjacentSticks, circle::{Circle, SpaceDistribution}, shape_builder::ShapeBuilder}, simulation::Simulation}}};
+use crate::{core::math::{unit_conversions::cm_to_m, vec2::Vec2, vec4::Vec4}, engine::app::input_action::InputAction, game::entity::entity_system::UpdateContext, simulation::{constraints::{spring_constraint::SpringConstraint, volume_constraint::VolumeConstraint}, particles::{particle::Particle, particle_manipulator::ParticleManipulator, particle_vec::{ParticleHandle, ParticleRemap, ParticleVec}, shape_builder::{adjacent_sticks::AdjacentSticks, circle::{Circle, SpaceDistribution}, shape_builder::ShapeBuilder}, simulation::Simulation}}};
 
+// the motor gives PEAK_TORQUE (in Nm) up to PEAK_TORQUE_SPEED, then tails off to nothing at MAX_WHEEL_SPEED (both in rad/s)
+const PEAK_TORQUE: f32 = 0.05;
+const PEAK_TORQUE_SPEED: f32 = 30.0;
+const MAX_WHEEL_SPEED: f32 = 60.0;
+
+// braking resists the wheel spinning, easing off below BRAKE_FULL_SPEED so it stops the wheel rather than flicking it back the other way
+const BRAKE_TORQUE: f32 = 0.1;
+const BRAKE_FULL_SPEED: f32 = 2.0;
+
+// time (in seconds) for key input to take the throttle from nothing to full, and from full back to nothing
+const THROTTLE_RAMP_UP_TIME: f32 = 0.1;
+const THROTTLE_RAMP_DOWN_TIME: f32 = 0.05;
+
+/// Torque at full throttle for how fast the wheel is already spinning the way it is being driven (negative if it is spinning the other way)
+fn torque_curve(wheel_speed: f32) -> f32 {
+    if wheel_speed <= PEAK_TORQUE_SPEED {
+        return PEAK_TORQUE;
+    }
+    PEAK_TORQUE * (1.0 - (wheel_speed - PEAK_TORQUE_SPEED) / (MAX_WHEEL_SPEED - PEAK_TORQUE_SPEED)).max(0.0)
+}
+
+/// How hard the car is being driven, from -1 (full reverse) to 1 (full ahead).
+/// Keys ramp the throttle up and down, analog input (a trigger or stick) sets it directly and takes over while it is held.
+#[derive(Clone, Default)]
+pub struct Throttle {
+    is_accelerate_pressed: bool,
+    is_reverse_pressed: bool,
+    is_brake_pressed: bool,
+    analog_accelerate: f32, // 0 to 1
+    analog_reverse: f32,
+    analog_brake: f32,
+    value: f32,
+}
+
+impl Throttle {
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// 0 to 1
+    pub fn brake(&self) -> f32 {
+        if self.is_brake_pressed { 1.0 } else { self.analog_brake }
+    }
+
+    /// Where the input wants the throttle to be
+    pub fn target(&self) -> f32 {
+        let analog = self.analog_accelerate - self.analog_reverse;
+        if analog != 0.0 {
+            return analog;
+        }
+        (self.is_accelerate_pressed as i32 - self.is_reverse_pressed as i32) as f32
+    }
+
+    pub fn handle_action(&mut self, action: InputAction, is_pressed: bool) -> bool {
+        match action {
+            InputAction::Accelerate => self.is_accelerate_pressed = is_pressed,
+            InputAction::Reverse => self.is_reverse_pressed = is_pressed,
+            InputAction::Brake => self.is_brake_pressed = is_pressed,
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn handle_action_value(&mut self, action: InputAction, value: f32) -> bool {
+        let value = value.clamp(0.0, 1.0);
+        match action {
+            InputAction::Accelerate => self.analog_accelerate = value,
+            InputAction::Reverse => self.analog_reverse = value,
+            InputAction::Brake => self.analog_brake = value,
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn update(&mut self, time_delta: f32) {
+        let target = self.target();
+        if self.analog_accelerate != 0.0 || self.analog_reverse != 0.0 {
+            self.value = target;
+            return;
+        }
+
+        // ramp up while heading further from zero the same way, otherwise ramp down (through zero when changing direction)
+        let ramping_up = target != 0.0 && self.value * target >= 0.0;
+        let rate = if ramping_up { 1.0 / THROTTLE_RAMP_UP_TIME } else { 1.0 / THROTTLE_RAMP_DOWN_TIME };
+        let step = rate * time_delta;
+        self.value = if (target - self.value).abs() <= step { target } else { self.value + step * (target - self.value).signum() };
+    }
+}
+
+#[derive(Clone)]
 pub struct CarWheel {
     hub_particle_handle: ParticleHandle,
     surface_particle_handles: Vec<ParticleHandle>,
+    surface_offsets: Vec<Vec2>, // from the hub to each surface particle when the wheel was made
     spring_constraint_ids: Vec<usize>,
     volume_constraint_ids: Vec<usize>,
     surface_constraint_ids: Vec<usize>,
@@ -12,9 +103,8 @@ impl CarWheel {
     pub fn new(origin: Vec2, _particle_vec: &mut ParticleVec, sim: &mut Simulation) -> Self {
         let particle_mass = 1.0; //g_to_kg(10.0);
 
-        // wheel hub - this is on mask layer zero which is a special no collisions layer
+        // wheel hub - on the default layer like the tyre, so it still props the car up if the tyre gets squashed flat
         let hub_particle_handle = {
-            //let mask = 0x0;
             let particle_radius = cm_to_m(6.0);
             let mut builder = ShapeBuilder::from_particle_template(
                 Particle::default().set_mass(particle_mass).set_radius(particle_radius).set_colour(Vec4::GREEN).clone()
@@ -22,12 +112,11 @@ impl CarWheel {
             builder.add_particle(builder.create_particle().set_pos(origin).clone())
                 .create_in_simulation(sim);
 
-            builder.particle_handles.first().unwrap().clone()
+            *builder.particle_handles.first().unwrap()
         };
 
         // wheel surface
         let (surface_particle_handles, surface_constraint_ids) = {
-            //let mask = 0x1;
             //let divisions = 20;
             let circle_radius = cm_to_m(35.0); // around a typical car tyre size - 17-18" (once you account for particle radius)
             let particle_radius = cm_to_m(8.0);
@@ -55,35 +144,94 @@ impl CarWheel {
             for (_idx, surface_particle_handle) in surface_particle_handles.iter().enumerate() {
                 let dist = (sim.particles[hub_particle_handle].pos - sim.particles[*surface_particle_handle].pos).magnitude(); 
                 //sim.add_distance_constraint(DistanceConstraint::new(dist, hub_particle_handle, *surface_particle_handle, false));
-                let id = sim.add_spring_constraint(SpringConstraint::new(dist, 1000.0, hub_particle_handle, *surface_particle_handle, false));
+                let id = sim.add_spring_constraint(SpringConstraint::new(dist, 1000.0, hub_particle_handle.index(), surface_particle_handle.index(), false));
                 spring_constraint_ids.push(id);
             }
 
             // Add volume constraint
             let compliance = 0.00001; // Tunable parameter
-            let id = sim.add_volume_constraint(VolumeConstraint::new(compliance, surface_particle_handles.clone(), &sim.particles));
+            let id = sim.add_volume_constraint(VolumeConstraint::new(compliance, surface_particle_handles.iter().map(|h| h.index()).collect(), &sim.particles));
             volume_constraint_ids.push(id);
         }
 
+        let surface_offsets = surface_particle_handles.iter().map(|h| sim.particles[*h].pos - origin).collect();
+
         Self {
             hub_particle_handle,
             surface_particle_handles,
+            surface_offsets,
             spring_constraint_ids,
             volume_constraint_ids,
             surface_constraint_ids,
         }
     }
 
-    fn rotate(&mut self, direction: f32, particle_vec: &mut ParticleVec) {
+    /// How fast the surface is spinning around the hub, in rad/s counter-clockwise
+    fn angular_velocity(&self, particle_vec: &ParticleVec) -> f32 {
+        if self.surface_particle_handles.is_empty() {
+            return 0.0;
+        }
+
+        let hub_particle = particle_vec[self.hub_particle_handle];
+        let mut angular_velocity = 0.0;
+        for surface_particle_handle in self.surface_particle_handles.iter() {
+            let particle = &particle_vec[*surface_particle_handle];
+            let delta = particle.pos - hub_particle.pos;
+            let vel = particle.vel - hub_particle.vel;
+            angular_velocity += (delta.x * vel.y - delta.y * vel.x) / delta.dot(delta);
+        }
+        angular_velocity / self.surface_particle_handles.len() as f32
+    }
+
+    /// Drive the wheel with the motor at throttle (positive is clockwise, driving right) and hold it back with the brake
+    fn rotate(&mut self, throttle: f32, brake: f32, particle_vec: &mut ParticleVec) {
         let hub_particle = particle_vec[self.hub_particle_handle];
         let centre = hub_particle.pos;
-        let torque = 0.05; // Nm
+        let angular_velocity = self.angular_velocity(particle_vec);
+
+        // torque is counter-clockwise, the opposite way to the throttle
+        let mut torque = 0.0; // Nm
+        if throttle != 0.0 {
+            let wheel_speed = -angular_velocity * throttle.signum();
+            torque -= throttle * torque_curve(wheel_speed);
+        }
+        if brake > 0.0 {
+            torque -= angular_velocity.signum() * BRAKE_TORQUE * brake * (angular_velocity.abs() / BRAKE_FULL_SPEED).min(1.0);
+        }
+        if torque == 0.0 {
+            return;
+        }
 
         let particle_manipulator = ParticleManipulator::new();
 
         // todo: instead of rotating the points to the wheel tangent. Try moving points towards the next point in the wheel (or where it would be in a perfect wheel).
         // this will stop the wheels expanding outwards as you accelerate
-        particle_manipulator.add_torque_around_point(particle_vec, &self.surface_particle_handles, centre, torque * direction);
+        particle_manipulator.add_torque_around_point(particle_vec, &self.surface_particle_handles, centre, torque);
+    }
+
+    // A removed hub is left stale, so using it will be caught by ParticleVec::is_valid
+    fn remap_particles(&mut self, remap: &ParticleRemap) {
+        if let Some(handle) = remap.handle(self.hub_particle_handle) {
+            self.hub_particle_handle = handle;
+        }
+        // drop the offsets of any removed surface particles along with their handles
+        let (handles, offsets) = self.surface_particle_handles.iter().zip(self.surface_offsets.iter())
+            .filter_map(|(h, offset)| remap.handle(*h).map(|h| (h, *offset)))
+            .unzip();
+        self.surface_particle_handles = handles;
+        self.surface_offsets = offsets;
+    }
+
+    // Put the wheel back in its original shape around hub_pos, at rest
+    fn respawn(&self, particle_vec: &mut ParticleVec, hub_pos: Vec2) {
+        let handles = std::iter::once(self.hub_particle_handle).chain(self.surface_particle_handles.iter().copied());
+        let offsets = std::iter::once(Vec2::new(0.0, 0.0)).chain(self.surface_offsets.iter().copied());
+        for (handle, offset) in handles.zip(offsets) {
+            let particle = &mut particle_vec[handle];
+            particle.pos = hub_pos + offset;
+            particle.pos_guess = particle.pos;
+            particle.vel = Vec2::new(0.0, 0.0);
+        }
     }
 
     fn disable_constraints(&mut self, sim: &mut Simulation) {
@@ -101,10 +249,12 @@ impl CarWheel {
 
 const NUM_WHEELS: usize = 2;
 
+#[derive(Clone)]
 pub struct CarEntity {
     pub wheels: [CarWheel; NUM_WHEELS],
-    is_left_pressed: bool,
-    is_right_pressed: bool,
+    wheel_offsets: [Vec2; NUM_WHEELS], // from the origin to each hub
+    pub origin: Vec2, // where the car was made
+    pub throttle: Throttle,
     axle_constraint_id: usize,
     pub game_ended: bool,
 }
@@ -116,28 +266,32 @@ impl CarEntity {
         let wheel_spacing = 1.2; // metres - 
         let half_wheel_spacing = wheel_spacing * 0.5; // metres
 
-        let wheel_1 = CarWheel::new(origin + Vec2::new(half_wheel_spacing, 0.0), particle_vec, sim);
-        let wheel_2 = CarWheel::new(origin - Vec2::new(half_wheel_spacing, 0.0), particle_vec, sim);
+        let wheel_offsets = [Vec2::new(half_wheel_spacing, 0.0), Vec2::new(-half_wheel_spacing, 0.0)];
+        let wheel_1 = CarWheel::new(origin + wheel_offsets[0], particle_vec, sim);
+        let wheel_2 = CarWheel::new(origin + wheel_offsets[1], particle_vec, sim);
 
         // Axle constraint to connect the two wheel hubs
         let axle_constraint_id = {
             let dist = (sim.particles[wheel_1.hub_particle_handle].pos - sim.particles[wheel_2.hub_particle_handle].pos).magnitude(); 
             //sim.add_distance_constraint(DistanceConstraint::new(dist, wheel_1.hub_particle_handle, wheel_2.hub_particle_handle, false));
-            sim.add_spring_constraint(SpringConstraint::new(dist, 2000.0, wheel_1.hub_particle_handle, wheel_2.hub_particle_handle, false))
+            sim.add_spring_constraint(SpringConstraint::new(dist, 2000.0, wheel_1.hub_particle_handle.index(), wheel_2.hub_particle_handle.index(), false))
         };
 
         Self {
             wheels: [wheel_1, wheel_2],
-            is_left_pressed: false,
-            is_right_pressed: false,
+            wheel_offsets,
+            origin,
+            throttle: Throttle::default(),
             axle_constraint_id,
             game_ended: false,
         }
     }
 
-    fn rotate_wheels(&mut self, direction: f32, particle_vec: &mut ParticleVec) {
+    fn rotate_wheels(&mut self, particle_vec: &mut ParticleVec) {
+        let throttle = self.throttle.value();
+        let brake = self.throttle.brake();
         for wheel in self.wheels.iter_mut() { 
-            wheel.rotate(direction, particle_vec);
+            wheel.rotate(throttle, brake, particle_vec);
         }
     }
 
@@ -153,66 +307,64 @@ impl CarEntity {
         pos
     }
 
-    fn update(&mut self, context: &mut UpdateContext, finish_entity_system: &FinishEntitySystem) {
+    /// The particles that set off triggers, e.g. the finish line: the surface of the wheels
+    pub fn trigger_particle_handles(&self) -> Vec<ParticleHandle> {
+        self.wheels.iter().flat_map(|wheel| wheel.surface_particle_handles.iter().copied()).collect()
+    }
+
+    /// Is the player trying to drive?
+    pub fn is_driving(&self) -> bool {
+        self.throttle.target() != 0.0
+    }
+
+    /// Put the car back together at position, at rest and the right way up
+    pub fn respawn(&mut self, particle_vec: &mut ParticleVec, position: Vec2) {
+        for (wheel, offset) in self.wheels.iter().zip(self.wheel_offsets.iter()) {
+            wheel.respawn(particle_vec, position + *offset);
+        }
+    }
+
+    /// Stop the car at the finish line
+    pub fn finish(&mut self, context: &mut UpdateContext) {
         if self.game_ended {
             return;
         }
+        self.game_ended = true;
+        println!("Game Finished! Time: {:.2}s", context.total_time);
 
-        // Apply input to wheels
-        if self.is_left_pressed {
-            self.rotate_wheels(1.0, &mut context.sim.particles); // ccw
+        // Break the car apart!
+        context.sim.spring_constraints.0[self.axle_constraint_id].enabled = false;
+        for wheel in self.wheels.iter_mut() {
+            wheel.disable_constraints(context.sim);
         }
-        if self.is_right_pressed {
-            self.rotate_wheels(-1.0, &mut context.sim.particles); // clockwise
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) {
+        if self.game_ended {
+            return;
         }
 
+        // Apply input to wheels
+        self.throttle.update(context.time_delta);
+        self.rotate_wheels(&mut context.sim.particles);
+
         // Update the camera to follow the car
         let look_at_pos = self.get_camera_look_at_position(&mut context.sim.particles);
         context.camera.target = cgmath::Point3::new(look_at_pos.x, look_at_pos.y, 0.0);
+    }
 
-        // Check for finish 
-        // - todo: check against wheel hub centres so we only need to check 2 points instead of every wheel surface
-        //      and/or check against the simulation spatial partition.
-        for finish_entity in &finish_entity_system.entities {
-            for wheel in &self.wheels {
-                for particle_handle in &wheel.surface_particle_handles {
-                    let particle = &context.sim.particles[*particle_handle];
-                    if particle.pos.x >= finish_entity.aabb.min.x && particle.pos.x <= finish_entity.aabb.max.x &&
-                       particle.pos.y >= finish_entity.aabb.min.y && particle.pos.y <= finish_entity.aabb.max.y {
-                        self.game_ended = true;
-                    }
-                }
-            }
-
-            if self.game_ended {
-                println!("Game Finished! Time: {:.2}s", context.total_time);
-
-                // Break the car apart!
-                context.sim.spring_constraints.0[self.axle_constraint_id].enabled = false;
-                for wheel in self.wheels.iter_mut() {
-                    wheel.disable_constraints(context.sim);
-                }
-            }
-        }
+    fn handle_action(&mut self, action: InputAction, is_pressed: bool) -> bool {
+        self.throttle.handle_action(action, is_pressed)
     }
 
-    fn handle_key(&mut self, key: KeyCodeType, is_pressed: bool) -> bool {
-        match key {
-            KeyCodeType::KeyZ => {
-                self.is_left_pressed = is_pressed;
-                true
-            }
-            KeyCodeType::KeyX => {
-                self.is_right_pressed = is_pressed;
-                true
-            }
-            _ => false,
-        }
+    fn handle_action_value(&mut self, action: InputAction, value: f32) -> bool {
+        self.throttle.handle_action_value(action, value)
     }
 }
 
 
 
+#[derive(Clone)]
 pub struct CarEntitySystem(pub Vec<CarEntity>);
 
 impl CarEntitySystem {
@@ -224,15 +376,101 @@ impl CarEntitySystem {
         self.0.push(c);
     }
 
-    pub fn update(&mut self, context: &mut UpdateContext, finish_entity_system: &FinishEntitySystem) {
+    pub fn update(&mut self, context: &mut UpdateContext) {
         for e in self.0.iter_mut() {
-            e.update(context, finish_entity_system);
+            e.update(context);
         }
     }
 
-    pub fn handle_key(&mut self, key: KeyCodeType, is_pressed: bool) {
+    /// Every particle of every car
+    pub fn particle_handles(&self) -> Vec<ParticleHandle> {
+        self.0.iter().flat_map(|e| e.wheels.iter().flat_map(|wheel| std::iter::once(wheel.hub_particle_handle).chain(wheel.surface_particle_handles.iter().copied()))).collect()
+    }
+
+    /// Particle groups for the TriggerEntitySystem, one for each car
+    pub fn trigger_particle_groups(&self) -> Vec<Vec<ParticleHandle>> {
+        self.0.iter().map(|e| e.trigger_particle_handles()).collect()
+    }
+
+    pub fn handle_action(&mut self, action: InputAction, is_pressed: bool) {
+        for e in self.0.iter_mut() {
+            e.handle_action(action, is_pressed);
+        }
+    }
+
+    pub fn handle_action_value(&mut self, action: InputAction, value: f32) {
+        for e in self.0.iter_mut() {
+            e.handle_action_value(action, value);
+        }
+    }
+
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
         for e in self.0.iter_mut() {
-            e.handle_key(key, is_pressed);
+            for wheel in e.wheels.iter_mut() {
+                wheel.remap_particles(remap);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+    use super::*;
+
+    #[test]
+    fn test_throttle_ramp() {
+        let time_delta = 0.005;
+        let mut throttle = Throttle::default();
+        throttle.handle_action(InputAction::Accelerate, true);
+        for _ in 0..10 {
+            throttle.update(time_delta);
+        }
+        assert!((throttle.value() - 0.5).abs() < 0.001, "{}", throttle.value());
+
+        // reversing ramps down through zero first, which is quicker
+        throttle.handle_action(InputAction::Accelerate, false);
+        throttle.handle_action(InputAction::Reverse, true);
+        for _ in 0..5 {
+            throttle.update(time_delta);
+        }
+        assert!(throttle.value().abs() < 0.001, "{}", throttle.value());
+        for _ in 0..25 {
+            throttle.update(time_delta);
+        }
+        assert_eq!(throttle.value(), -1.0);
+
+        // analog input sets the throttle straight away
+        throttle.handle_action_value(InputAction::Accelerate, 0.4);
+        throttle.update(time_delta);
+        assert_eq!(throttle.value(), 0.4);
+    }
+
+    #[test]
+    fn test_torque_curve() {
+        assert_eq!(torque_curve(-10.0), PEAK_TORQUE);
+        assert_eq!(torque_curve(PEAK_TORQUE_SPEED), PEAK_TORQUE);
+        assert!((torque_curve((PEAK_TORQUE_SPEED + MAX_WHEEL_SPEED) * 0.5) - PEAK_TORQUE * 0.5).abs() < 0.0001);
+        assert_eq!(torque_curve(MAX_WHEEL_SPEED * 2.0), 0.0);
+    }
+
+    #[test]
+    fn test_brake_stops_wheel() {
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        let mut particle_vec = ParticleVec::new();
+        let mut wheel = CarWheel::new(Vec2::new(0.0, 0.0), &mut particle_vec, &mut sim);
+
+        // full throttle spins the wheel clockwise
+        wheel.rotate(1.0, 0.0, &mut sim.particles);
+        let spin = wheel.angular_velocity(&sim.particles);
+        assert!(spin < 0.0, "{}", spin);
+
+        // the brake slows it to a stop without spinning it back the other way
+        for _ in 0..20 {
+            wheel.rotate(0.0, 1.0, &mut sim.particles);
+            assert!(wheel.angular_velocity(&sim.particles) <= 0.0);
         }
+        assert!(wheel.angular_velocity(&sim.particles).abs() < spin.abs() * 0.01);
     }
 }
@@ -0,0 +1,163 @@
+use crate::{core::math::vec2::Vec2, game::entity::{entities::{car_entity::CarEntitySystem, trigger_entity::{TriggerEntitySystem, TriggerEventType}}, entity_system::UpdateContext}};
+
+/// How far below the lowest point of the level the car can fall before it is put back on the track, or the LevelChecker gives up on it
+pub const FALL_OUT_DISTANCE: f32 = 10.0;
+
+// if the car is driven but doesn't get this far (in m) in STUCK_TIME (in seconds) it is stuck
+const STUCK_DISTANCE: f32 = 0.5;
+const STUCK_TIME: f32 = 5.0;
+
+#[derive(Clone)]
+pub struct CheckpointEntity {
+    pub trigger: usize, // in the TriggerEntitySystem
+    pub respawn_position: Vec2, // where the car is put back if it falls out of the level after passing this checkpoint
+    pub split_time: Option<f32>, // total time when the car first passed through
+}
+
+impl CheckpointEntity {
+    pub fn new(trigger: usize, respawn_position: Vec2) -> Self {
+        Self {
+            trigger,
+            respawn_position,
+            split_time: None,
+        }
+    }
+}
+
+// how long a car has been driving without getting anywhere
+#[derive(Clone, Copy)]
+struct StuckTimer {
+    position: Vec2,
+    time: f32,
+}
+
+/// Checkpoints along the level, in the order they are built. Records split times as the car passes through them and,
+/// if respawn is set, puts the car back at the last one it passed when it falls out of the level or gets stuck.
+#[derive(Clone)]
+pub struct CheckpointEntitySystem {
+    pub entities: Vec<CheckpointEntity>,
+    pub respawn: bool,
+    pub level_bottom: f32, // lowest point of the level, kept up to date by the LevelBuilder
+    last_reached: Option<usize>,
+    stuck_timers: Vec<StuckTimer>, // one for each car
+}
+
+impl CheckpointEntitySystem {
+    pub fn new() -> Self {
+        Self {
+            entities: vec![],
+            respawn: false,
+            level_bottom: f32::MAX,
+            last_reached: None,
+            stuck_timers: vec![],
+        }
+    }
+
+    pub fn push(&mut self, entity: CheckpointEntity) {
+        self.entities.push(entity);
+    }
+
+    /// Split time of each checkpoint, None for any not reached yet
+    pub fn split_times(&self) -> Vec<Option<f32>> {
+        self.entities.iter().map(|e| e.split_time).collect()
+    }
+
+    /// Trigger groups are the cars, see CarEntitySystem::trigger_particle_groups
+    pub fn update(&mut self, context: &mut UpdateContext, trigger_entity_system: &TriggerEntitySystem, car_entity_system: &mut CarEntitySystem) {
+        for (ci, checkpoint) in self.entities.iter_mut().enumerate() {
+            for event in trigger_entity_system.events_for(checkpoint.trigger) {
+                if event.event_type == TriggerEventType::Entered && checkpoint.split_time.is_none() && !car_entity_system.0[event.group].game_ended {
+                    checkpoint.split_time = Some(context.total_time);
+                    self.last_reached = Some(ci);
+                }
+            }
+        }
+
+        self.stuck_timers.resize(car_entity_system.0.len(), StuckTimer { position: Vec2::new(0.0, 0.0), time: 0.0 });
+
+        for (car, stuck_timer) in car_entity_system.0.iter_mut().zip(self.stuck_timers.iter_mut()) {
+            if car.game_ended {
+                continue;
+            }
+
+            if !self.respawn {
+                continue;
+            }
+
+            let pos = car.get_camera_look_at_position(&context.sim.particles);
+            if !car.is_driving() || (pos - stuck_timer.position).magnitude() > STUCK_DISTANCE {
+                stuck_timer.position = pos;
+                stuck_timer.time = 0.0;
+            } else {
+                stuck_timer.time += context.time_delta;
+            }
+
+            let fallen_out = pos.y < self.level_bottom - FALL_OUT_DISTANCE;
+            if fallen_out || stuck_timer.time > STUCK_TIME {
+                let respawn_position = match self.last_reached {
+                    Some(ci) => self.entities[ci].respawn_position,
+                    None => car.origin,
+                };
+                car.respawn(&mut context.sim.particles, respawn_position);
+                stuck_timer.position = respawn_position;
+                stuck_timer.time = 0.0;
+            }
+        }
+    }
+}
+
+impl Default for CheckpointEntitySystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+    use crate::{engine::app::{camera::Camera, input_action::InputAction}, game::{entity::{entities::car_entity::CarEntity, entity_system::EntitySystem}, level::{level_builder::LevelBuilder, level_file::{LevelBlock, LevelFile}}}, simulation::particles::{particle_vec::ParticleVec, simulation::Simulation}};
+    use super::*;
+
+    #[test]
+    fn test_split_times_and_respawn() {
+        let block = |operation: &str| LevelBlock { operation: operation.to_owned(), ..Default::default() };
+        let level_file = LevelFile { blocks: vec![block("SpawnOperation"), block("StraightLevelBlock"), block("StraightLevelBlock"), block("FinishOperation")], ..Default::default() };
+
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        LevelBuilder::default().generate_level_from_file(&level_file, &mut entity_system, &mut particle_vec, &mut sim).unwrap();
+        entity_system.checkpoint_entity_system.respawn = true;
+
+        // every block but the finish drops a checkpoint
+        assert_eq!(entity_system.checkpoint_entity_system.entities.len(), 3);
+
+        let car = CarEntity::new(&mut particle_vec, &mut sim, Vec2::new(0.0, 1.0));
+        entity_system.car_entity_system.push(car);
+        entity_system.handle_action(InputAction::Accelerate, true);
+
+        let mut camera = Camera::headless(1.0);
+        let time_delta = 0.005;
+        let mut i = 0;
+        while entity_system.checkpoint_entity_system.split_times()[1].is_none() && i < 3000 {
+            entity_system.step_simulation(&mut sim, time_delta);
+            entity_system.update(&mut particle_vec, &mut sim, &mut camera, time_delta, i as f32 * time_delta);
+            i += 1;
+        }
+
+        // checkpoints are passed in order
+        let split_times = entity_system.checkpoint_entity_system.split_times();
+        assert!(split_times[0].unwrap() < split_times[1].unwrap(), "{:?}", split_times);
+        assert!(split_times[2].is_none());
+
+        // drop the car out of the level and it comes back at the last checkpoint
+        let car_pos = entity_system.car_entity_system.0[0].get_camera_look_at_position(&sim.particles);
+        entity_system.car_entity_system.0[0].respawn(&mut sim.particles, car_pos - Vec2::new(0.0, 100.0));
+        entity_system.update(&mut particle_vec, &mut sim, &mut camera, time_delta, i as f32 * time_delta);
+
+        let car_pos = entity_system.car_entity_system.0[0].get_camera_look_at_position(&sim.particles);
+        let respawn_position = entity_system.checkpoint_entity_system.entities[1].respawn_position;
+        assert!((car_pos - respawn_position).magnitude() < 0.001, "{:?}", car_pos);
+    }
+}
@@ -1,17 +1,19 @@
-use crate::core::math::aabb2d::Aabb2d;
+use crate::game::entity::{entities::{car_entity::CarEntitySystem, trigger_entity::{TriggerEntitySystem, TriggerEventType}}, entity_system::UpdateContext};
 
+#[derive(Clone)]
 pub struct FinishEntity {
-    pub aabb: Aabb2d,
+    pub trigger: usize, // the finish line, in the TriggerEntitySystem
 }
 
 impl FinishEntity {
-    pub fn new(aabb: Aabb2d) -> Self {
+    pub fn new(trigger: usize) -> Self {
         Self {
-            aabb,
+            trigger,
         }
     }
 }
 
+#[derive(Clone)]
 pub struct FinishEntitySystem {
     pub entities: Vec<FinishEntity>,
 }
@@ -26,4 +28,15 @@ impl FinishEntitySystem {
     pub fn push(&mut self, entity: FinishEntity) {
         self.entities.push(entity);
     }
-}
+
+    /// Finish any car that crossed the line. Trigger groups are the cars, see CarEntitySystem::trigger_particle_groups
+    pub fn update(&mut self, context: &mut UpdateContext, trigger_entity_system: &TriggerEntitySystem, car_entity_system: &mut CarEntitySystem) {
+        for entity in &self.entities {
+            for event in trigger_entity_system.events_for(entity.trigger) {
+                if event.event_type == TriggerEventType::Entered {
+                    car_entity_system.0[event.group].finish(context);
+                }
+            }
+        }
+    }
+}
\ No newline at end of file
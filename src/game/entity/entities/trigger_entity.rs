@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use crate::{core::math::{aabb2d::Aabb2d, vec2::Vec2}, simulation::particles::{particle_vec::ParticleHandle, simulation::Simulation}};
+
+// the spatial hash is filled at the start of a step, so search a little further out to catch particles that have moved since
+const SPATIAL_HASH_MARGIN: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerShape {
+    Aabb(Aabb2d),
+    Circle { centre: Vec2, radius: f32 },
+}
+
+impl TriggerShape {
+    pub fn get_aabb(&self) -> Aabb2d {
+        match *self {
+            TriggerShape::Aabb(aabb) => aabb,
+            TriggerShape::Circle { centre, radius } => Aabb2d { min: centre, max: centre }.grow(Vec2::new(radius, radius)),
+        }
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        match *self {
+            TriggerShape::Aabb(aabb) => aabb.contains_point(point),
+            TriggerShape::Circle { centre, radius } => (point - centre).magnitude() <= radius,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerEventType {
+    Entered,
+    Exited,
+}
+
+/// A particle group moving into or out of a trigger
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TriggerEvent {
+    pub trigger: usize, // index into TriggerEntitySystem::entities
+    pub group: usize, // index into the groups passed to TriggerEntitySystem::update
+    pub event_type: TriggerEventType,
+}
+
+#[derive(Clone)]
+pub struct TriggerEntity {
+    pub shape: TriggerShape,
+    inside: Vec<bool>, // for each particle group
+}
+
+impl TriggerEntity {
+    pub fn new(shape: TriggerShape) -> Self {
+        Self {
+            shape,
+            inside: vec![],
+        }
+    }
+
+    /// Is any particle of the group inside the trigger?
+    pub fn contains_group(&self, group: usize) -> bool {
+        self.inside.get(group).copied().unwrap_or(false)
+    }
+}
+
+/// Volumes that watch for groups of particles, e.g. a car's wheels, moving in and out of them.
+/// Finish lines and checkpoints are built on these, reading the events each update.
+#[derive(Clone)]
+pub struct TriggerEntitySystem {
+    pub entities: Vec<TriggerEntity>,
+    pub events: Vec<TriggerEvent>, // from the last update
+}
+
+impl TriggerEntitySystem {
+    pub fn new() -> Self {
+        Self {
+            entities: vec![],
+            events: vec![],
+        }
+    }
+
+    /// Returns the index of the trigger, to match against TriggerEvent::trigger
+    pub fn push(&mut self, entity: TriggerEntity) -> usize {
+        self.entities.push(entity);
+        self.entities.len() - 1
+    }
+
+    /// Events for the given trigger from the last update
+    pub fn events_for(&self, trigger: usize) -> impl Iterator<Item = &TriggerEvent> + '_ {
+        self.events.iter().filter(move |event| event.trigger == trigger)
+    }
+
+    pub fn update(&mut self, sim: &Simulation, groups: &[Vec<ParticleHandle>]) {
+        self.events.clear();
+
+        // which group each particle belongs to
+        let mut particle_groups = HashMap::new();
+        for (group, particle_handles) in groups.iter().enumerate() {
+            for particle_handle in particle_handles {
+                particle_groups.insert(particle_handle.index(), group);
+            }
+        }
+
+        for (trigger, entity) in self.entities.iter_mut().enumerate() {
+            let mut inside = vec![false; groups.len()];
+
+            let search_aabb = entity.shape.get_aabb().grow(Vec2::new(SPATIAL_HASH_MARGIN, SPATIAL_HASH_MARGIN));
+            for i in sim.spatial_hash.aabb_iter(search_aabb) {
+                let Some(&group) = particle_groups.get(&i) else {
+                    continue;
+                };
+                if !inside[group] && entity.shape.contains_point(sim.particles[i].pos) {
+                    inside[group] = true;
+                }
+            }
+
+            entity.inside.resize(groups.len(), false);
+            for (group, (&was_inside, &is_inside)) in entity.inside.iter().zip(inside.iter()).enumerate() {
+                if was_inside != is_inside {
+                    let event_type = if is_inside { TriggerEventType::Entered } else { TriggerEventType::Exited };
+                    self.events.push(TriggerEvent { trigger, group, event_type });
+                }
+            }
+            entity.inside = inside;
+        }
+    }
+}
+
+impl Default for TriggerEntitySystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+    use crate::simulation::particles::{particle::Particle, shape_builder::shape_builder::ShapeBuilder};
+    use super::*;
+
+    #[test]
+    fn test_enter_and_exit() {
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        sim.gravity = Vec2::new(0.0, 0.0);
+        let mut sb = ShapeBuilder::from_particle_template(*Particle::default().set_radius(0.1).set_vel(Vec2::new(10.0, 0.0)));
+        sb.add_particle_at_position(Vec2::new(0.0, 0.0))
+            .create_in_simulation(&mut sim);
+        let groups = vec![sb.particle_handles.clone()];
+
+        let mut trigger_entity_system = TriggerEntitySystem::new();
+        let circle = trigger_entity_system.push(TriggerEntity::new(TriggerShape::Circle { centre: Vec2::new(1.0, 0.0), radius: 0.3 }));
+
+        let mut events = vec![];
+        for _ in 0..40 {
+            sim.pre_solve(0.005);
+            sim.solve(0.005, 1, 0);
+            sim.post_solve(0.005);
+            trigger_entity_system.update(&sim, &groups);
+            events.extend(trigger_entity_system.events.iter().copied());
+        }
+
+        // the particle passes through the circle, from x = 0.7 to 1.3
+        assert_eq!(events, vec![
+            TriggerEvent { trigger: circle, group: 0, event_type: TriggerEventType::Entered },
+            TriggerEvent { trigger: circle, group: 0, event_type: TriggerEventType::Exited },
+        ]);
+        assert!(!trigger_entity_system.entities[circle].contains_group(0));
+    }
+}
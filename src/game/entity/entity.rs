@@ -0,0 +1,42 @@
+use std::any::Any;
+
+use crate::{engine::app::input_action::InputAction, game::entity::entity_system::UpdateContext, simulation::particles::{particle_vec::ParticleRemap, simulation::Simulation}};
+
+/// A behaviour a level block leaves running in the level, e.g. an elevator moving its platform.
+/// Register them with EntitySystem::entities and the hooks get called each step.
+pub trait Entity {
+    fn type_name(&self) -> &str;
+
+    fn box_clone(&self) -> Box<dyn Entity + Send + Sync>;
+
+    /// For finding entities of a type, see EntityRegistry::find
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Called once a frame, after stepping the simulation
+    fn update(&mut self, _context: &mut UpdateContext) {
+    }
+
+    /// Returns true if the action was used
+    fn handle_action(&mut self, _action: InputAction, _is_pressed: bool) -> bool {
+        false
+    }
+
+    /// How far an analog input bound to the action is pushed, 0 to 1. Returns true if the value was used
+    fn handle_action_value(&mut self, _action: InputAction, _value: f32) -> bool {
+        false
+    }
+
+    /// Add to Simulation::counts for the particles solve_constraints moves, so they are averaged with the other constraints
+    fn update_counts(&mut self, _sim: &mut Simulation) {
+    }
+
+    /// Called with the simulation's constraints each solver iteration
+    fn solve_constraints(&mut self, _sim: &mut Simulation, _time_delta: f32) {
+    }
+
+    /// Call after removing particles from the simulation, see Simulation::remove_particles
+    fn remap_particles(&mut self, _remap: &ParticleRemap) {
+    }
+}
\ No newline at end of file
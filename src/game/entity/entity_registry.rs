@@ -0,0 +1,52 @@
+use crate::game::entity::entity::Entity;
+
+
+pub struct EntityRegistry(Vec<Box<dyn Entity + Send + Sync>>);
+
+impl EntityRegistry {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn register<T: Entity + Send + Sync + 'static>(&mut self, entity: T) -> &mut Self {
+        self.0.push(Box::new(entity));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Every entity of type T, in the order they were registered
+    pub fn find<T: Entity + 'static>(&self) -> impl Iterator<Item = &T> {
+        self.0.iter().filter_map(|entity| entity.as_any().downcast_ref::<T>())
+    }
+
+    pub fn find_mut<T: Entity + 'static>(&mut self) -> impl Iterator<Item = &mut T> {
+        self.0.iter_mut().filter_map(|entity| entity.as_any_mut().downcast_mut::<T>())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Box<dyn Entity + Send + Sync>> {
+        self.0.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Entity + Send + Sync>> {
+        self.0.iter_mut()
+    }
+}
+
+impl Default for EntityRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for EntityRegistry {
+    fn clone(&self) -> Self {
+        Self(self.0.iter().map(|entity| entity.box_clone()).collect())
+    }
+}
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::{engine::app::{camera::Camera, event_system::KeyCodeType}, game::{entity::entities::{car_entity::CarEntitySystem, finish_entity::FinishEntitySystem}, level::level_blocks::elevator::ElevatorEntitySystem}, simulation::particles::{particle_vec::ParticleVec, simulation::Simulation}};
+use crate::{core::math::aabb2d::Aabb2d, engine::app::{camera::Camera, input_action::InputAction}, game::entity::{entities::{car_entity::CarEntitySystem, checkpoint_entity::CheckpointEntitySystem, finish_entity::FinishEntitySystem, trigger_entity::TriggerEntitySystem}, entity_registry::EntityRegistry}, simulation::particles::{particle_vec::{ParticleRemap, ParticleVec}, simulation::Simulation}};
 
 pub struct UpdateContext<'a> {
     pub particle_vec: &'a mut ParticleVec,
@@ -9,18 +9,27 @@ pub struct UpdateContext<'a> {
 }
 
 
+/// The cars and the rules of the race (triggers, finish lines and checkpoints), along with
+/// whatever behaviours level blocks have registered in entities.
+#[derive(Clone)]
 pub struct EntitySystem {
-    pub elevator_entity_system: ElevatorEntitySystem,
+    pub entities: EntityRegistry,
     pub car_entity_system: CarEntitySystem,
+    pub trigger_entity_system: TriggerEntitySystem,
     pub finish_entity_system: FinishEntitySystem,
+    pub checkpoint_entity_system: CheckpointEntitySystem,
+    pub despawn_bounds: Option<Aabb2d>, // particles that leave these are removed, kept up to date by the LevelBuilder
 }
 
 impl EntitySystem {
     pub fn new() -> Self {
         Self {
-            elevator_entity_system: ElevatorEntitySystem::new(),
+            entities: EntityRegistry::new(),
             car_entity_system: CarEntitySystem::new(),
+            trigger_entity_system: TriggerEntitySystem::new(),
             finish_entity_system: FinishEntitySystem::new(),
+            checkpoint_entity_system: CheckpointEntitySystem::new(),
+            despawn_bounds: None,
         }
     }
 
@@ -33,11 +42,64 @@ impl EntitySystem {
             camera,
         };
 
-        self.elevator_entity_system.update(&mut context);
-        self.car_entity_system.update(&mut context, &self.finish_entity_system);
+        for entity in self.entities.iter_mut() {
+            entity.update(&mut context);
+        }
+        self.car_entity_system.update(&mut context);
+
+        self.trigger_entity_system.update(context.sim, &self.car_entity_system.trigger_particle_groups());
+        self.finish_entity_system.update(&mut context, &self.trigger_entity_system, &mut self.car_entity_system);
+        self.checkpoint_entity_system.update(&mut context, &self.trigger_entity_system, &mut self.car_entity_system);
+
+        self.despawn_particles(context.sim);
+    }
+
+    // Remove particles that have left the level, e.g. debris and smoke, so they don't pile up. Cars are left alone, the checkpoints look after them.
+    fn despawn_particles(&mut self, sim: &mut Simulation) {
+        let Some(bounds) = self.despawn_bounds else {
+            return;
+        };
+        let keep: Vec<usize> = self.car_entity_system.particle_handles().iter().map(|h| h.index()).collect();
+        if let Some(remap) = sim.remove_particles_outside(&bounds, &keep) {
+            self.remap_particles(&remap);
+        }
     }
 
-    pub fn handle_key(&mut self, key: KeyCodeType, pressed: bool) {
-        self.car_entity_system.handle_key(key, pressed);
+    /// Step the simulation along with the constraints entities add to it, e.g. moving elevator platforms
+    pub fn step_simulation(&mut self, sim: &mut Simulation, time_delta: f32) {
+        sim.pre_solve(time_delta);
+        for entity in self.entities.iter_mut() {
+            entity.update_counts(sim);
+        }
+
+        for i in 0..3 {
+            sim.solve(time_delta, 3, i);
+            for entity in self.entities.iter_mut() {
+                entity.solve_constraints(sim, time_delta);
+            }
+        }
+        sim.post_solve(time_delta);
+    }
+
+    pub fn handle_action(&mut self, action: InputAction, pressed: bool) {
+        self.car_entity_system.handle_action(action, pressed);
+        for entity in self.entities.iter_mut() {
+            entity.handle_action(action, pressed);
+        }
+    }
+
+    pub fn handle_action_value(&mut self, action: InputAction, value: f32) {
+        self.car_entity_system.handle_action_value(action, value);
+        for entity in self.entities.iter_mut() {
+            entity.handle_action_value(action, value);
+        }
+    }
+
+    /// Call after removing particles from the simulation, see Simulation::remove_particles
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        for entity in self.entities.iter_mut() {
+            entity.remap_particles(remap);
+        }
+        self.car_entity_system.remap_particles(remap);
     }
 }
\ No newline at end of file
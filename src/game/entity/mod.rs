@@ -1,2 +1,5 @@
 pub mod entities;
-pub mod entity_system;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod entity;
+pub mod entity_registry;
+pub mod entity_system;
@@ -1,4 +1,4 @@
-use std::env;
+use std::{env, path::Path};
 
 use crate::{
     core::math::vec2::Vec2,
@@ -16,15 +16,20 @@ use crate::{
     },
     game::{
         entity::{entities::car_entity::CarEntity, entity_system::EntitySystem},
-        level::level_builder::LevelBuilder,
+        ghost_car::GhostCar,
+        level::{level_builder::LevelBuilder, level_checker::{BotPolicy, LevelChecker}, level_file::LevelFile, level_seed::LevelSeed},
         irc::irc_manager::{IrcManager, IrcEvent},
         leaderboard::Leaderboard,
         game_state::GameState,
+        menu::{Level, Menu, MenuItem},
         settings::Settings,
+        ui::game_ui::SplitTime,
     },
     simulation::particles::{particle_vec::ParticleVec, simulation::Simulation, simulation_demos::SimulationDemos},
 };
-use crate::engine::app::event_system::{GameEvent, ElementStateType, KeyCodeType};
+use crate::engine::app::event_system::{EventRecording, GameEvent, ElementStateType};
+use crate::engine::app::input_action::{InputAction, InputBindings};
+use crate::core::math::vec4::Vec4;
 use cgmath::Rotation3;
 
 pub struct Game {
@@ -45,9 +50,140 @@ pub struct Game {
     current_nickname: String,
     leaderboard: Leaderboard,
     ui: crate::game::ui::game_ui::GameUI,
+    seed: LevelSeed,
+    ghost_car: Option<GhostCar>,
+    best_split_times: Vec<Option<f32>>, // from the personal best on this level, to compare against at each checkpoint
+    respawn_at_checkpoint: bool,
+    demo_scene: Option<&'static str>, // played instead of the level for the seed
+    level_file: Option<(String, LevelFile)>, // loaded with "level <path>" and played instead of the level for the seed, with its file name
+    menu: Menu,
+    menu_parent: GameState, // where backing out of the level select or controls goes
+    rebinding: Option<InputAction>, // waiting on the controls menu for a key or button to bind to this
 }
 
+// levels the bot tries with "check_level" before settling for one it can't finish
+const CHECK_LEVEL_TRIES: usize = 5;
+
+// how see-through the ghost car is drawn
+const GHOST_ALPHA: f32 = 0.35;
+
 impl Game {
+    /// The level file given with "level <path>", along with its file name
+    fn load_level_file() -> Option<(String, LevelFile)> {
+        let args: Vec<String> = env::args().collect();
+        if args.len() < 3 || args[1] != "level" {
+            return None;
+        }
+
+        match LevelFile::load(&args[2]) {
+            Ok(level_file) => {
+                let name = Path::new(&args[2]).file_name().map_or_else(|| args[2].clone(), |name| name.to_string_lossy().into_owned());
+                Some((name, level_file))
+            }
+            Err(e) => {
+                eprintln!("Failed to load level file '{}': {}", args[2], e);
+                None
+            }
+        }
+    }
+
+    /// Build the level for a seed, or from a level file. "export_level <path>" saves the level so it can be tweaked and shared.
+    /// "check_level" has a bot try to drive the level first, generating another if it can't finish it, and reports how it got on.
+    fn generate_level(seed: &LevelSeed, level_file: Option<&LevelFile>, entity_system: &mut EntitySystem, particle_vec: &mut ParticleVec, simulation: &mut Simulation) {
+        let args: Vec<String> = env::args().collect();
+        let mut level_builder = LevelBuilder::default();
+
+        if let Some(level_file) = level_file {
+            if let Err(e) = level_builder.generate_level_from_file(level_file, entity_system, particle_vec, simulation) {
+                eprintln!("Failed to build level file: {}", e);
+            }
+            return;
+        }
+
+        if args.len() >= 2 && args[1] == "check_level" {
+            let level_checker = *LevelChecker::default().set_policy(BotPolicy::Search { interval: 0.5 });
+            level_builder.set_reroll_unsolvable(level_checker, CHECK_LEVEL_TRIES);
+        }
+
+        if let Some(result) = level_builder.generate_level_from_seed(seed, entity_system, particle_vec, simulation) {
+            match result.finish_time {
+                Some(finish_time) => println!("Level solved by the bot in {:.2}s", finish_time),
+                None => println!("Level not solved by the bot, got {:.1}m of {:.1}m along", result.progress, result.path_length),
+            }
+        }
+
+        if args.len() >= 3 && args[1] == "export_level" {
+            if let Err(e) = level_builder.level_file().save(&args[2]) {
+                eprintln!("Failed to export level file '{}': {}", args[2], e);
+            }
+        }
+    }
+
+    /// Set up a demo scene, or else the level for the seed (or level file) with a car at the start.
+    /// With with_ghost, a ghost car to race gets its own copy of the level, if there is a run to race against.
+    fn build_level(seed: &LevelSeed, level_file: Option<&(String, LevelFile)>, demo_scene: Option<&str>, with_ghost: bool) -> (EntitySystem, ParticleVec, Simulation, Option<GhostCar>) {
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut simulation = Simulation::new(seed.rng());
+        let mut ghost_car = None;
+
+        match demo_scene {
+            Some(scene) => {
+                SimulationDemos::init_scene(scene, &mut simulation);
+            }
+            None => {
+                Self::generate_level(seed, level_file.map(|(_, level_file)| level_file), &mut entity_system, &mut particle_vec, &mut simulation);
+                if with_ghost {
+                    ghost_car = Self::load_ghost_car(seed, level_file.map(|(name, _)| name.as_str()), &entity_system, &particle_vec, &simulation);
+                }
+                let car = CarEntity::new(&mut particle_vec, &mut simulation, Vec2::new(0.0, 1.0));
+                entity_system.car_entity_system.push(car);
+            }
+        }
+        (entity_system, particle_vec, simulation, ghost_car)
+    }
+
+    /// A previous run to race against on a copy of the level: a recording given with "ghost <path>", or else the personal best on this level
+    fn load_ghost_car(seed: &LevelSeed, level_file_name: Option<&str>, entity_system: &EntitySystem, particle_vec: &ParticleVec, simulation: &Simulation) -> Option<GhostCar> {
+        let args: Vec<String> = env::args().collect();
+        let path = if args.len() >= 3 && args[1] == "ghost" {
+            args[2].clone()
+        } else {
+            GhostCar::personal_best_path(seed, level_file_name)
+        };
+
+        match GhostCar::load(&path, seed, level_file_name) {
+            Ok(recording) => Some(GhostCar::new(recording, entity_system.clone(), particle_vec.clone(), simulation.clone())),
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    eprintln!("Failed to load ghost '{}': {}", path, e);
+                }
+                None
+            }
+        }
+    }
+
+    /// Split times of the personal best on this level, if there is one
+    fn load_best_split_times(seed: &LevelSeed, level_file_name: Option<&str>) -> Vec<Option<f32>> {
+        EventRecording::load(&GhostCar::personal_best_path(seed, level_file_name)).map(|best| best.split_times).unwrap_or_default()
+    }
+
+    /// Name of the level file being played, if it isn't the level for the seed
+    fn level_file_name(&self) -> Option<&str> {
+        self.level_file.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    /// Checkpoints passed so far, compared against the personal best
+    fn split_times(&self) -> Vec<SplitTime> {
+        self.entity_system.checkpoint_entity_system.split_times().iter().enumerate()
+            .filter_map(|(checkpoint, time)| time.map(|time| SplitTime {
+                checkpoint,
+                time,
+                delta: self.best_split_times.get(checkpoint).copied().flatten().map(|best| time - best),
+            }))
+            .collect()
+    }
+
     fn update_particle_instances(&mut self, queue: &wgpu::Queue, device: &wgpu::Device) {
         let mut instances: Vec<Instance> = vec![]; 
         let particles = &self.simulation.particles;
@@ -69,6 +205,44 @@ impl Game {
 
             instances.push(Instance { position, rotation, colour, radius });
         }
+
+        // Static colliders are drawn as a dotted outline
+        let collider_point_radius = 0.05;
+        for collider in &self.simulation.colliders {
+            for point in collider.outline_points(collider_point_radius * 2.0) {
+                let position = cgmath::Vector3 {
+                    x: point.x,
+                    y: point.y,
+                    z: 0.0,
+                };
+
+                let rotation = cgmath::Quaternion::from_axis_angle(
+                    cgmath::Vector3::unit_z(),
+                    cgmath::Deg(0.0),
+                );
+
+                instances.push(Instance { position, rotation, colour: collider.colour, radius: collider_point_radius });
+            }
+        }
+
+        // The ghost car is drawn last so it shows up translucent over the background but behind everything else
+        if let Some(ghost_car) = &self.ghost_car {
+            for particle in ghost_car.car_particles() {
+                let position = cgmath::Vector3 {
+                    x: particle.pos.x,
+                    y: particle.pos.y,
+                    z: 0.0,
+                };
+
+                let rotation = cgmath::Quaternion::from_axis_angle(
+                    cgmath::Vector3::unit_z(),
+                    cgmath::Deg(0.0),
+                );
+
+                let colour = Vec4::new(particle.colour.x, particle.colour.y, particle.colour.z, GHOST_ALPHA);
+                instances.push(Instance { position, rotation, colour, radius: particle.radius });
+            }
+        }
         self.particle_instance_renderer.update_instances(&instances, queue, device);
     }
     pub fn reset(&mut self, ctx: &mut Context) {
@@ -76,43 +250,194 @@ impl Game {
         self.game_state = GameState::Playing;
         self.frame_idx = 0;
         
-        // Re-initialize systems
-        self.entity_system = EntitySystem::new();
-        self.particle_vec = ParticleVec::new();
-        
-        let rng = crate::core::math::random::Random::seed_from_beginning_of_day();
-        self.simulation = Simulation::new(rng);
-        
         // Re-generate level
-        LevelBuilder::default().generate_level_based_on_date(&mut self.entity_system, &mut self.particle_vec, &mut self.simulation);
-        let car = CarEntity::new(&mut self.particle_vec, &mut self.simulation, Vec2::new(0.0, 1.0));
-        self.entity_system.car_entity_system.push(car);
+        (self.entity_system, self.particle_vec, self.simulation, self.ghost_car) = Self::build_level(&self.seed, self.level_file.as_ref(), self.demo_scene, true);
+        self.entity_system.checkpoint_entity_system.respawn = self.respawn_at_checkpoint;
+        self.best_split_times = Self::load_best_split_times(&self.seed, self.level_file_name());
         
         // Update UI
         self.ui.update(crate::game::ui::game_ui::Message::UpdateGameState(GameState::Playing));
         self.ui.update(crate::game::ui::game_ui::Message::UpdateTime(0.0));
+        self.ui.update(crate::game::ui::game_ui::Message::UpdateSplitTimes(vec![]));
+        self.ui.update(crate::game::ui::game_ui::Message::UpdateSeed(self.level_name()));
         
         // Reset recording if necessary
-        let args: Vec<String> = env::args().collect();
-        let scene = if args.len() >= 2 { args[1].clone() } else { String::from("") };
-        let is_demo_scene = matches!(scene.as_str(), "friction" | "granular" | "sdf" | "boxes" | "wall" | "pendulum" | "rope" | "fluid" | "fluid_solid" | "gas" | "water_balloon" | "newtons_cradle" | "smoke_open" | "smoke_closed" | "rope_gas" | "volcano" | "wrecking_ball");
-        
-        if !is_demo_scene {
+        if self.demo_scene.is_none() {
+            ctx.event_system.set_recording_seed(Some(self.seed.to_string()));
             ctx.event_system.start_recording();
+        } else if ctx.event_system.is_recording() {
+            ctx.event_system.stop_recording();
         }
         
         self.update_particle_instances(&ctx.graphics.queue, &ctx.graphics.device);
     }
+
+    /// The seed's share code, or the demo scene or level file being played
+    fn level_name(&self) -> String {
+        match (self.demo_scene, self.level_file_name()) {
+            (Some(scene), _) => Level::Demo(scene).label(),
+            (None, Some(level_file_name)) => level_file_name.to_owned(),
+            (None, None) => self.seed.to_string(),
+        }
+    }
+
+    fn set_game_state(&mut self, game_state: GameState) {
+        self.game_state = game_state;
+        self.ui.update(crate::game::ui::game_ui::Message::UpdateGameState(game_state));
+    }
+
+    fn open_menu(&mut self, game_state: GameState, menu: Menu) {
+        self.menu = menu;
+        self.ui.update(crate::game::ui::game_ui::Message::UpdateMenu(self.menu.clone()));
+        self.set_game_state(game_state);
+    }
+
+    fn select_menu_item(&mut self, item: MenuItem, ctx: &mut Context) {
+        match item {
+            MenuItem::Resume => self.set_game_state(GameState::Playing),
+            MenuItem::Restart => self.reset(ctx),
+            MenuItem::LevelSelect => {
+                self.menu_parent = self.game_state;
+                self.open_menu(GameState::LevelSelect, Menu::level_select());
+            }
+            MenuItem::Controls => {
+                self.menu_parent = self.game_state;
+                self.open_menu(GameState::Controls, Menu::controls(ctx.event_system.bindings()));
+            }
+            MenuItem::Rebind { action, .. } => {
+                self.rebinding = Some(action);
+                self.menu.title = format!("Press a key or button for {}", action.label());
+                self.ui.update(crate::game::ui::game_ui::Message::UpdateMenu(self.menu.clone()));
+            }
+            MenuItem::MainMenu => self.open_menu(GameState::MainMenu, Menu::main_menu()),
+            MenuItem::Play(level) => {
+                if ctx.event_system.is_replaying() {
+                    ctx.event_system.stop_replay();
+                }
+                match level {
+                    Level::Seed(seed) => {
+                        self.seed = seed;
+                        self.demo_scene = None;
+                        self.level_file = None;
+                    }
+                    Level::Demo(scene) => self.demo_scene = Some(scene),
+                }
+                self.reset(ctx);
+            }
+            MenuItem::Back => match self.menu_parent {
+                GameState::Paused => self.open_menu(GameState::Paused, Menu::pause_menu()),
+                _ => self.open_menu(GameState::MainMenu, Menu::main_menu()),
+            },
+        }
+    }
+
+    /// Pausing again resumes, and in the other menus it backs out
+    fn handle_pause(&mut self, ctx: &mut Context) {
+        match self.game_state {
+            GameState::Playing => self.open_menu(GameState::Paused, Menu::pause_menu()),
+            GameState::Paused => self.select_menu_item(MenuItem::Resume, ctx),
+            GameState::LevelSelect | GameState::Controls => self.select_menu_item(MenuItem::Back, ctx),
+            GameState::Finished => self.open_menu(GameState::MainMenu, Menu::main_menu()),
+            GameState::NameEntry | GameState::MainMenu => {}
+        }
+    }
+
+    /// Bind the key or button pressed while the controls menu was waiting for one, and save it to the settings
+    fn rebind(&mut self, ctx: &mut Context, bind: impl FnOnce(&mut InputBindings, InputAction)) {
+        let Some(action) = self.rebinding.take() else {
+            return;
+        };
+        let mut bindings = ctx.event_system.bindings().clone();
+        bind(&mut bindings, action);
+        ctx.event_system.set_bindings(bindings.clone());
+
+        let settings = Settings {
+            bindings,
+            ..Settings::load()
+        };
+        if let Err(e) = settings.save() {
+            eprintln!("Failed to save settings: {}", e);
+        }
+
+        let selected = self.menu.selected;
+        self.menu = Menu::controls(ctx.event_system.bindings());
+        self.menu.selected = selected;
+        self.ui.update(crate::game::ui::game_ui::Message::UpdateMenu(self.menu.clone()));
+    }
+
+    // the leaderboard is kept per seed, so demo scenes and level files don't have one
+    fn leaderboard_seed(&self) -> Option<String> {
+        if self.demo_scene.is_some() || self.level_file.is_some() {
+            return None;
+        }
+        Some(self.seed.to_string())
+    }
+
+    // time of the run just finished to show on the leaderboard, if it counts
+    fn leaderboard_run_time(&self) -> Option<f32> {
+        if self.game_state == GameState::Finished && !self.respawn_at_checkpoint { Some(self.total_time) } else { None }
+    }
+
+    fn process_irc_events(&mut self) {
+        if let Some(irc) = &self.irc_manager {
+            for event in irc.process_events() {
+                let IrcEvent::MessageReceived { target, message, .. } = event else {
+                    continue;
+                };
+                if target != "#planck-leaderboard" {
+                    continue;
+                }
+
+                let seed = self.leaderboard_seed();
+                if message.starts_with("BEST_TIME") {
+                    self.leaderboard.parse_message(&message);
+                    if let Some(sync_msg) = seed.as_deref().and_then(|seed| self.leaderboard.serialize_sync(seed)) {
+                        irc.send_message("#planck-leaderboard".to_owned(), sync_msg);
+                    }
+                } else if message.starts_with("LEADERBOARD_SYNC") {
+                    self.leaderboard.parse_sync_message(&message);
+                }
+                if let Some(seed) = seed {
+                    let entries = self.leaderboard.get_leaderboard_entries(&seed, &self.current_nickname, self.leaderboard_run_time());
+                    self.ui.update(crate::game::ui::game_ui::Message::UpdateLeaderboardResults(entries));
+                }
+            }
+        }
+    }
 }
 
 impl GameLoop for Game {
     fn new(ctx: &mut Context) -> Self {
         let camera_controller = CameraController::new(0.2);
-        let mut entity_system = EntitySystem::new();
-        let mut particle_vec = ParticleVec::new();
+
+        let args: Vec<String> = env::args().collect();
+        let scene = if args.len() >= 2 { args[1].clone() } else { String::from("") };
         
-        let rng = crate::core::math::random::Random::seed_from_beginning_of_day();
-        let mut simulation = Simulation::new(rng);
+        let replay_file = if args.len() >= 3 && args[1] == "replay" {
+            Some(args[2].clone())
+        } else {
+            None
+        };
+
+        let replay_loaded = match &replay_file {
+            Some(replay_path) => match ctx.event_system.load_replay(replay_path) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("Failed to load replay file '{}': {}", replay_path, e);
+                    false
+                }
+            },
+            None => false,
+        };
+
+        // Pick the level: the one a replay was recorded on, one given with "seed <share code>", or todays
+        let seed = if let Some(replay_seed) = ctx.event_system.replay_seed() {
+            LevelSeed::from_share_code(replay_seed)
+        } else if args.len() >= 3 && args[1] == "seed" {
+            LevelSeed::from_share_code(&args[2])
+        } else {
+            LevelSeed::today()
+        };
 
         let particle_instance_renderer = InstanceRenderer::new(&ctx.graphics.device, &ctx.graphics.queue, &ctx.graphics.config);
         let quad_mesh = Mesh::from_verticies_and_indicies("Quad".to_owned(), &ctx.graphics.device, QUAD_VERTICES, QUAD_INDICES);
@@ -124,65 +449,43 @@ impl GameLoop for Game {
         let particle_shader = ShaderBuilder::from_file("particle_shader.wgsl".to_owned(), &ctx.graphics.device)
             .camera(&camera)
             .diffuse_texture(diffuse_texture)
+            .blend(wgpu::BlendState::ALPHA_BLENDING)
             .build(&[Vertex::desc(), InstanceRaw::desc()], ctx.graphics.config.format);
         
         let line_shader = ShaderBuilder::from_file("line_shader.wgsl".to_owned(), &ctx.graphics.device)
             .camera(&camera)
             .build(&[Vertex::desc(), InstanceRaw::desc()], ctx.graphics.config.format);
-
-        let args: Vec<String> = env::args().collect();
-        let scene = if args.len() >= 2 { args[1].clone() } else { String::from("") };
         
-        let replay_file = if args.len() >= 3 && args[1] == "replay" {
-            Some(args[2].clone())
-        } else {
-            None
-        };
-        
-        let is_demo_scene = match scene.as_str() {
-            "friction" => { SimulationDemos::init_friction(&mut simulation); true }
-            "granular" => { SimulationDemos::init_granular(&mut simulation); true }
-            "sdf" => { SimulationDemos::init_sdf(&mut simulation); true }
-            "boxes" => { SimulationDemos::init_boxes(&mut simulation); true }
-            "wall" => { SimulationDemos::init_wall(&mut simulation); true }
-            "pendulum" => { SimulationDemos::init_pendulum(&mut simulation); true }
-            "rope" => { SimulationDemos::init_rope(&mut simulation); true }
-            "fluid" => { SimulationDemos::init_fluid(&mut simulation); true }
-            "fluid_solid" => { SimulationDemos::init_fluid_solid(&mut simulation); true }
-            "gas" => { SimulationDemos::init_gas(&mut simulation); true }
-            "water_balloon" => { SimulationDemos::init_water_balloon(&mut simulation); true }
-            "newtons_cradle" => { SimulationDemos::init_newtons_cradle(&mut simulation); true }
-            "smoke_open" => { SimulationDemos::init_smoke_open(&mut simulation); true }
-            "smoke_closed" => { SimulationDemos::init_smoke_closed(&mut simulation); true }
-            "rope_gas" => { SimulationDemos::init_rope_gas(&mut simulation); true }
-            "volcano" => { SimulationDemos::init_volcano(&mut simulation); true }
-            "wrecking_ball" => { SimulationDemos::init_wrecking_ball(&mut simulation); true }
-            "replay" | _ => {
-                LevelBuilder::default().generate_level_based_on_date(&mut entity_system, &mut particle_vec, &mut simulation);
-                let car = CarEntity::new(&mut particle_vec, &mut simulation, Vec2::new(0.0, 1.0));
-                entity_system.car_entity_system.push(car);
-                false
-            }
-        };
-
-        if let Some(replay_path) = replay_file {
-            if let Err(e) = ctx.event_system.load_replay(&replay_path) {
-                eprintln!("Failed to load replay file '{}': {}", replay_path, e);
-            } else {
+        let demo_scene = SimulationDemos::SCENES.iter().copied().find(|s| *s == scene);
+        let is_demo_scene = demo_scene.is_some();
+        let level_file = Self::load_level_file();
+        let (mut entity_system, particle_vec, simulation, ghost_car) = Self::build_level(&seed, level_file.as_ref(), demo_scene, replay_file.is_none());
+        let level_file_name = level_file.as_ref().map(|(name, _)| name.as_str());
+
+        if replay_file.is_some() {
+            if replay_loaded {
                 ctx.event_system.start_replay();
             }
         } else if !is_demo_scene {
+            ctx.event_system.set_recording_seed(Some(seed.to_string()));
             ctx.event_system.start_recording();
         }
 
         let settings = Settings::load();
-        let (game_state, nickname) = if let Some(name) = settings.player_name {
-            (GameState::Playing, name)
+        ctx.event_system.set_bindings(settings.bindings.clone());
+        // with nothing given on the command line start at the main menu, with todays level waiting behind it
+        let (game_state, nickname) = if let Some(name) = settings.player_name.clone() {
+            (if args.len() < 2 { GameState::MainMenu } else { GameState::Playing }, name)
         } else {
             (GameState::NameEntry, format!("Player{}", chrono::Utc::now().timestamp_subsec_micros()))
         };
 
-        let irc_manager = if game_state == GameState::Playing {
+        let best_split_times = Self::load_best_split_times(&seed, level_file_name);
+        // a replay only plays back the same if the car is put back at checkpoints the same way it was when recorded
+        let respawn_at_checkpoint = if replay_loaded { ctx.event_system.replay_respawn_at_checkpoint() } else { settings.respawn_at_checkpoint };
+        entity_system.checkpoint_entity_system.respawn = respawn_at_checkpoint;
+
+        let irc_manager = if game_state != GameState::NameEntry {
             Some(IrcManager::new(
                  "irc.libera.chat".to_owned(),
                  nickname.clone(),
@@ -194,6 +497,7 @@ impl GameLoop for Game {
 
         let mut ui = crate::game::ui::game_ui::GameUI::new();
         ui.update(crate::game::ui::game_ui::Message::UpdateGameState(game_state));
+        ui.update(crate::game::ui::game_ui::Message::UpdateSeed(demo_scene.map_or_else(|| level_file_name.map_or_else(|| seed.to_string(), str::to_owned), |scene| Level::Demo(scene).label())));
 
         let mut game = Self {
             camera,
@@ -213,6 +517,15 @@ impl GameLoop for Game {
             current_nickname: nickname,
             leaderboard: Leaderboard::new(),
             ui,
+            seed,
+            ghost_car,
+            best_split_times,
+            respawn_at_checkpoint,
+            demo_scene,
+            level_file,
+            menu: Menu::main_menu(),
+            menu_parent: GameState::MainMenu,
+            rebinding: None,
         };
 
         game.update_particle_instances(&ctx.graphics.queue, &ctx.graphics.device);
@@ -224,44 +537,79 @@ impl GameLoop for Game {
         let fps = (1.0 / dt).round() as i32;
         self.ui.update(crate::game::ui::game_ui::Message::UpdateFps(fps));
 
-        self.frame_idx += 1;
-        ctx.event_system.set_frame(self.frame_idx);
+        // the frame only moves on when the simulation steps, so input while paused is recorded against the step it will affect
+        ctx.event_system.set_frame(self.frame_idx + 1);
         ctx.event_system.process_events();
 
-        let mut should_reset = false;
-        for event in ctx.event_system.events.iter() {
-            match event {
+        let events = std::mem::take(&mut ctx.event_system.events);
+        let mut rebound = false; // the key or button just bound still queued its old actions this frame
+        for event in events.iter() {
+            match *event {
                 GameEvent::KeyboardInput { key_code, state } => {
                     let is_pressed = matches!(state, ElementStateType::Pressed);
-                    self.camera_controller.handle_key(*key_code, is_pressed);
-                    self.entity_system.handle_key(*key_code, is_pressed);
-                    
-                    if *key_code == KeyCodeType::KeyR && is_pressed && self.game_state == GameState::Finished {
-                        should_reset = true;
+                    if is_pressed && self.rebinding.is_some() {
+                        self.rebind(ctx, |bindings, action| bindings.rebind_key(action, key_code));
+                        rebound = true;
+                    }
+
+                    // the menu keys would also move the camera, so it only sees them let go of while a menu is up
+                    if !(self.game_state.is_menu() && is_pressed) {
+                        self.camera_controller.handle_key(key_code, is_pressed);
+                    }
+                }
+                GameEvent::GamepadButton { button, state } => {
+                    if matches!(state, ElementStateType::Pressed) && self.rebinding.is_some() {
+                        self.rebind(ctx, |bindings, action| bindings.rebind_button(action, button));
+                        rebound = true;
+                    }
+                }
+                GameEvent::ActionValue { action, value } => {
+                    self.entity_system.handle_action_value(action, value);
+                }
+                GameEvent::Action { action, state } => {
+                    let is_pressed = matches!(state, ElementStateType::Pressed);
+                    self.entity_system.handle_action(action, is_pressed);
+
+                    if !is_pressed || self.rebinding.is_some() || rebound {
+                        continue;
+                    }
+
+                    if self.game_state.is_menu() {
+                        match self.menu.handle_action(action) {
+                            Some(item) => self.select_menu_item(item, ctx),
+                            None => self.ui.update(crate::game::ui::game_ui::Message::UpdateMenu(self.menu.clone())),
+                        }
+                    }
+
+                    // r is also typed into the seed box on the leaderboard, so only retry there while it is empty
+                    let can_restart = match self.game_state {
+                        GameState::Playing | GameState::Paused => true,
+                        GameState::Finished => self.ui.seed_input.is_empty(),
+                        _ => false,
+                    };
+                    match action {
+                        InputAction::Pause => self.handle_pause(ctx),
+                        InputAction::Restart if can_restart => self.reset(ctx),
+                        _ => {}
                     }
                 }
                 _ => {}
             }
         }
-        
-        if should_reset {
-            self.reset(ctx);
-        }
         ctx.event_system.clear_events();
 
-        if self.game_state == GameState::NameEntry {
+        self.process_irc_events();
+
+        if self.game_state == GameState::NameEntry || self.game_state.is_menu() {
             return;
         }
 
+        self.frame_idx += 1;
         let time_delta: f32 = 0.005;
-        self.simulation.pre_solve(time_delta);
-        self.entity_system.elevator_entity_system.update_counts(&mut self.simulation);
-
-        for i in 0..3 {
-            self.simulation.solve(time_delta, 3, i);
-            self.entity_system.elevator_entity_system.solve_constraints(&mut self.simulation, time_delta);
+        self.entity_system.step_simulation(&mut self.simulation, time_delta);
+        if let Some(ghost_car) = &mut self.ghost_car {
+            ghost_car.update(self.frame_idx, time_delta, self.total_time);
         }
-        self.simulation.post_solve(time_delta);
         
         self.camera_controller.update_camera(&mut self.camera);
 
@@ -272,6 +620,11 @@ impl GameLoop for Game {
         self.entity_system.update(&mut self.particle_vec, &mut self.simulation, &mut self.camera, time_delta, self.total_time);
 
         if self.game_state == GameState::Playing {
+            let split_times = self.split_times();
+            if split_times.len() != self.ui.split_times.len() {
+                self.ui.update(crate::game::ui::game_ui::Message::UpdateSplitTimes(split_times));
+            }
+
             let game_finished = self.entity_system.car_entity_system.0.iter().any(|car| car.game_ended);
             if game_finished {
                 self.game_state = GameState::Finished;
@@ -279,55 +632,51 @@ impl GameLoop for Game {
                 
                 if ctx.event_system.is_recording() {
                     ctx.event_system.stop_recording();
+                    let mut recording = ctx.event_system.recording();
+                    recording.time = Some(self.total_time);
+                    recording.split_times = self.entity_system.checkpoint_entity_system.split_times();
+                    recording.respawn_at_checkpoint = self.respawn_at_checkpoint;
+                    recording.level_file = self.level_file_name().map(str::to_owned);
+
+                    // with everything a replay needs to play it back the same
                     let filename = "recording.json";
-                    let _ = ctx.event_system.export_recording(&filename);
-                }
-                
-                let seed = chrono::Utc::now().format("%Y-%m-%d").to_string();
-                let msg = format!("BEST_TIME seed={} time={:.3} user={}", seed, self.total_time, self.current_nickname);
-                if let Some(irc) = &self.irc_manager {
-                    irc.send_message("#planck-leaderboard".to_owned(), msg);
+                    if let Err(e) = recording.save(filename) {
+                        eprintln!("Failed to export recording '{}': {}", filename, e);
+                    }
+
+                    // keep the fastest run to race against as a ghost next time
+                    if let Err(e) = GhostCar::save_if_personal_best(&recording, &self.seed) {
+                        eprintln!("Failed to save personal best: {}", e);
+                    }
                 }
                 
-                self.leaderboard.add_score(seed.clone(), self.current_nickname.clone(), self.total_time);
-
-                let entries = self.leaderboard.get_leaderboard_entries(&seed, &self.current_nickname, Some(self.total_time));
-                self.ui.update(crate::game::ui::game_ui::Message::UpdateLeaderboardResults(entries));
+                if let Some(seed) = self.leaderboard_seed() {
+                    // runs where the car was put back at checkpoints don't go on the leaderboard, they wouldn't be a fair comparison
+                    if !self.respawn_at_checkpoint {
+                        let msg = format!("BEST_TIME seed={} time={:.3} user={}", seed, self.total_time, self.current_nickname);
+                        if let Some(irc) = &self.irc_manager {
+                            irc.send_message("#planck-leaderboard".to_owned(), msg);
+                        }
 
-                if let Some(top10) = self.leaderboard.get_top_10(&seed) {
-                    if let Some(irc) = &self.irc_manager {
-                        irc.send_message("#planck-global".to_owned(), top10);
+                        self.leaderboard.add_score(seed.clone(), self.current_nickname.clone(), self.total_time);
                     }
-                }
-            }
-        }
 
-        self.camera.update_camera_uniform(&ctx.graphics.queue);
-        self.update_particle_instances(&ctx.graphics.queue, &ctx.graphics.device);
+                    let entries = self.leaderboard.get_leaderboard_entries(&seed, &self.current_nickname, self.leaderboard_run_time());
+                    self.ui.update(crate::game::ui::game_ui::Message::UpdateLeaderboardResults(entries));
 
-        if let Some(irc) = &self.irc_manager {
-            for event in irc.process_events() {
-                match event {
-                    IrcEvent::MessageReceived { target, message, .. } => {
-                        if target == "#planck-leaderboard" {
-                            let seed = chrono::Utc::now().format("%Y-%m-%d").to_string();
-                            if message.starts_with("BEST_TIME") {
-                                self.leaderboard.parse_message(&message);
-                                if let Some(sync_msg) = self.leaderboard.serialize_sync(&seed) {
-                                    irc.send_message("#planck-leaderboard".to_owned(), sync_msg);
-                                }
-                            } else if message.starts_with("LEADERBOARD_SYNC") {
-                                self.leaderboard.parse_sync_message(&message);
+                    if !self.respawn_at_checkpoint {
+                        if let Some(top10) = self.leaderboard.get_top_10(&seed) {
+                            if let Some(irc) = &self.irc_manager {
+                                irc.send_message("#planck-global".to_owned(), top10);
                             }
-                            let current_run_time = if self.game_state == GameState::Finished { Some(self.total_time) } else { None };
-                            let entries = self.leaderboard.get_leaderboard_entries(&seed, &self.current_nickname, current_run_time);
-                            self.ui.update(crate::game::ui::game_ui::Message::UpdateLeaderboardResults(entries));
                         }
-                    },
-                    _ => {}
+                    }
                 }
             }
         }
+
+        self.camera.update_camera_uniform(&ctx.graphics.queue);
+        self.update_particle_instances(&ctx.graphics.queue, &ctx.graphics.device);
     }
 
     fn render(&mut self, ctx: &mut Context) {
@@ -383,6 +732,7 @@ impl GameLoop for Game {
                         self.current_nickname = self.ui.name_input.trim().to_string();
                         let settings = Settings {
                             player_name: Some(self.current_nickname.clone()),
+                            ..Settings::load()
                         };
                         let _ = settings.save();
 
@@ -396,6 +746,21 @@ impl GameLoop for Game {
                         self.ui.update(crate::game::ui::game_ui::Message::UpdateGameState(GameState::Playing));
                     }
                 }
+                crate::game::ui::game_ui::Message::SelectMenuItem(i) => {
+                    if let Some(item) = self.menu.items.get(i).cloned() {
+                        self.menu.selected = i;
+                        self.select_menu_item(item, ctx);
+                    }
+                }
+                crate::game::ui::game_ui::Message::SubmitSeed => {
+                    if !self.ui.seed_input.trim().is_empty() {
+                        self.seed = LevelSeed::from_share_code(&self.ui.seed_input);
+                        self.demo_scene = None;
+                        self.level_file = None;
+                        self.ui.update(crate::game::ui::game_ui::Message::UpdateSeedInput(String::new()));
+                        self.reset(ctx);
+                    }
+                }
                 _ => self.ui.update(msg),
             }
         }
@@ -1,6 +1,17 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameState {
     NameEntry,
+    MainMenu,
+    LevelSelect,
     Playing,
+    Paused,
+    Controls,
     Finished,
 }
+
+impl GameState {
+    /// States that show a menu over the level, which is frozen until it is closed
+    pub fn is_menu(&self) -> bool {
+        matches!(self, GameState::MainMenu | GameState::LevelSelect | GameState::Paused | GameState::Controls)
+    }
+}
@@ -0,0 +1,208 @@
+use std::io;
+
+use crate::{
+    core::math::vec2::Vec2,
+    engine::app::{camera::Camera, event_system::{ElementStateType, EventRecording, GameEvent}},
+    game::{entity::{entities::car_entity::CarEntity, entity_system::EntitySystem}, level::level_seed::LevelSeed},
+    simulation::particles::{particle::Particle, particle_vec::ParticleVec, simulation::Simulation},
+};
+
+/// A previous run replayed alongside the player, e.g. their personal best or a run someone shared.
+/// It drives its own copy of the level in a separate Simulation so it never bumps into the player, and is drawn translucent over the live run.
+pub struct GhostCar {
+    entity_system: EntitySystem,
+    particle_vec: ParticleVec,
+    simulation: Simulation,
+    camera: Camera, // the car moves a camera to follow it, this one is never shown
+    recording: EventRecording,
+    replay_index: usize,
+}
+
+impl GhostCar {
+    /// Race on a copy of the level being played, built without the player's car
+    pub fn new(recording: EventRecording, mut entity_system: EntitySystem, mut particle_vec: ParticleVec, mut simulation: Simulation) -> Self {
+        entity_system.checkpoint_entity_system.respawn = recording.respawn_at_checkpoint;
+
+        let car = CarEntity::new(&mut particle_vec, &mut simulation, Vec2::new(0.0, 1.0));
+        entity_system.car_entity_system.push(car);
+
+        Self {
+            entity_system,
+            particle_vec,
+            simulation,
+            camera: Camera::headless(1.0),
+            recording,
+            replay_index: 0,
+        }
+    }
+
+    /// Load a recording to race against. It has to have been recorded on the same level, the level file if one is being played or else the seed.
+    pub fn load(path: &str, seed: &LevelSeed, level_file: Option<&str>) -> io::Result<EventRecording> {
+        let recording = EventRecording::load(path)?;
+        let same_level = match level_file {
+            Some(level_file) => recording.level_file.as_deref() == Some(level_file),
+            None => recording.level_file.is_none() && recording.seed.as_deref().map(LevelSeed::from_share_code).as_ref() == Some(seed),
+        };
+        if !same_level {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("recording is not of level {}", level_file.map_or_else(|| seed.to_string(), str::to_owned))));
+        }
+        Ok(recording)
+    }
+
+    /// Where the fastest finished run of a level is kept, keyed by the level file if one is being played or else the seed
+    pub fn personal_best_path(seed: &LevelSeed, level_file: Option<&str>) -> String {
+        let level = match level_file {
+            Some(level_file) => format!("level_{}", level_file),
+            None => seed.to_string(),
+        };
+        let name: String = level.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect();
+        format!("personal_best_{}.json", name)
+    }
+
+    /// Save the recording as the personal best for its level if it beats the one saved already. Returns true if it did.
+    pub fn save_if_personal_best(recording: &EventRecording, seed: &LevelSeed) -> io::Result<bool> {
+        let path = Self::personal_best_path(seed, recording.level_file.as_deref());
+        let best_time = EventRecording::load(&path).ok().and_then(|best| best.time);
+        if best_time.is_some_and(|best_time| recording.time.is_none_or(|time| time >= best_time)) {
+            return Ok(false);
+        }
+        recording.save(&path)?;
+        Ok(true)
+    }
+
+    /// Finish time of the run being replayed
+    pub fn time(&self) -> Option<f32> {
+        self.recording.time
+    }
+
+    /// Feed in the recorded input up to this frame and step the ghost's simulation, the same way Game::update steps the live one
+    pub fn update(&mut self, frame: u128, time_delta: f32, total_time: f32) {
+        while let Some(framed_event) = self.recording.events.get(self.replay_index) {
+            if framed_event.frame > frame {
+                break;
+            }
+            match framed_event.event {
+                GameEvent::Action { action, state } => {
+                    self.entity_system.handle_action(action, matches!(state, ElementStateType::Pressed));
+                }
+                GameEvent::ActionValue { action, value } => {
+                    self.entity_system.handle_action_value(action, value);
+                }
+                _ => {}
+            }
+            self.replay_index += 1;
+        }
+
+        self.entity_system.step_simulation(&mut self.simulation, time_delta);
+        self.entity_system.update(&mut self.particle_vec, &mut self.simulation, &mut self.camera, time_delta, total_time);
+    }
+
+    /// The ghost car's particles, for drawing. The rest of its level is not drawn as it matches the live one.
+    /// Found through the car's handles, as particles falling out of the level move the car's particles along.
+    pub fn car_particles(&self) -> impl Iterator<Item = &Particle> {
+        self.entity_system.car_entity_system.particle_handles().into_iter().map(|handle| &self.simulation.particles[handle])
+    }
+
+    pub fn has_finished(&self) -> bool {
+        self.entity_system.car_entity_system.0.iter().any(|car| car.game_ended)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::level::level_builder::LevelBuilder;
+    use crate::engine::app::{event_system::{EventSystem, FramedEvent}, input_action::InputAction};
+
+    #[test]
+    fn test_ghost_follows_recording() {
+        let seed = LevelSeed::text("ghost");
+        let accelerate = |frame: u128, state: ElementStateType| FramedEvent { frame, event: GameEvent::Action { action: InputAction::Accelerate, state } };
+        let recording = EventRecording {
+            events: vec![accelerate(10, ElementStateType::Pressed), accelerate(150, ElementStateType::Released)],
+            seed: Some(seed.to_string()),
+            level_file: None,
+            time: None,
+            split_times: vec![],
+            respawn_at_checkpoint: false,
+        };
+
+        // drive a live car the same way on the same level
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(seed.rng());
+        LevelBuilder::default().generate_level_from_seed(&seed, &mut entity_system, &mut particle_vec, &mut sim);
+        let mut ghost_car = GhostCar::new(recording, entity_system.clone(), particle_vec.clone(), sim.clone());
+        let car = CarEntity::new(&mut particle_vec, &mut sim, Vec2::new(0.0, 1.0));
+        entity_system.car_entity_system.push(car);
+
+        let start_x = ghost_car.car_particles().next().unwrap().pos.x;
+        let mut camera = Camera::headless(1.0);
+        let time_delta = 0.005;
+        for frame in 1..300 {
+            if frame == 10 || frame == 150 {
+                entity_system.handle_action(InputAction::Accelerate, frame == 10);
+            }
+            entity_system.step_simulation(&mut sim, time_delta);
+            entity_system.update(&mut particle_vec, &mut sim, &mut camera, time_delta, frame as f32 * time_delta);
+
+            ghost_car.update(frame, time_delta, frame as f32 * time_delta);
+        }
+
+        let car_particles: Vec<Particle> = entity_system.car_entity_system.particle_handles().into_iter().map(|handle| sim.particles[handle]).collect();
+        assert!(ghost_car.car_particles().eq(car_particles.iter()));
+        assert!(car_particles[0].pos.x > start_x + 0.5, "{:?}", car_particles[0].pos);
+
+        // free text seeds can hold anything, so keep them out of the file name
+        assert!(!GhostCar::personal_best_path(&LevelSeed::text("../a b"), None).contains('/'));
+    }
+
+    #[test]
+    fn test_ghost_car_survives_despawn() {
+        let seed = LevelSeed::text("ghost");
+        let recording = EventRecording { seed: Some(seed.to_string()), ..EventSystem::new().recording() };
+
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(seed.rng());
+        LevelBuilder::default().generate_level_from_seed(&seed, &mut entity_system, &mut particle_vec, &mut sim);
+        let mut ghost_car = GhostCar::new(recording.clone(), entity_system.clone(), particle_vec.clone(), sim.clone());
+
+        // debris already out of the level is despawned on the first update, moving the car's particles down
+        let bounds = entity_system.despawn_bounds.unwrap();
+        sim.add_particle(*Particle::default().set_pos(bounds.min - Vec2::new(10.0, 10.0)).set_mass(1.0));
+        let mut ghost_car_with_debris = GhostCar::new(recording, entity_system, particle_vec, sim);
+        let particle_count = ghost_car_with_debris.simulation.particles.len();
+
+        let time_delta = 0.005;
+        for frame in 1..50 {
+            ghost_car.update(frame, time_delta, frame as f32 * time_delta);
+            ghost_car_with_debris.update(frame, time_delta, frame as f32 * time_delta);
+        }
+
+        assert_eq!(ghost_car_with_debris.simulation.particles.len(), particle_count - 1);
+        assert_eq!(ghost_car_with_debris.car_particles().count(), ghost_car.car_particles().count());
+        for (p, p_with_debris) in ghost_car.car_particles().zip(ghost_car_with_debris.car_particles()) {
+            assert!((p.pos - p_with_debris.pos).magnitude() < 0.01, "{:?} {:?}", p.pos, p_with_debris.pos);
+        }
+    }
+
+    #[test]
+    fn test_personal_best_per_level_file() {
+        let seed = LevelSeed::text("ghost");
+        assert_ne!(GhostCar::personal_best_path(&seed, None), GhostCar::personal_best_path(&seed, Some("ghost")));
+        assert_ne!(GhostCar::personal_best_path(&seed, Some("a.json")), GhostCar::personal_best_path(&seed, Some("b.json")));
+
+        // a run on a level file only races that level file, whatever the seed was at the time
+        let mut recording = EventSystem::new().recording();
+        recording.seed = Some(seed.to_string());
+        recording.level_file = Some("a.json".to_owned());
+        let path = std::env::temp_dir().join("planck_test_ghost_level_file.json");
+        let path = path.to_str().unwrap();
+        recording.save(path).unwrap();
+        assert!(GhostCar::load(path, &LevelSeed::text("other"), Some("a.json")).is_ok());
+        assert!(GhostCar::load(path, &seed, Some("b.json")).is_err());
+        assert!(GhostCar::load(path, &seed, None).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
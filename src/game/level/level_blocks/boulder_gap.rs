@@ -0,0 +1,101 @@
+use crate::{core::math::{vec2::Vec2, vec4::Vec4}, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}, simulation::{constraints::volume_constraint::VolumeConstraint, particles::shape_builder::{adjacent_sticks::AdjacentSticks, circle::{Circle, SpaceDistribution}, shape_builder::ShapeBuilder}}};
+
+/// A big pressurised ball sat in front of a pit. The car pushes the boulder along until it drops into the pit,
+/// which fills it in enough for the car to drive across.
+pub struct BoulderGapOperation {
+}
+
+impl LevelBuilderOperation for BoulderGapOperation {
+    fn type_name(&self) -> &str {"BoulderGapOperation"}
+
+    fn box_clone(&self) -> Box<dyn LevelBuilderOperation + Send + Sync> {
+        Box::new(BoulderGapOperation {})
+    }
+
+    fn difficulty(&self) -> f32 {
+        1.5
+    }
+
+    fn default_spawn_chance(&self) -> f32 {
+        0.5
+    }
+
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("boulder_radius", 0.6..=1.0, 0.8),
+            LevelBuilderParam::new("run_up", 1.5..=3.0, 2.0), // ground between the boulder and the pit to push it along
+            LevelBuilderParam::new("boulder_mass", 0.1..=0.3, 0.2), // of each particle in its skin. Any heavier and the car climbs over it instead of pushing it
+        ]
+    }
+
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let boulder_radius = params.get("boulder_radius");
+        let run_up = params.get("run_up");
+        let particle_radius = level_builder_context.particle_template.radius;
+        let x_direction = level_builder_context.x_direction;
+
+        // The pit is a little wider than the boulder so it drops straight in, and as deep so the top of the boulder
+        // ends up about level with the ground.
+        let boulder_diameter = (boulder_radius + particle_radius) * 2.0;
+        let pit_width = boulder_diameter + particle_radius;
+        let pit_depth = boulder_diameter - particle_radius * 2.0;
+
+        // leave room behind the boulder for the car to get up to it
+        let boulder_start = 1.0 + boulder_diameter;
+
+        let cursor_start = level_builder_context.cursor;
+        let pit_start = cursor_start + Vec2::new((boulder_start + run_up) * x_direction, 0.0);
+        let pit_end = pit_start + Vec2::new(pit_width * x_direction, 0.0);
+        let cursor_end = pit_end + Vec2::new(1.0 * x_direction, 0.0);
+        let down = Vec2::new(0.0, -pit_depth);
+
+        // Ground and pit. The walls are brought in by a particle radius to where the inside of a particle wall would be.
+        let inset = Vec2::new(particle_radius * x_direction, 0.0);
+        let (wall_start, wall_end) = (pit_start + inset, pit_end - inset);
+        level_builder_context.add_ground(&[cursor_start, wall_start, wall_start + down, wall_end + down, wall_end, cursor_end]);
+
+        // Boulder. A ring of particles held together like the car's tyres, and kept round by the air inside it.
+        let boulder_centre = cursor_start + Vec2::new(boulder_start * x_direction, boulder_radius + particle_radius * 2.0);
+        let mut particle_template = *level_builder_context.particle_template.clone().set_mass(params.get("boulder_mass")).set_colour(Vec4::new(0.6, 0.5, 0.4, 1.0));
+        // slippery enough that the wheels push it along rather than climbing up it
+        particle_template.s_friction = 0.2;
+        particle_template.k_friction = 0.2;
+
+        let mut sb = ShapeBuilder::from_particle_template(particle_template);
+        sb.apply_operation(Circle::new(boulder_centre, boulder_radius, SpaceDistribution::SpaceBetweenParticles))
+            .create_in_simulation(level_builder_context.sim);
+
+        AdjacentSticks::new(1, true)
+            .apply_to_particle_handles(level_builder_context.sim, &sb.particle_handles);
+
+        let compliance = 0.00001;
+        let particle_indices = sb.particle_handles.iter().map(|h| h.index()).collect();
+        let volume_constraint = VolumeConstraint::new(compliance, particle_indices, &level_builder_context.sim.particles);
+        level_builder_context.sim.add_volume_constraint(volume_constraint);
+
+        level_builder_context.cursor = cursor_end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{core::math::vec2::Vec2, game::level::level_builder::tests::TestLevel, simulation::particles::simulation::Simulation};
+
+    #[test]
+    fn test_car_pushes_boulder_into_pit() {
+        let mut level = TestLevel::from_operation("BoulderGapOperation");
+
+        // the boulder is the only thing in the level held together by a volume constraint
+        let boulder = level.sim.volume_constraints.0[0].particle_indices.clone();
+        let boulder_centre = |sim: &Simulation| boulder.iter().fold(Vec2::new(0.0, 0.0), |c, &i| c + sim.particles[i].pos) / boulder.len() as f32;
+        let pit_end = level.level_builder.path()[2].x - 1.0;
+
+        // the car has driven over the pit rather than into it
+        let car_pos = level.drive_past(pit_end + 0.5, 1200);
+        assert!(car_pos.x > pit_end && car_pos.y > 0.0, "{:?}", car_pos);
+
+        // because the boulder is down in it
+        let centre = boulder_centre(&level.sim);
+        assert!(centre.y < 0.0 && centre.x < pit_end, "{:?}", centre);
+    }
+}
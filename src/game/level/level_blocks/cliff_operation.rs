@@ -1,6 +1,4 @@
-use rand::Rng;
-
-use crate::{core::math::vec2::Vec2, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
+use crate::{core::math::vec2::Vec2, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
 
 pub struct CliffOperation {
 }
@@ -16,11 +14,15 @@ impl LevelBuilderOperation for CliffOperation {
         0.5
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
-        let rng = &mut level_builder_context.rng;
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("height", -2.0..=-0.5, -1.0),
+        ]
+    }
 
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
         let width = 0.0;
-        let height = rng.random_range(-2.0..=-0.5);
+        let height = params.get("height");
 
         let cursor_start = level_builder_context.cursor;
         let cursor_end = cursor_start + Vec2::new(width * level_builder_context.x_direction, height);
@@ -0,0 +1,147 @@
+use std::any::Any;
+
+use crate::{core::math::{vec2::Vec2, vec4::Vec4}, game::{entity::{entity::Entity, entity_system::UpdateContext}, level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}}, simulation::{constraints::distance_constraint::DistanceConstraint, particles::{shape_builder::{line_segment::LineSegment, rectangle::Rectangle, rectangle_stick_grid::RectangleStickGrid, shape_builder::ShapeBuilder}, simulation::Simulation}}};
+
+// particles across the thickness of the curtain
+const THICKNESS_PARTICLES: f32 = 2.0;
+
+/// A curtain of cloth stretched between a rail and the ground. The car has to rip through it, as the cloth tears when stretched too far.
+pub struct ClothCurtainOperation {
+}
+
+impl LevelBuilderOperation for ClothCurtainOperation {
+    fn type_name(&self) -> &str {"ClothCurtainOperation"}
+
+    fn box_clone(&self) -> Box<dyn LevelBuilderOperation + Send + Sync> {
+        Box::new(ClothCurtainOperation {})
+    }
+
+    fn default_spawn_chance(&self) -> f32 {
+        0.5
+    }
+
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("width", 5.0..=8.0, 6.0),
+            LevelBuilderParam::new("height", 2.0..=3.0, 2.5), // of the rail the curtain hangs from
+            LevelBuilderParam::new("max_strain", 0.3..=0.8, 0.5), // how far the cloth stretches before it tears
+        ]
+    }
+
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let width = params.get("width");
+        let height = params.get("height");
+        let particle_radius = level_builder_context.particle_template.radius;
+
+        let horizontal_movement = Vec2::new(width * level_builder_context.x_direction, 0.0);
+        let cursor_start = level_builder_context.cursor;
+        let cursor_end = cursor_start + horizontal_movement;
+        let curtain_centre = cursor_start + horizontal_movement * 0.5;
+
+        // Ground and the rail the curtain hangs from:
+        let rail_half_width = Vec2::new(0.5, 0.0);
+        let rail = curtain_centre + Vec2::new(0.0, height);
+        ShapeBuilder::from_particle_template(*level_builder_context.particle_template.clone().set_static(true))
+            .apply_operation(LineSegment::new(cursor_start, cursor_end))
+            .apply_operation(LineSegment::new(rail - rail_half_width, rail + rail_half_width))
+            .create_in_simulation(level_builder_context.sim);
+
+        // Curtain. Rectangle leaves off the far edges, so pad it slightly to get a whole number of particles across.
+        let half_thickness = particle_radius * (THICKNESS_PARTICLES + 0.25);
+        let rectangle = Rectangle::from_corners(
+            curtain_centre + Vec2::new(-half_thickness, particle_radius * 2.0),
+            rail + Vec2::new(half_thickness, -particle_radius * 2.0),
+        );
+
+        let particle_vec_start_index = level_builder_context.sim.particles.len();
+
+        let mut particle_template = *level_builder_context.particle_template.clone().set_mass(0.2).set_colour(Vec4::new(0.8, 0.2, 0.8, 1.0));
+        particle_template.s_friction = 0.1;
+        particle_template.k_friction = 0.1;
+
+        let mut sb = ShapeBuilder::from_particle_template(particle_template);
+        sb.apply_operation(rectangle.clone());
+
+        // pin the top row to the rail and the bottom row to the ground
+        let aabb = sb.get_aabb();
+        sb.particles.iter_mut().for_each(|particle| {
+            if particle.pos.y == aabb.min.y || particle.pos.y == aabb.max.y {
+                particle.set_mass(0.0);
+            }
+        });
+        sb.create_in_simulation(level_builder_context.sim);
+
+        let max_strain = params.get("max_strain");
+        let constraint_ids = RectangleStickGrid::from_rectangle(rectangle)
+            .compute_particle_pairs(sb.particle_radius(), particle_vec_start_index)
+            .iter()
+            .map(|particle_handles| {
+                level_builder_context.sim.add_distance_constraint(
+                    DistanceConstraint::from_particles(particle_handles[0], particle_handles[1], &level_builder_context.sim.particles).set_max_strain(max_strain).clone()
+                )
+            })
+            .collect();
+
+        level_builder_context.entity_system.entities.register(ClothCurtainEntity {
+            constraint_ids,
+            torn: false,
+        });
+
+        level_builder_context.cursor = cursor_end;
+    }
+}
+
+#[derive(Clone)]
+pub struct ClothCurtainEntity {
+    constraint_ids: Vec<usize>,
+    pub torn: bool, // has the car ripped through it
+}
+
+impl ClothCurtainEntity {
+    /// How many threads of the cloth have snapped
+    pub fn num_broken_constraints(&self, sim: &Simulation) -> usize {
+        self.constraint_ids.iter().filter(|&&id| sim.distance_constraints.0[id].broken).count()
+    }
+}
+
+impl Entity for ClothCurtainEntity {
+    fn type_name(&self) -> &str {"ClothCurtainEntity"}
+
+    fn box_clone(&self) -> Box<dyn Entity + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) {
+        if !self.torn && self.num_broken_constraints(context.sim) > 0 {
+            self.torn = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::level::level_builder::tests::TestLevel;
+
+    #[test]
+    fn test_car_tears_cloth_curtain() {
+        let mut level = TestLevel::from_operation("ClothCurtainOperation");
+
+        // the car makes it through to the other side
+        let car_pos = level.drive_past(8.0, 1000);
+        assert!(car_pos.x > 8.0, "{:?}", car_pos);
+
+        let curtains: Vec<&ClothCurtainEntity> = level.entity_system.entities.find::<ClothCurtainEntity>().collect();
+        assert_eq!(curtains.len(), 1);
+        assert!(curtains[0].torn);
+        assert!(curtains[0].num_broken_constraints(&level.sim) > 0);
+    }
+}
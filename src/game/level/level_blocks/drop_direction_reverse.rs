@@ -1,6 +1,4 @@
-use rand::Rng;
-
-use crate::{core::math::vec2::Vec2, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
+use crate::{core::math::vec2::Vec2, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
 
 pub struct DropDirectionReverse {
 }
@@ -12,15 +10,24 @@ impl LevelBuilderOperation for DropDirectionReverse {
         Box::new(DropDirectionReverse {})
     }
 
+    fn difficulty(&self) -> f32 {
+        2.0
+    }
+
     fn default_spawn_chance(&self) -> f32 {
         0.3
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
-        let rng = &mut level_builder_context.rng;
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("width", 3.0..=3.0, 3.0),
+            LevelBuilderParam::new("height", -4.5..=-2.5, -3.5),
+        ]
+    }
 
-        let width = 3.0;
-        let height = rng.random_range(-4.5..=-2.5);
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let width = params.get("width");
+        let height = params.get("height");
 
         let cursor_start = level_builder_context.cursor;
         let cursor_end = cursor_start + Vec2::new(level_builder_context.x_direction * width, height);
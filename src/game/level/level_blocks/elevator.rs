@@ -1,6 +1,6 @@
-use rand::Rng;
+use std::any::Any;
 
-use crate::{core::math::{vec2::Vec2, vec4::Vec4}, game::{entity::entity_system::UpdateContext, level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation}}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
+use crate::{core::math::{aabb2d::Aabb2d, vec2::Vec2, vec4::Vec4}, game::{entity::{entity::Entity, entity_system::UpdateContext}, level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}}, simulation::particles::{particle_vec::ParticleRemap, shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}, simulation::Simulation}};
 
 pub struct ElevatorOperation {
 }
@@ -12,15 +12,39 @@ impl LevelBuilderOperation for ElevatorOperation {
         Box::new(ElevatorOperation {})
     }
 
+    fn difficulty(&self) -> f32 {
+        2.0
+    }
+
+    fn estimated_time(&self, params: &LevelBuilderParams) -> f32 {
+        // on average we wait half a cycle (one trip and one wait), then ride it up
+        let trip_time = params.get("height") / params.get("speed");
+        params.get("wait_time") + trip_time * 2.0
+    }
+
     fn default_spawn_chance(&self) -> f32 {
         0.5
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
-        let rng = &mut level_builder_context.rng;
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("width", 2.0..=2.0, 2.0),
+            LevelBuilderParam::new("height", 1.0..=4.0, 2.5),
+            LevelBuilderParam::new("speed", 1.0..=2.0, 1.5),
+            LevelBuilderParam::new("wait_time", 2.0..=2.0, 2.0),
+        ]
+    }
 
-        let width = 2.0;
-        let height = rng.random_range(1.0..=4.0);
+    fn aabb(&self, level_builder_context: &LevelBuilderContext, params: &LevelBuilderParams, built: Aabb2d) -> Aabb2d {
+        // the platform rides all the way up to where the block ends
+        let cursor_end = level_builder_context.cursor;
+        let cursor_start = cursor_end - Vec2::new(params.get("width") * level_builder_context.x_direction, params.get("height"));
+        built.merge(&Aabb2d::from_point_cloud(&[cursor_start, cursor_end]))
+    }
+
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let width = params.get("width");
+        let height = params.get("height");
 
         let horizontal_movement = Vec2::new(width * level_builder_context.x_direction, 0.0);
         let vertical_movement = Vec2::new(0.0, height);
@@ -47,15 +71,15 @@ impl LevelBuilderOperation for ElevatorOperation {
 
         let first_particle_offset = platform.particles[0].pos - elevator_start;
 
-        level_builder_context.entity_system.elevator_entity_system.push(ElevatorEntity {
+        level_builder_context.entity_system.entities.register(ElevatorEntity {
             start: elevator_start,
             end: elevator_end,
-            speed: rng.random_range(1.0..=2.0),
+            speed: params.get("speed"),
             state: ElevatorState::MovingUp,
             pos: elevator_start,
-            particle_indicies: platform.particle_handles,
+            particle_indicies: platform.particle_handles.iter().map(|h| h.index()).collect(),
             first_particle_offset,
-            wait_time: 2.0,
+            wait_time: params.get("wait_time"),
             wait_timer: 0.0,
             particle_radius: level_builder_context.particle_template.radius,
         });
@@ -64,6 +88,7 @@ impl LevelBuilderOperation for ElevatorOperation {
     }
 }
 
+#[derive(Clone)]
 enum ElevatorState {
     MovingUp,
     AtTopWaiting,
@@ -71,6 +96,7 @@ enum ElevatorState {
     AtBottomWaiting,
 }
 
+#[derive(Clone)]
 pub struct ElevatorEntity {
     start: Vec2,
     end: Vec2,
@@ -84,90 +110,88 @@ pub struct ElevatorEntity {
     particle_radius: f32,
 }
 
-pub struct ElevatorEntitySystem(pub Vec<ElevatorEntity>);
+impl Entity for ElevatorEntity {
+    fn type_name(&self) -> &str {"ElevatorEntity"}
+
+    fn box_clone(&self) -> Box<dyn Entity + Send + Sync> {
+        Box::new(self.clone())
+    }
 
-impl ElevatorEntitySystem {
-    pub fn new() -> Self {
-        Self(vec![])
+    fn as_any(&self) -> &dyn Any {
+        self
     }
 
-    pub fn push(&mut self, c: ElevatorEntity) {
-        self.0.push(c);
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
     }
 
-    pub fn update(&mut self, context: &mut UpdateContext) {
-        for e in self.0.iter_mut() {
-            // todo: support horizontal movement too
-            match e.state {
-                ElevatorState::MovingUp => {
-                    if e.pos.y >= e.end.y {
-                        e.state = ElevatorState::AtTopWaiting;
-                        e.wait_timer = e.wait_time;
-                        e.pos = e.end;
-                    } else {
-                        e.pos += Vec2::new(0.0, e.speed) * context.time_delta;
-                    }
+    fn update(&mut self, context: &mut UpdateContext) {
+        // todo: support horizontal movement too
+        match self.state {
+            ElevatorState::MovingUp => {
+                if self.pos.y >= self.end.y {
+                    self.state = ElevatorState::AtTopWaiting;
+                    self.wait_timer = self.wait_time;
+                    self.pos = self.end;
+                } else {
+                    self.pos += Vec2::new(0.0, self.speed) * context.time_delta;
                 }
+            }
 
-                ElevatorState::AtTopWaiting => {
-                    e.wait_timer -= context.time_delta;
-                    if e.wait_timer <= 0.0 {
-                        e.state = ElevatorState::MovingDown;
-                    }
+            ElevatorState::AtTopWaiting => {
+                self.wait_timer -= context.time_delta;
+                if self.wait_timer <= 0.0 {
+                    self.state = ElevatorState::MovingDown;
                 }
+            }
 
-                ElevatorState::MovingDown => {
-                    if e.pos.y <= e.start.y {
-                        e.state = ElevatorState::AtBottomWaiting;
-                        e.wait_timer = e.wait_time;
-                        e.pos = e.start;
-                    } else {
-                        e.pos += Vec2::new(0.0, -e.speed) * context.time_delta;
-                    }
+            ElevatorState::MovingDown => {
+                if self.pos.y <= self.start.y {
+                    self.state = ElevatorState::AtBottomWaiting;
+                    self.wait_timer = self.wait_time;
+                    self.pos = self.start;
+                } else {
+                    self.pos += Vec2::new(0.0, -self.speed) * context.time_delta;
                 }
-                
-                ElevatorState::AtBottomWaiting => {
-                    e.wait_timer -= context.time_delta;
-                    if e.wait_timer <= 0.0 {
-                        e.state = ElevatorState::MovingUp;
-                    }
-                },
-            };
-        }
+            }
+            
+            ElevatorState::AtBottomWaiting => {
+                self.wait_timer -= context.time_delta;
+                if self.wait_timer <= 0.0 {
+                    self.state = ElevatorState::MovingUp;
+                }
+            },
+        };
     }
 
-    pub fn update_counts(&mut self, sim: &mut crate::simulation::particles::simulation::Simulation) {
-        // for e in self.0.iter_mut() {
-        //     sim.bodies[e.body_idx].update_counts(&mut sim.counts, 1);
-        // }
+    fn remap_particles(&mut self, remap: &ParticleRemap) {
+        remap.indices(&mut self.particle_indicies);
+    }
 
-        for e in self.0.iter_mut() {
-            for pi in e.particle_indicies.iter() {
-                sim.counts[*pi] += 1;
-            }
+    fn update_counts(&mut self, sim: &mut Simulation) {
+        // sim.bodies[self.body_idx].update_counts(&mut sim.counts, 1);
+
+        for pi in self.particle_indicies.iter() {
+            sim.counts[*pi] += 1;
         }
     }
 
-    pub fn solve_constraints(&mut self, sim: &mut crate::simulation::particles::simulation::Simulation, _time_delta: f32) {
-        // for e in self.0.iter_mut() {
-        //     let body_pos = sim.bodies[e.body_idx].center;
-        //     let offset = e.pos - body_pos;
+    fn solve_constraints(&mut self, sim: &mut Simulation, _time_delta: f32) {
+        // let body_pos = sim.bodies[self.body_idx].center;
+        // let offset = self.pos - body_pos;
 
-        //     sim.bodies[e.body_idx].for_each_particle(|particle_idx| {
-        //         let particle = &mut sim.particles[particle_idx];
-        //         particle.pos_guess += offset / sim.counts[particle_idx] as f32;
-        //     });
-        // }
+        // sim.bodies[self.body_idx].for_each_particle(|particle_idx| {
+        //     let particle = &mut sim.particles[particle_idx];
+        //     particle.pos_guess += offset / sim.counts[particle_idx] as f32;
+        // });
 
-        for e in self.0.iter_mut() {
-            for (idx, pi) in e.particle_indicies.iter().enumerate() {
-                let pos_guess = sim.particles[*pi].pos_guess;
-                let where_particle_pos_guess_should_be = e.pos + e.first_particle_offset + Vec2::new(e.particle_radius * 2.0 * (idx as f32), 0.0);
+        for (idx, pi) in self.particle_indicies.iter().enumerate() {
+            let pos_guess = sim.particles[*pi].pos_guess;
+            let where_particle_pos_guess_should_be = self.pos + self.first_particle_offset + Vec2::new(self.particle_radius * 2.0 * (idx as f32), 0.0);
 
-                let offset = where_particle_pos_guess_should_be - pos_guess;
+            let offset = where_particle_pos_guess_should_be - pos_guess;
 
-                sim.particles[*pi].pos_guess += offset / sim.counts[*pi] as f32;
-            }
+            sim.particles[*pi].pos_guess += offset / sim.counts[*pi] as f32;
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::{core::math::{aabb2d::Aabb2d, unit_conversions::cm_to_m, vec2::Vec2}, game::{entity::entities::finish_entity::FinishEntity, level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation}}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
+use crate::{core::math::{aabb2d::Aabb2d, unit_conversions::cm_to_m, vec2::Vec2}, game::{entity::entities::{finish_entity::FinishEntity, trigger_entity::{TriggerEntity, TriggerShape}}, level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::LevelBuilderParams}}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
 
 pub struct FinishOperation {
 }
@@ -10,6 +10,14 @@ impl LevelBuilderOperation for FinishOperation {
         Box::new(FinishOperation {})
     }
 
+    fn difficulty(&self) -> f32 {
+        0.0
+    }
+
+    fn has_checkpoint(&self) -> bool {
+        false // the finish line is the last one
+    }
+
     fn prepare(&self, level_builder_context: &mut LevelBuilderContext, level_builder_operations: &mut Vec<(f32, Box<dyn LevelBuilderOperation + Send + Sync>)>) {
         // ensure that we are always the last operation that gets applied
         // and is never used outside of that range
@@ -28,7 +36,7 @@ impl LevelBuilderOperation for FinishOperation {
         }
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, _params: &LevelBuilderParams) {
         let width = 3.0;
         let height = 0.0;
 
@@ -56,6 +64,7 @@ impl LevelBuilderOperation for FinishOperation {
 
         // Add finish entity
         let aabb = Aabb2d::from_point_cloud(&[finish_start, cursor_end]);
-        level_builder_context.entity_system.finish_entity_system.push(FinishEntity::new(aabb));
+        let trigger = level_builder_context.entity_system.trigger_entity_system.push(TriggerEntity::new(TriggerShape::Aabb(aabb)));
+        level_builder_context.entity_system.finish_entity_system.push(FinishEntity::new(trigger));
     }
 }
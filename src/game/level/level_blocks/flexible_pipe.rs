@@ -0,0 +1,186 @@
+use crate::{core::math::{aabb2d::Aabb2d, bezier_spline::{BezierSpline, CubicBezierCurve}, vec2::Vec2, vec4::Vec4}, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}, simulation::{constraints::spring_constraint::SpringConstraint, particles::{particle::{Particle, COLLISION_LAYER_SOFT_BODY}, shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}, simulation::Simulation}}};
+
+// every n-th particle along a wall is pinned in place, the ones between hang off springs
+const ANCHOR_INTERVAL: usize = 4;
+
+// how far back over the way in the level needs room to carry on, once the pipe has turned it around
+const RETURN_ROOM: f32 = 10.0;
+
+// distance between control points and the ends of a quarter circle made from a cubic bezier curve, as a fraction of the radius
+const QUARTER_CIRCLE_HANDLE: f32 = 0.5523;
+
+/// A pipe that bends up and back over itself. The car drives in along the bottom, loops up around the outside wall
+/// and drops out of the top upside down, heading back the way it came. The walls are springy so the pipe flexes as the car goes round.
+pub struct FlexiblePipeOperation {
+}
+
+impl FlexiblePipeOperation {
+    // half a circle around centre, from the bottom round to the top, bulging out in x_direction
+    fn u_bend(centre: Vec2, radius: f32, x_direction: f32) -> BezierSpline {
+        let handle = radius * QUARTER_CIRCLE_HANDLE;
+        let bottom = centre + Vec2::new(0.0, -radius);
+        let side = centre + Vec2::new(radius * x_direction, 0.0);
+        let top = centre + Vec2::new(0.0, radius);
+
+        let mut spline = BezierSpline::new();
+        spline.add_curve(CubicBezierCurve::new(bottom, bottom + Vec2::new(handle * x_direction, 0.0), side + Vec2::new(0.0, -handle), side));
+        spline.add_curve(CubicBezierCurve::new(side, side + Vec2::new(0.0, handle), top + Vec2::new(handle * x_direction, 0.0), top));
+        spline
+    }
+
+    // range of heights above the way in that the pipe can come out at, over all its params
+    fn exit_heights(&self) -> (f32, f32) {
+        let params = self.params();
+        let param = |name: &str| params.iter().find(|param| param.name == name).unwrap();
+        let (radius, pipe_width) = (param("radius"), param("pipe_width"));
+        (radius.min * 2.0 - pipe_width.max, radius.max * 2.0 - pipe_width.min)
+    }
+
+    // A wall along the bottom leg, round the bend and back along the top leg, as a chain of springs pinned at intervals.
+    fn create_wall(sim: &mut Simulation, particle_template: Particle, bottom_start: Vec2, centre: Vec2, radius: f32, top_end: Vec2, x_direction: f32) {
+        let spline = Self::u_bend(centre, radius, x_direction);
+        let arc_length = std::f32::consts::PI * radius;
+        let mut bend_points = spline.get_points((arc_length / (particle_template.radius * 2.0)).ceil() as usize);
+        let bend_end = bend_points.pop().unwrap();
+
+        let mut sb = ShapeBuilder::from_particle_template(particle_template);
+        sb.apply_operation(LineSegment::new(bottom_start, centre + Vec2::new(0.0, -radius)))
+            .add_particles_from_points(&bend_points)
+            .apply_operation(LineSegment::new(bend_end, top_end))
+            .add_particle_at_position(top_end);
+
+        let last = sb.particles.len() - 1;
+        sb.particles.iter_mut().enumerate().for_each(|(i, particle)| {
+            if i % ANCHOR_INTERVAL == 0 || i == last {
+                particle.set_static(true);
+            }
+        });
+        sb.create_in_simulation(sim);
+
+        let stiffness = 2000.0;
+        for particle_handles in sb.particle_handles.windows(2) {
+            let spring = SpringConstraint::from_particles(particle_handles[0].index(), particle_handles[1].index(), &sim.particles, stiffness);
+            sim.add_spring_constraint(spring);
+        }
+    }
+}
+
+impl LevelBuilderOperation for FlexiblePipeOperation {
+    fn type_name(&self) -> &str {"FlexiblePipeOperation"}
+
+    fn box_clone(&self) -> Box<dyn LevelBuilderOperation + Send + Sync> {
+        Box::new(FlexiblePipeOperation {})
+    }
+
+    fn difficulty(&self) -> f32 {
+        2.0
+    }
+
+    fn default_spawn_chance(&self) -> f32 {
+        0.3
+    }
+
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("run_up", 3.0..=5.0, 4.0), // length of the bottom leg, to get up speed for the loop
+            LevelBuilderParam::new("radius", 1.8..=2.2, 2.0), // of the bend, measured to the outside wall
+            LevelBuilderParam::new("pipe_width", 1.1..=1.3, 1.2), // leave room for the car's wheels
+        ]
+    }
+
+    fn prepare(&self, level_builder_context: &mut LevelBuilderContext, level_builder_operations: &mut Vec<(f32, Box<dyn LevelBuilderOperation + Send + Sync>)>) {
+        // The level comes out of the pipe heading back over the blocks before it. Rule the pipe out if they are in the way,
+        // rather than have the level run into them a few blocks later with nowhere left to go.
+        let (lowest_exit, highest_exit) = self.exit_heights();
+        let cursor = level_builder_context.cursor;
+        let return_room = Aabb2d::from_point_cloud(&[
+            cursor + Vec2::new(0.0, lowest_exit),
+            cursor + Vec2::new(-RETURN_ROOM * level_builder_context.x_direction, highest_exit),
+        ]);
+        if level_builder_context.intersects_any(&return_room) {
+            for op_chance in level_builder_operations.iter_mut() {
+                if op_chance.1.type_name() == self.type_name() {
+                    op_chance.0 = 0.0;
+                }
+            }
+        }
+    }
+
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let run_up = params.get("run_up");
+        let radius = params.get("radius");
+        let pipe_width = params.get("pipe_width");
+        let x_direction = level_builder_context.x_direction;
+
+        let cursor_start = level_builder_context.cursor;
+        let centre = cursor_start + Vec2::new(run_up * x_direction, radius);
+
+        let mut particle_template = *level_builder_context.particle_template.clone().set_mass(0.5).set_colour(Vec4::new(0.3, 0.6, 0.9, 1.0));
+        particle_template.set_collision_layer(COLLISION_LAYER_SOFT_BODY, !COLLISION_LAYER_SOFT_BODY); // Prevent self-collision
+
+        // Outside wall. The floor on the way in and the ceiling on the way out, which stops short so the car drops onto the inside wall.
+        let ceiling_end = centre + Vec2::new(-run_up * 0.5 * x_direction, radius);
+        Self::create_wall(level_builder_context.sim, particle_template, cursor_start, centre, radius, ceiling_end, x_direction);
+
+        // Inside wall. The ceiling on the way in, leaving room to drive under it, and the floor on the way out.
+        let inner_radius = radius - pipe_width;
+        let inner_start = cursor_start + Vec2::new(1.0 * x_direction, pipe_width);
+        let cursor_end = centre + Vec2::new(-run_up * x_direction, inner_radius);
+        Self::create_wall(level_builder_context.sim, particle_template, inner_start, centre, inner_radius, cursor_end, x_direction);
+
+        level_builder_context.cursor = cursor_end;
+        level_builder_context.x_direction = -x_direction;
+        level_builder_context.x_direction_changed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use super::*;
+    use crate::game::{entity::entity_system::EntitySystem, level::level_builder::tests::TestLevel};
+    use crate::simulation::particles::particle_vec::ParticleVec;
+
+    #[test]
+    fn test_pipe_needs_room_to_come_back() {
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        let mut rng = Pcg64::seed_from_u64(0);
+        let mut level_builder_context = LevelBuilderContext::new(&mut entity_system, &mut particle_vec, &mut sim, &mut rng);
+        level_builder_context.cursor = Vec2::new(10.0, 0.0);
+        let spawn_chance = |level_builder_context: &mut LevelBuilderContext| {
+            let mut operations: Vec<(f32, Box<dyn LevelBuilderOperation + Send + Sync>)> = vec![(1.0, Box::new(FlexiblePipeOperation {}))];
+            FlexiblePipeOperation {}.prepare(level_builder_context, &mut operations);
+            operations[0].0
+        };
+
+        // flat ground on the way in is fine
+        level_builder_context.aabbs.push(Aabb2d::from_point_cloud(&[Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)]));
+        assert_eq!(spawn_chance(&mut level_builder_context), 1.0);
+
+        // a hill behind it is in the way
+        level_builder_context.aabbs.push(Aabb2d::from_point_cloud(&[Vec2::new(2.0, 0.0), Vec2::new(6.0, 2.0)]));
+        assert_eq!(spawn_chance(&mut level_builder_context), 0.0);
+    }
+
+    #[test]
+    fn test_pipe_flips_direction() {
+        let mut level = TestLevel::from_operation("FlexiblePipeOperation");
+
+        // comes out above where it went in, and the next block heads back the other way
+        let path = level.level_builder.path();
+        assert!((path[2].x - path[1].x).abs() < 0.001);
+        assert!(path[2].y > path[1].y);
+        assert!(path[3].x < path[2].x);
+
+        // the walls flex but keep their shape
+        let start: Vec<_> = level.sim.particles.iter().map(|p| p.pos).collect();
+        level.step(200);
+        for (i, pos) in start.iter().enumerate() {
+            assert!((level.sim.particles[i].pos - *pos).magnitude() < 0.2, "{} moved to {:?}", i, level.sim.particles[i].pos);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 
-use crate::{game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation}, core::math::{unit_conversions::g_to_kg, vec2::Vec2, vec4::Vec4}, simulation::particles::{particle::Particle, shape_builder::{line_segment::LineSegment, rectangle::Rectangle, shape_builder::ShapeBuilder}}};
+use crate::{game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::LevelBuilderParams}, core::math::{unit_conversions::g_to_kg, vec2::Vec2, vec4::Vec4}, simulation::particles::{fluid_parameters::FluidParameters, particle::Particle, particle_vec::ParticleVec, shape_builder::{line_segment::LineSegment, rectangle::Rectangle, shape_builder::ShapeBuilder}}};
 
 
 pub struct FluidFunnel {
@@ -12,22 +12,15 @@ impl LevelBuilderOperation for FluidFunnel {
         Box::new(FluidFunnel {})
     }
 
-    fn default_spawn_chance(&self) -> f32 {
-        0.5
+    fn difficulty(&self) -> f32 {
+        1.5
     }
 
-    fn prepare(&self, level_builder_context: &mut LevelBuilderContext, level_builder_operations: &mut Vec<(f32, Box<dyn LevelBuilderOperation + Send + Sync>)>) {
-        // once the level changes direction, do not spawn a fluid funnel as it takes up to much vertical space and can interfere with stuff above us
-        if level_builder_context.x_direction_changed {
-            for op_chance in level_builder_operations.iter_mut() {
-                if op_chance.1.type_name() == self.type_name() {
-                    op_chance.0 = 0.0;
-                }
-            }
-        }
+    fn default_spawn_chance(&self) -> f32 {
+        0.5
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, _params: &LevelBuilderParams) {
         let _rng = &mut level_builder_context.rng;
 
         // let width = 0.0;
@@ -56,10 +49,13 @@ impl LevelBuilderOperation for FluidFunnel {
         let width = liquid_particle_radius * 2.0 * 20.0;
         let height = liquid_particle_radius * 2.0 * 15.0;
 
-        // Liquid
+        // Liquid - coupled both ways so the car floats, slows and splashes as it drives through
+        let mut liquid_particles = ParticleVec::new();
         ShapeBuilder::from_particle_template(Particle::default().set_mass(liquid_particle_mass).set_radius(liquid_particle_radius).set_colour(Vec4::new(0.0, 0.0, 1.0, 1.0)).clone())
             .apply_operation(Rectangle::from_center_size(origin + Vec2::new(0.0, funnel_height + 1.0), Vec2::new(width, height)))
-            .create_in_simulation(level_builder_context.sim); //.create_in_particle_vec(level_builder_context.particle_vec);
+            .create_in_particle_vec(&mut liquid_particles);
+
+        level_builder_context.sim.create_fluid(&liquid_particles, *FluidParameters::new(4.0).set_solid_coupling(0.5).set_buoyancy(1.0).set_drag(0.1));
 
         // Funnel
         ShapeBuilder::from_particle_template(Particle::default().set_static(true).set_radius(funnel_particle_radius).clone())
@@ -1,4 +1,3 @@
-use rand::Rng;
 use crate::{
     core::math::{
         vec2::Vec2, 
@@ -6,15 +5,19 @@ use crate::{
     }, 
     game::level::{
         level_builder::LevelBuilderContext, 
-        level_builder_operation::LevelBuilderOperation
-    }, 
-    simulation::particles::
-        shape_builder::shape_builder::ShapeBuilder
-    
+        level_builder_operation::LevelBuilderOperation,
+        level_builder_params::{LevelBuilderParam, LevelBuilderParams}
+    }
 };
 
 pub struct HillOperation;
 
+// length of the straight pieces the ground follows the spline with
+const GROUND_SEGMENT_LENGTH: f32 = 0.5;
+
+// one height per segment, up to the maximum number of segments
+const HEIGHT_PARAMS: [&str; 4] = ["height_0", "height_1", "height_2", "height_3"];
+
 impl LevelBuilderOperation for HillOperation {
     fn type_name(&self) -> &str { "HillOperation" }
 
@@ -22,11 +25,20 @@ impl LevelBuilderOperation for HillOperation {
         Box::new(HillOperation)
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
-        let rng = &mut level_builder_context.rng;
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        let mut params = vec![
+            LevelBuilderParam::new("width", 5.0..=15.0, 10.0),
+            *LevelBuilderParam::new("segments", 2.0..=HEIGHT_PARAMS.len() as f32, 3.0).set_integer(true),
+        ];
+        for name in HEIGHT_PARAMS {
+            params.push(LevelBuilderParam::new(name, -2.5..=2.5, 0.0));
+        }
+        params
+    }
 
-        let total_width = rng.random_range(5.0..=15.0);
-        let num_segments = rng.random_range(2..=4);
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let total_width = params.get("width");
+        let num_segments = params.get_int("segments").clamp(1, HEIGHT_PARAMS.len() as i32);
         let segment_width = total_width / num_segments as f32;
         let direction = level_builder_context.x_direction;
 
@@ -40,7 +52,7 @@ impl LevelBuilderOperation for HillOperation {
             let next_x = current_pos.x + segment_width * direction;
             // Randomize height, but keep it somewhat relative to start y to avoid huge cliffs
             // Maybe trend upwards or downwards or oscillate
-            let height_variation = rng.random_range(-2.5..=2.5);
+            let height_variation = params.get(HEIGHT_PARAMS[i as usize]);
             let next_y = start_pos.y + height_variation; 
             
             // If it's the last segment, maybe bring it back to a standard level? 
@@ -68,47 +80,34 @@ impl LevelBuilderOperation for HillOperation {
             current_pos = next_pos;
         }
 
-        // Generate particles
-        let particle_radius = level_builder_context.particle_template.radius;
-
-        // adding a gap to allow wheels to grip going up hill
-        let gap_multiplier = 2.0;
-        let step_size = particle_radius * 2.0 * gap_multiplier;
-        
-        // We want to fill the area under the curve.
-        // Let's define a "floor" level. Maybe a fixed depth below the curve?
-        // Or just fill down to a certain y?
-        // Let's fill down to min(start_y, end_y) - 5.0 or something.
-        // Or just make a thick terrain layer, e.g. 5 units thick.
-        //let thickness = 1.0;
-
-        let mut sb = ShapeBuilder::from_particle_template(level_builder_context.particle_template.clone());
-        // Make it static (ground)
-        sb.particle_template.set_static(true);
-        // Set color to something earthy? Or just keep default/random?
-        // Let's set a color if we can.
-        // sb.particle_template.color = Vec4::new(0.4, 0.3, 0.1, 1.0); // Brownish
-
-        // Sample the spline
-        // We need enough samples to cover the x range with particles
-        let num_x_steps = (total_width / step_size).ceil() as usize;
-        
-        for i in 0..=num_x_steps {
-            let t = i as f32 / num_x_steps as f32;
-            let surface_point = spline.sample(t);
-            
-            // Fill column downwards
-            let num_y_steps = 1; //(thickness / step_size).ceil() as usize;
-            for j in 0..num_y_steps {
-                let y_offset = j as f32 * step_size;
-                let pos = surface_point - Vec2::new(0.0, y_offset);
-                sb.add_particle_at_position(pos);
-            }
-        }
-
-        sb.create_in_simulation(level_builder_context.sim);
+        // Follow the spline with segment colliders. Friction gives the wheels grip going up hill.
+        let num_samples = (total_width / GROUND_SEGMENT_LENGTH).ceil().max(1.0) as usize;
+        let points: Vec<Vec2> = (0..=num_samples).map(|i| spline.sample(i as f32 / num_samples as f32)).collect();
+        level_builder_context.add_ground(&points);
 
         // Update cursor
         level_builder_context.cursor = current_pos;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::game::level::{level_builder::tests::TestLevel, level_file::LevelBlock};
+
+    #[test]
+    fn test_car_climbs_hill_of_colliders() {
+        let params = BTreeMap::from([("segments".to_owned(), 3.0), ("height_0".to_owned(), 0.5), ("height_1".to_owned(), 1.0), ("height_2".to_owned(), 1.5)]);
+        let mut level = TestLevel::new(LevelBlock { operation: "HillOperation".to_owned(), params });
+
+        // the hill is solid ground without being made of particles
+        let hill_start = level.level_builder.path()[1];
+        let hill_end = level.level_builder.path()[2];
+        assert!(level.sim.colliders.iter().any(|c| c.aabb.min.x >= hill_start.x && c.aabb.max.x <= hill_end.x + 0.01));
+        assert!(level.sim.particles.as_slice().iter().all(|p| p.pos.x <= hill_start.x || p.pos.x >= hill_end.x));
+
+        let car_pos = level.drive_past(hill_end.x, 2000);
+        assert!(car_pos.x > hill_end.x && car_pos.y > hill_end.y, "{:?}", car_pos);
+    }
+}
@@ -1,37 +1,89 @@
-// use crate::{game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation}, core::math::unit_conversions::cm_to_m, simulation::particles::{particle::Particle, shape_builder::shape_builder::ShapeBuilder}};
+use crate::{core::math::{vec2::Vec2, vec4::Vec4}, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}, simulation::{constraints::{distance_constraint::DistanceConstraint, volume_constraint::VolumeConstraint}, particles::{particle::Particle, shape_builder::{line_segment::LineSegment, rectangle::Rectangle, rectangle_stick_grid::RectangleStickGrid, shape_builder::ShapeBuilder}, simulation::Simulation}}};
 
+/// A wobbly block of jelly sat on the ground for the car to push past or climb over.
+pub struct JellyCube {
+}
 
-// pub struct JellyCube {
-// }
+impl JellyCube {
+    /// Fill a rectangle with particles held together along its rows and columns, with a volume constraint around the
+    /// outside so the jelly squashes and springs back rather than folding flat. Returns the particle indices, row by row from rectangle.min.
+    pub fn create_in_simulation(sim: &mut Simulation, rectangle: Rectangle, particle_template: Particle, compliance: f32) -> Vec<usize> {
+        let particle_vec_start_index = sim.particles.len();
 
-// impl LevelBuilderOperation for JellyCube {
-//     fn type_name(&self) -> &str {"JellyCube"}
+        let mut sb = ShapeBuilder::from_particle_template(particle_template);
+        sb.apply_operation(rectangle.clone())
+            .create_in_simulation(sim);
 
-//     fn box_clone(&self) -> Box<dyn LevelBuilderOperation + Send + Sync> {
-//         Box::new(JellyCube {})
-//     }
+        for particle_handles in RectangleStickGrid::from_rectangle(rectangle.clone()).compute_particle_pairs(sb.particle_radius(), particle_vec_start_index) {
+            sim.add_distance_constraint(DistanceConstraint::from_particles(particle_handles[0], particle_handles[1], &sim.particles));
+        }
 
-//     fn default_spawn_chance(&self) -> f32 {
-//         0.5
-//     }
+        // walk around the outside of the grid for the volume constraint
+        let (x_divisions, y_divisions, _, _) = rectangle.get_divisions_and_deltas_for_radius(sb.particle_radius());
+        debug_assert!(x_divisions >= 2 && y_divisions >= 2);
+        let grid_index = |xi: usize, yi: usize| particle_vec_start_index + yi * x_divisions + xi;
 
-//     fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
-//         let rng = &mut level_builder_context.rng;
+        let mut perimeter = vec![];
+        perimeter.extend((0..x_divisions).map(|xi| grid_index(xi, 0)));
+        perimeter.extend((1..y_divisions).map(|yi| grid_index(x_divisions - 1, yi)));
+        perimeter.extend((0..(x_divisions - 1)).rev().map(|xi| grid_index(xi, y_divisions - 1)));
+        perimeter.extend((1..(y_divisions - 1)).rev().map(|yi| grid_index(0, yi)));
 
-//         let width = 0.0;
-//         let height = 0.0; //rng.gen_range(-2.0..=-0.5);
+        let volume_constraint = VolumeConstraint::new(compliance, perimeter, &sim.particles);
+        sim.add_volume_constraint(volume_constraint);
 
-//         let particle_radius = cm_to_m(4.0);
-//         let particle_mass = 1.0; //g_to_kg(0.1);
+        sb.particle_handles.iter().map(|h| h.index()).collect()
+    }
 
-//         let origin = level_builder_context.cursor;
+    /// A rectangle holding exactly `size` particles across each way, starting at first_particle and going up and along in x_direction.
+    /// Rectangle leaves off the far edges, so it is padded slightly.
+    pub fn rectangle_for_particles(first_particle: Vec2, size: [usize; 2], x_direction: f32, particle_radius: f32) -> Rectangle {
+        let extent = |n: usize| particle_radius * (2.0 * n as f32 + 0.25);
+        let mut min = first_particle;
+        if x_direction < 0.0 {
+            min.x -= particle_radius * 2.0 * (size[0] as f32 - 1.0);
+        }
+        Rectangle::from_corners(min, min + Vec2::new(extent(size[0]), extent(size[1])))
+    }
+}
 
-//         // add a jellow cube to the scene
-//         ShapeBuilder::new()
-//             .set_particle_template(Particle::default().set_mass(particle_mass).set_radius(particle_radius).set_color(Color::from(LinearRgba::RED)).clone())
-//             //.set_constraint_template(StickConstraint::default().set_stiffness_factor(1.0).clone())// this ignores mass
-//             .apply_operation(RectangleStickGrid::from_rectangle(StickConstraint::default().set_stiffness_factor(1.0).clone(), 
-//                 Rectangle::from_center_size(origin + vec2(0.0, 0.5), vec2(0.4, 0.8))))//                 //.add_stick_grid(2, 5, particle_radius * 2.2, Vec2::new(-3.0, cm_to_m(50.0)))
-//             .create_in_particle_sim(level_builder_context.particle_sim);
-//     }
-// }
+impl LevelBuilderOperation for JellyCube {
+    fn type_name(&self) -> &str {"JellyCube"}
+
+    fn box_clone(&self) -> Box<dyn LevelBuilderOperation + Send + Sync> {
+        Box::new(JellyCube {})
+    }
+
+    fn default_spawn_chance(&self) -> f32 {
+        0.5
+    }
+
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("width", 3.0..=5.0, 4.0),
+            *LevelBuilderParam::new("size", 3.0..=5.0, 4.0).set_integer(true), // particles along each side of the cube
+        ]
+    }
+
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let width = params.get("width");
+        let size = params.get_int("size").max(2) as usize;
+        let particle_radius = level_builder_context.particle_template.radius;
+        let x_direction = level_builder_context.x_direction;
+
+        let cursor_start = level_builder_context.cursor;
+        let cursor_end = cursor_start + Vec2::new(width * x_direction, 0.0);
+
+        ShapeBuilder::from_particle_template(*level_builder_context.particle_template.clone().set_static(true))
+            .apply_operation(LineSegment::new(cursor_start, cursor_end))
+            .create_in_simulation(level_builder_context.sim);
+
+        // add a jelly cube to the scene, sat on the ground half way along
+        let first_particle = cursor_start + Vec2::new(width * 0.5 * x_direction, particle_radius * 2.0);
+        let rectangle = JellyCube::rectangle_for_particles(first_particle, [size, size], x_direction, particle_radius);
+        let particle_template = *level_builder_context.particle_template.clone().set_mass(0.2).set_colour(Vec4::RED);
+        JellyCube::create_in_simulation(level_builder_context.sim, rectangle, particle_template, 0.0001);
+
+        level_builder_context.cursor = cursor_end;
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{core::math::{vec2::Vec2, vec4::Vec4}, game::level::{level_blocks::jelly_cube::JellyCube, level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
+
+// particles across the thickness of the bridge, any thinner and it sags into the gap under the car
+const THICKNESS_PARTICLES: usize = 5;
+
+// height of the lip at the near edge of the gap, in particles. Tall enough that the car can't shove the foot of the bridge over it
+const LIP_PARTICLES: usize = 3;
+
+// how far the bridge reaches past the far side of the gap once it has fallen
+const OVERHANG: f32 = 1.0;
+
+/// A tall slab of jelly standing at the edge of a gap. The car drives into it and knocks it over,
+/// and it lands across the gap as a bridge to drive over.
+pub struct JellyDrawbridgeOperation {
+}
+
+impl LevelBuilderOperation for JellyDrawbridgeOperation {
+    fn type_name(&self) -> &str {"JellyDrawbridgeOperation"}
+
+    fn box_clone(&self) -> Box<dyn LevelBuilderOperation + Send + Sync> {
+        Box::new(JellyDrawbridgeOperation {})
+    }
+
+    fn difficulty(&self) -> f32 {
+        1.5
+    }
+
+    fn default_spawn_chance(&self) -> f32 {
+        0.5
+    }
+
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("gap", 1.5..=2.5, 2.0),
+            LevelBuilderParam::new("run_up", 2.0..=3.0, 2.5),
+            LevelBuilderParam::new("depth", 2.0..=3.0, 2.5), // of the pit under the gap, deep enough that falling in is the end of the run
+        ]
+    }
+
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let gap = params.get("gap");
+        let particle_radius = level_builder_context.particle_template.radius;
+        let x_direction = level_builder_context.x_direction;
+
+        let cursor_start = level_builder_context.cursor;
+        let near_edge = cursor_start + Vec2::new(params.get("run_up") * x_direction, 0.0);
+        let far_edge = near_edge + Vec2::new(gap * x_direction, 0.0);
+        let cursor_end = far_edge + Vec2::new((OVERHANG + 1.0) * x_direction, 0.0);
+        let down = Vec2::new(0.0, -params.get("depth"));
+
+        // Ground and pit, with a lip at the near edge for the foot of the bridge to pivot on so it topples rather than slides in
+        // The walls are brought in by a particle radius to where the inside of a particle wall would be.
+        let inset = Vec2::new(particle_radius * x_direction, 0.0);
+        let (wall_start, wall_end) = (near_edge + inset, far_edge - inset);
+        level_builder_context.add_ground(&[cursor_start, wall_start, wall_start + down, wall_end + down, wall_end, cursor_end]);
+
+        let lip = LineSegment::new(near_edge + Vec2::new(0.0, particle_radius * 2.0), near_edge + Vec2::new(0.0, particle_radius * 2.0 * LIP_PARTICLES as f32));
+        ShapeBuilder::from_particle_template(*level_builder_context.particle_template.clone().set_static(true))
+            .apply_operation(lip)
+            .create_in_simulation(level_builder_context.sim);
+
+        // Bridge. Tall enough to reach over the gap when it falls.
+        let rows = ((gap + OVERHANG) / (particle_radius * 2.0)).ceil() as usize;
+        let first_particle = near_edge + Vec2::new(-particle_radius * 2.0 * THICKNESS_PARTICLES as f32 * x_direction, particle_radius * 2.0);
+        let rectangle = JellyCube::rectangle_for_particles(first_particle, [THICKNESS_PARTICLES, rows], x_direction, particle_radius);
+
+        let mut particle_template = *level_builder_context.particle_template.clone().set_mass(0.15).set_colour(Vec4::new(0.9, 0.3, 0.3, 1.0));
+        // grippy so it doesn't slide off the far side once it lands
+        particle_template.s_friction = 0.8;
+        particle_template.k_friction = 0.8;
+        JellyCube::create_in_simulation(level_builder_context.sim, rectangle, particle_template, 0.0001);
+
+        level_builder_context.cursor = cursor_end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::level::level_builder::tests::TestLevel, simulation::particles::simulation::Simulation};
+
+    #[test]
+    fn test_car_topples_drawbridge_across_gap() {
+        let mut level = TestLevel::from_operation("JellyDrawbridgeOperation");
+
+        // the bridge is the only thing in the level held together by a volume constraint
+        let bridge = level.sim.volume_constraints.0[0].particle_indices.clone();
+        let bridge_height = |sim: &Simulation| bridge.iter().map(|&i| sim.particles[i].pos.y).fold(f32::MIN, f32::max);
+        let standing_height = bridge_height(&level.sim);
+        let far_edge = level.level_builder.path()[2].x - 2.0;
+
+        // the car has driven across the gap
+        let car_pos = level.drive_past(far_edge + 0.5, 1500);
+        assert!(car_pos.x > far_edge && car_pos.y > 0.0, "{:?}", car_pos);
+
+        // over the bridge, which has fallen across it
+        assert!(bridge_height(&level.sim) < standing_height * 0.5, "{}", bridge_height(&level.sim));
+    }
+}
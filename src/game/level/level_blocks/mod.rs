@@ -8,4 +8,10 @@ pub mod straight_level_block;
 pub mod finish_operation;
 pub mod elevator;
 pub mod water_balloon_drop;
-pub mod hill_operation;
\ No newline at end of file
+pub mod hill_operation;
+pub mod windmill;
+pub mod cloth_curtain;
+pub mod boulder_gap;
+pub mod toothed_incline;
+pub mod jelly_drawbridge;
+pub mod flexible_pipe;
\ No newline at end of file
@@ -1,6 +1,4 @@
-use rand::Rng;
-
-use crate::{core::math::{vec2::Vec2, vec4::Vec4}, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation}, simulation::{constraints::distance_constraint::DistanceConstraint, particles::shape_builder::{rectangle::Rectangle, rectangle_stick_grid::RectangleStickGrid, shape_builder::ShapeBuilder}}};
+use crate::{core::math::{vec2::Vec2, vec4::Vec4}, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}, simulation::{constraints::distance_constraint::DistanceConstraint, particles::shape_builder::{rectangle::Rectangle, rectangle_stick_grid::RectangleStickGrid, shape_builder::ShapeBuilder}}};
 
 
 pub struct SaggyBridgeOperation {
@@ -13,10 +11,18 @@ impl LevelBuilderOperation for SaggyBridgeOperation {
         Box::new(SaggyBridgeOperation {})
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
-        let rng = &mut level_builder_context.rng;
+    fn difficulty(&self) -> f32 {
+        1.5
+    }
+
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("width", 2.0..=5.0, 3.5),
+        ]
+    }
 
-        let width = rng.random_range(2.0..=5.0);
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let width = params.get("width");
         let height = 0.0;
 
         let rect_height = level_builder_context.particle_template.radius * 8.0; //4.0; 4 = 2 particle thickness
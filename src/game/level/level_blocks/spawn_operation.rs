@@ -1,4 +1,4 @@
-use crate::{core::math::vec2::Vec2, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
+use crate::{core::math::vec2::Vec2, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::LevelBuilderParams}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
 
 
 pub struct SpawnOperation {
@@ -11,6 +11,10 @@ impl LevelBuilderOperation for SpawnOperation {
         Box::new(SpawnOperation {})
     }
 
+    fn difficulty(&self) -> f32 {
+        0.0
+    }
+
     fn prepare(&self, level_builder_context: &mut LevelBuilderContext, level_builder_operations: &mut Vec<(f32, Box<dyn LevelBuilderOperation + Send + Sync>)>) {
         // ensure that we are always the first operation that gets applied
         // and is never used outside of that range
@@ -29,7 +33,7 @@ impl LevelBuilderOperation for SpawnOperation {
         }
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, _params: &LevelBuilderParams) {
         let width = 4.0;
         let height = 0.0;
 
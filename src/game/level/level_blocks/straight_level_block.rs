@@ -1,6 +1,4 @@
-use rand::Rng;
-
-use crate::{core::math::vec2::Vec2, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation}, simulation::particles::shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}};
+use crate::{core::math::vec2::Vec2, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}};
 
 pub struct StraightLevelBlock {
 }
@@ -12,7 +10,18 @@ impl LevelBuilderOperation for StraightLevelBlock {
         Box::new(StraightLevelBlock {})
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
+    fn difficulty(&self) -> f32 {
+        0.5
+    }
+
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("width", 5.0..=10.0, 7.5),
+            LevelBuilderParam::new("height", -1.5..=1.5, 0.0),
+        ]
+    }
+
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
         // https://bevyengine.org/examples/2d-rendering/2d-shapes/
         // https://bevyengine.org/examples/3d-rendering/3d-shapes/
         // let commands = &mut level_builder_context.commands;
@@ -30,8 +39,8 @@ impl LevelBuilderOperation for StraightLevelBlock {
         //     1.0,
         // );
 
-        let width = level_builder_context.rng.random_range(5.0..=10.0);
-        let height = level_builder_context.rng.random_range(-1.5..=1.5);
+        let width = params.get("width");
+        let height = params.get("height");
  
  /* 
         // todo: https://github.com/bevyengine/bevy/discussions/15280
@@ -56,17 +65,7 @@ impl LevelBuilderOperation for StraightLevelBlock {
         let cursor_start = level_builder_context.cursor;
         let cursor_end = cursor_start + Vec2::new(width * level_builder_context.x_direction, height);
 
-        // Get the current length of particle_vec, as we are about to push on more particles
-        // let particle_vec_start_index = level_builder_context.particle_vec.len();
-        
-        ShapeBuilder::from_particle_template(*level_builder_context.particle_template.clone().set_mass(0.0))
-            .apply_operation(LineSegment::new(level_builder_context.cursor, cursor_end)) 
-            .create_in_simulation(level_builder_context.sim); //.create_in_particle_vec(level_builder_context.particle_vec);
-
-        // Now we have pushed in more particles that have proper particle indicies, we take a slice of the new particles
-        // and hand them off to create an array of fixed springs constraints for this slice of particles
-        // let fixed_point_spring_vec = FixedPointSpringVec::from_existing_particle_positions(&level_builder_context.particle_vec.0[particle_vec_start_index..]);
-        // level_builder_context.entity_system.push(FixedPointSpringVecEntity::new(fixed_point_spring_vec));
+        level_builder_context.add_ground(&[cursor_start, cursor_end]);
 
         // Update the cursor to the right side of the spawned rectangle
         level_builder_context.cursor = cursor_end;
@@ -0,0 +1,144 @@
+use crate::{core::math::{vec2::Vec2, vec4::Vec4}, game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}, simulation::{constraints::spring_constraint::SpringConstraint, particles::{particle::COLLISION_LAYER_SOFT_BODY, shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}}}};
+
+// particles in each grass stalk
+const STALK_PARTICLES: usize = 3;
+
+// ground particles either side of a stalk's root that it is sprung to, to keep it standing up
+const STALK_ANCHOR_OFFSET: usize = 2;
+
+/// A steep incline covered in either ridged teeth or springy grass stalks for the tyres to dig into.
+/// The ground is slippery between them, so a car that crawls up slides back down. It needs a run up to make it.
+pub struct ToothedInclineOperation {
+}
+
+impl LevelBuilderOperation for ToothedInclineOperation {
+    fn type_name(&self) -> &str {"ToothedInclineOperation"}
+
+    fn box_clone(&self) -> Box<dyn LevelBuilderOperation + Send + Sync> {
+        Box::new(ToothedInclineOperation {})
+    }
+
+    fn difficulty(&self) -> f32 {
+        1.5
+    }
+
+    fn default_spawn_chance(&self) -> f32 {
+        0.5
+    }
+
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("run_up", 2.0..=4.0, 3.0),
+            LevelBuilderParam::new("length", 3.0..=5.0, 4.0), // horizontal distance covered by the slope
+            LevelBuilderParam::new("height", 1.5..=2.5, 2.0),
+            *LevelBuilderParam::new("grass", 0.0..=1.0, 0.0).set_integer(true), // 0 for ridged teeth, 1 for springy grass stalks
+            LevelBuilderParam::new("spacing", 0.4..=0.8, 0.6), // between teeth or stalks
+            LevelBuilderParam::new("friction", 0.2..=0.35, 0.3), // of the slope. Any grippier and the car can crawl up without a run up
+        ]
+    }
+
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let particle_radius = level_builder_context.particle_template.radius;
+        let x_direction = level_builder_context.x_direction;
+
+        let cursor_start = level_builder_context.cursor;
+        let slope_start = cursor_start + Vec2::new(params.get("run_up") * x_direction, 0.0);
+        let slope_end = slope_start + Vec2::new(params.get("length") * x_direction, params.get("height"));
+        let cursor_end = slope_end + Vec2::new(1.0 * x_direction, 0.0);
+
+        // pointing out of the slope, towards the sky
+        let tangent = (slope_end - slope_start).normalize();
+        let normal = Vec2::new(-tangent.y * x_direction, tangent.x * x_direction);
+
+        // Run up and the flat at the top
+        level_builder_context.add_ground(&[cursor_start, slope_start]);
+        level_builder_context.add_ground(&[slope_end, cursor_end]);
+
+        // Slope. Particles rather than ground, as the grass stalks are sprung to them
+        let mut slope_template = *level_builder_context.particle_template.clone().set_static(true);
+        slope_template.s_friction = params.get("friction");
+        slope_template.k_friction = params.get("friction");
+
+        let mut slope_sb = ShapeBuilder::from_particle_template(slope_template);
+        slope_sb.apply_operation(LineSegment::new(slope_start, slope_end))
+            .create_in_simulation(level_builder_context.sim);
+
+        // Teeth or stalks go on every n-th slope particle, leaving the ends clear so the car can get on and off
+        let step = ((params.get("spacing") / (particle_radius * 2.0)).round() as usize).max(STALK_ANCHOR_OFFSET * 2);
+        let roots: Vec<usize> = (STALK_ANCHOR_OFFSET..slope_sb.particle_handles.len().saturating_sub(STALK_ANCHOR_OFFSET)).step_by(step).collect();
+
+        if params.get_int("grass") == 0 {
+            // Ridges, grippy so the tyres bite on them
+            let mut tooth_template = *level_builder_context.particle_template.clone().set_static(true).set_colour(Vec4::new(0.5, 0.5, 0.5, 1.0));
+            tooth_template.s_friction = 1.0;
+            tooth_template.k_friction = 1.0;
+
+            let mut sb = ShapeBuilder::from_particle_template(tooth_template);
+            for &root in roots.iter() {
+                sb.add_particle_at_position(slope_sb.particles[root].pos + normal * particle_radius * 2.0);
+            }
+            sb.create_in_simulation(level_builder_context.sim);
+        } else {
+            // Grass. Each stalk is a chain of light particles sprung to the slope either side of its root, so it bends
+            // under the tyres and springs back up.
+            let mut stalk_template = *level_builder_context.particle_template.clone().set_mass(0.2).set_colour(Vec4::new(0.2, 0.7, 0.2, 1.0));
+            stalk_template.s_friction = 1.0;
+            stalk_template.k_friction = 1.0;
+            stalk_template.set_collision_layer(COLLISION_LAYER_SOFT_BODY, !COLLISION_LAYER_SOFT_BODY); // Prevent self-collision
+
+            let stiffness = 2000.0;
+            for &root in roots.iter() {
+                let mut sb = ShapeBuilder::from_particle_template(stalk_template);
+                for i in 0..STALK_PARTICLES {
+                    sb.add_particle_at_position(slope_sb.particles[root].pos + normal * particle_radius * 2.0 * (i + 1) as f32);
+                }
+                sb.create_in_simulation(level_builder_context.sim);
+
+                let anchors = [
+                    slope_sb.particle_handles[root - STALK_ANCHOR_OFFSET].index(),
+                    slope_sb.particle_handles[root + STALK_ANCHOR_OFFSET].index(),
+                ];
+                for (i, particle_handle) in sb.particle_handles.iter().enumerate() {
+                    for anchor in anchors {
+                        let spring = SpringConstraint::from_particles(anchor, particle_handle.index(), &level_builder_context.sim.particles, stiffness);
+                        level_builder_context.sim.add_spring_constraint(spring);
+                    }
+                    if i > 0 {
+                        let spring = SpringConstraint::from_particles(sb.particle_handles[i - 1].index(), particle_handle.index(), &level_builder_context.sim.particles, stiffness);
+                        level_builder_context.sim.add_spring_constraint(spring);
+                    }
+                }
+            }
+        }
+
+        level_builder_context.cursor = cursor_end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use crate::game::level::{level_builder::tests::TestLevel, level_file::LevelBlock};
+
+    #[test]
+    fn test_grass_stalks_stand_up() {
+        let mut level = TestLevel::new(LevelBlock { operation: "ToothedInclineOperation".to_owned(), params: BTreeMap::from([("grass".to_owned(), 1.0)]) });
+
+        // the slope climbs by its height
+        let path = level.level_builder.path();
+        assert!((path[2].y - path[1].y - 2.0).abs() < 0.001);
+
+        // the stalks are the only springs in the level, and the only particles that can move
+        assert!(level.sim.spring_constraints.0.len() > 0);
+        let stalks: Vec<usize> = level.sim.spring_constraints.0.iter().map(|c| c.i2).collect();
+        assert!(stalks.iter().all(|&i| level.sim.particles[i].imass != 0.0));
+        let rest_heights: Vec<f32> = stalks.iter().map(|&i| level.sim.particles[i].pos.y).collect();
+
+        level.step(400);
+
+        // they droop a little under their own weight, but don't collapse
+        for (&i, rest_height) in stalks.iter().zip(rest_heights) {
+            assert!(level.sim.particles[i].pos.y > rest_height - 0.1, "{} sagged to {}", i, level.sim.particles[i].pos.y);
+        }
+    }
+}
@@ -2,9 +2,9 @@ use rand::Rng;
 
 use crate::{
     core::math::{vec2::Vec2, vec4::Vec4},
-    game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation},
+    game::level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}},
     simulation::
-        particles::{particle::Particle, particle_vec::ParticleVec, shape_builder::{adjacent_sticks::AdjacentSticks, circle::{Circle, SpaceDistribution}, shape_builder::ShapeBuilder}, simulation::Simulation}
+        particles::{fluid_parameters::FluidParameters, particle::{Particle, COLLISION_LAYER_SOFT_BODY}, particle_vec::ParticleVec, shape_builder::{adjacent_sticks::AdjacentSticks, circle::{Circle, SpaceDistribution}, shape_builder::ShapeBuilder}, simulation::Simulation}
     ,
 };
 
@@ -33,7 +33,7 @@ impl WaterBalloonDrop {
                 .set_radius(particle_rad)
                 //.set_pos(Vec2::new(f32::sin(angle), f32::cos(angle)) * balloon_radius + center)
                 .set_mass(1.0);
-            part.body = -2; // Prevent self-collision
+            part.set_collision_layer(COLLISION_LAYER_SOFT_BODY, !COLLISION_LAYER_SOFT_BODY); // Prevent self-collision
 
         let mut sb = ShapeBuilder::from_particle_template(part);
         sb.apply_operation(Circle::new(center, balloon_radius, SpaceDistribution::AdjustRadius))
@@ -62,7 +62,7 @@ impl WaterBalloonDrop {
         println!("water ballon has {} particles", particles.len());
 
         // Higher density lets particles get closer together
-        sim.create_fluid(&particles, 4.0);
+        sim.create_fluid(&particles, FluidParameters::new(4.0));
     }
 }
 
@@ -79,16 +79,23 @@ impl LevelBuilderOperation for WaterBalloonDrop {
         0.3
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext) {
-        let rng = &mut level_builder_context.rng;
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            LevelBuilderParam::new("balloon_radius", 0.5..=1.0, 0.75),
+            LevelBuilderParam::new("x_offset", -1.0..=1.0, 0.0),
+            LevelBuilderParam::new("y_offset", 2.0..=2.0, 2.0),
+        ]
+    }
+
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
         let particle_rad = level_builder_context.particle_template.radius;
         
         // Randomize balloon size (smaller than original)
-        let balloon_radius = rng.random_range(0.5..=1.0);
+        let balloon_radius = params.get("balloon_radius");
         
         // Randomize position offset from cursor
-        let x_offset = rng.random_range(-1.0..=1.0);
-        let y_offset = rng.random_range(2.0..=2.0);
+        let x_offset = params.get("x_offset");
+        let y_offset = params.get("y_offset");
         
         let balloon_center = level_builder_context.cursor + Vec2::new(x_offset, y_offset);
         
@@ -98,7 +105,7 @@ impl LevelBuilderOperation for WaterBalloonDrop {
             balloon_center,
             particle_rad,
             balloon_radius,
-            rng
+            level_builder_context.rng
         );
         
         // // 50% chance to spawn a second balloon
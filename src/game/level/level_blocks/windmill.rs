@@ -0,0 +1,191 @@
+use std::{any::Any, f32::consts::PI};
+
+use crate::{core::math::{aabb2d::Aabb2d, vec2::Vec2, vec4::Vec4}, game::{entity::{entity::Entity, entity_system::UpdateContext}, level::{level_builder::LevelBuilderContext, level_builder_operation::LevelBuilderOperation, level_builder_params::{LevelBuilderParam, LevelBuilderParams}}}, simulation::particles::{sdf_data::SdfData, shape_builder::{line_segment::LineSegment, shape_builder::ShapeBuilder}, simulation::Simulation}};
+
+/// A windmill of paddles spinning over flat ground. The paddle tips sweep the ground the car drives along,
+/// so the car has to time its pass between them, or hop on and ride one over.
+pub struct WindmillOperation {
+}
+
+impl LevelBuilderOperation for WindmillOperation {
+    fn type_name(&self) -> &str {"WindmillOperation"}
+
+    fn box_clone(&self) -> Box<dyn LevelBuilderOperation + Send + Sync> {
+        Box::new(WindmillOperation {})
+    }
+
+    fn difficulty(&self) -> f32 {
+        2.0
+    }
+
+    fn estimated_time(&self, params: &LevelBuilderParams) -> f32 {
+        // on average we wait half the time between paddles for a gap
+        let time_between_paddles = 2.0 * PI / (params.get("speed") * params.get("paddles"));
+        time_between_paddles * 0.5
+    }
+
+    fn default_spawn_chance(&self) -> f32 {
+        0.5
+    }
+
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![
+            *LevelBuilderParam::new("paddles", 2.0..=4.0, 3.0).set_integer(true),
+            LevelBuilderParam::new("radius", 1.5..=2.5, 2.0), // from the hub to the paddle tips
+            LevelBuilderParam::new("tip_height", 0.2..=0.5, 0.3), // gap between the paddle tips and the ground
+            LevelBuilderParam::new("speed", 0.5..=1.5, 1.0), // in radians per second
+        ]
+    }
+
+    fn aabb(&self, level_builder_context: &LevelBuilderContext, params: &LevelBuilderParams, built: Aabb2d) -> Aabb2d {
+        // the paddles sweep out a whole circle as they turn
+        let radius = params.get("radius");
+        let width = radius * 2.0 + 3.0;
+        let hub = level_builder_context.cursor + Vec2::new(-width * 0.5 * level_builder_context.x_direction, radius + params.get("tip_height"));
+        built.merge(&Aabb2d::from_point_cloud(&[hub]).grow(Vec2::new(radius, radius)))
+    }
+
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams) {
+        let num_paddles = params.get_int("paddles");
+        let radius = params.get("radius");
+        let particle_radius = level_builder_context.particle_template.radius;
+
+        // leave some room either side of the paddles to get ready to go
+        let width = radius * 2.0 + 3.0;
+        let horizontal_movement = Vec2::new(width * level_builder_context.x_direction, 0.0);
+
+        let cursor_start = level_builder_context.cursor;
+        let cursor_end = cursor_start + horizontal_movement;
+        let hub = cursor_start + horizontal_movement * 0.5 + Vec2::new(0.0, radius + params.get("tip_height"));
+
+        // Ground:
+        level_builder_context.add_ground(&[cursor_start, cursor_end]);
+
+        // Paddles. There is no particle on the hub itself, as Body does not count particles at its centre of mass towards it.
+        let mut paddles = ShapeBuilder::from_particle_template(*level_builder_context.particle_template.clone().set_mass(2.0).set_colour(Vec4::GREEN));
+        for i in 0..num_paddles {
+            let direction = Vec2::rotate_rad(Vec2::new(0.0, -1.0), 2.0 * PI * i as f32 / num_paddles as f32);
+            paddles.apply_operation(LineSegment::new(hub + direction * particle_radius * 2.0, hub + direction * (radius - particle_radius)));
+        }
+
+        // The paddles are thin, so there is no inside for the signed distance field to describe.
+        // A negative distance has contacts treat them as plain particles.
+        let mut particle_vec = paddles.particles.clone();
+        let sdf_data = vec![SdfData::new(Vec2::new(0.0, 0.0), -1.0); particle_vec.len()];
+        let body_index = level_builder_context.sim.create_rigid_body(&mut particle_vec, &sdf_data);
+
+        level_builder_context.entity_system.entities.register(WindmillEntity {
+            body_index,
+            hub,
+            // paddles at the bottom sweep the way we are heading
+            speed: params.get("speed") * level_builder_context.x_direction,
+            max_acceleration: 2.0,
+        });
+
+        level_builder_context.cursor = cursor_end;
+    }
+}
+
+#[derive(Clone)]
+pub struct WindmillEntity {
+    body_index: usize,
+    hub: Vec2,
+    speed: f32, // target angular velocity, in radians per second, anti-clockwise
+    max_acceleration: f32, // how hard the motor can push, in radians per second per second, so the car can hold it back
+}
+
+impl WindmillEntity {
+    fn angular_velocity(&self, sim: &Simulation) -> f32 {
+        let mut angular_momentum = 0.0;
+        let mut inertia = 0.0;
+        for &pi in &sim.bodies[self.body_index].particle_indicies {
+            let p = &sim.particles[pi];
+            let r = p.pos - self.hub;
+            angular_momentum += p.mass * (r.x * p.vel.y - r.y * p.vel.x);
+            inertia += p.mass * r.dot(r);
+        }
+
+        if inertia > 0.0 { angular_momentum / inertia } else { 0.0 }
+    }
+}
+
+impl Entity for WindmillEntity {
+    fn type_name(&self) -> &str {"WindmillEntity"}
+
+    fn box_clone(&self) -> Box<dyn Entity + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    /// The motor. Spin the paddles up (or down) towards the target speed.
+    fn update(&mut self, context: &mut UpdateContext) {
+        let angular_velocity = self.angular_velocity(context.sim);
+        let max_change = self.max_acceleration * context.time_delta;
+        let change = (self.speed - angular_velocity).clamp(-max_change, max_change);
+
+        for &pi in &context.sim.bodies[self.body_index].particle_indicies {
+            let p = &mut context.sim.particles[pi];
+            let r = p.pos - self.hub;
+            p.vel += Vec2::new(-r.y, r.x) * change;
+        }
+    }
+
+    /// The pin. Move the paddles back so the centre of mass stays on the hub, which leaves them free to turn.
+    fn solve_constraints(&mut self, sim: &mut Simulation, _time_delta: f32) {
+        let body = &sim.bodies[self.body_index];
+
+        let mut total_mass = 0.0;
+        let mut centre = Vec2::new(0.0, 0.0);
+        for &pi in &body.particle_indicies {
+            let p = &sim.particles[pi];
+            centre += p.pos_guess * p.mass;
+            total_mass += p.mass;
+        }
+        let offset = self.hub - centre / total_mass;
+
+        for &pi in &body.particle_indicies {
+            sim.particles[pi].pos_guess += offset;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+    use crate::{engine::app::camera::Camera, game::{entity::entity_system::EntitySystem, level::{level_builder::LevelBuilder, level_file::{LevelBlock, LevelFile}}}, simulation::particles::particle_vec::ParticleVec};
+
+    #[test]
+    fn test_windmill_spins_around_hub() {
+        let level_file = LevelFile { blocks: vec![LevelBlock { operation: "WindmillOperation".to_owned(), ..Default::default() }], ..Default::default() };
+
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        LevelBuilder::default().generate_level_from_file(&level_file, &mut entity_system, &mut particle_vec, &mut sim).unwrap();
+
+        let windmill = entity_system.entities.find::<WindmillEntity>().next().unwrap().clone();
+        let tip = *sim.bodies[windmill.body_index].particle_indicies.last().unwrap();
+        let tip_start = sim.particles[tip].pos;
+
+        let mut camera = Camera::headless(1.0);
+        let time_delta = 0.005;
+        for i in 0..400 {
+            entity_system.step_simulation(&mut sim, time_delta);
+            entity_system.update(&mut particle_vec, &mut sim, &mut camera, time_delta, i as f32 * time_delta);
+        }
+
+        // held on the hub rather than falling, and spun up to speed
+        assert!(((sim.particles[tip].pos - windmill.hub).magnitude() - (tip_start - windmill.hub).magnitude()).abs() < 0.05);
+        assert!((sim.particles[tip].pos - tip_start).magnitude() > 1.0);
+        assert!((windmill.angular_velocity(&sim) - windmill.speed).abs() < 0.1);
+    }
+}
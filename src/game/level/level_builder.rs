@@ -1,18 +1,59 @@
 use rand_pcg::Pcg64;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use std::io;
 
-use crate::{core::math::{random::Random, unit_conversions::cm_to_m, vec2::Vec2}, game::{entity::entity_system::EntitySystem, level::{level_blocks::{cliff_operation::CliffOperation, drop_direction_reverse::DropDirectionReverse, elevator::ElevatorOperation, finish_operation::FinishOperation, fluid_funnel::FluidFunnel, hill_operation::HillOperation, saggy_bridge_operation::SaggyBridgeOperation, spawn_operation::SpawnOperation, straight_level_block::StraightLevelBlock, water_balloon_drop::WaterBalloonDrop}, level_builder_operation::LevelBuilderOperation, level_builder_operation_registry::LevelBuilderOperationRegistry}}, simulation::particles::{particle::Particle, particle_vec::ParticleVec, simulation::Simulation}};
+use crate::{core::math::{aabb2d::Aabb2d, unit_conversions::cm_to_m, vec2::Vec2}, game::{entity::{entities::{checkpoint_entity::CheckpointEntity, trigger_entity::{TriggerEntity, TriggerShape}}, entity_system::EntitySystem}, level::{level_blocks::{cliff_operation::CliffOperation, boulder_gap::BoulderGapOperation, cloth_curtain::ClothCurtainOperation, drop_direction_reverse::DropDirectionReverse, elevator::ElevatorOperation, finish_operation::FinishOperation, flexible_pipe::FlexiblePipeOperation, fluid_funnel::FluidFunnel, hill_operation::HillOperation, jelly_cube::JellyCube, jelly_drawbridge::JellyDrawbridgeOperation, saggy_bridge_operation::SaggyBridgeOperation, spawn_operation::SpawnOperation, straight_level_block::StraightLevelBlock, toothed_incline::ToothedInclineOperation, water_balloon_drop::WaterBalloonDrop, windmill::WindmillOperation}, level_builder_operation::LevelBuilderOperation, level_builder_operation_registry::LevelBuilderOperationRegistry, level_builder_params::LevelBuilderParams, level_checker::{LevelCheckResult, LevelChecker}, level_file::{LevelBlock, LevelFile}, level_goal::{LevelGoal, LevelProgress, ESTIMATED_CAR_SPEED}, level_seed::LevelSeed}}, simulation::{colliders::collider::Collider, particles::{particle::Particle, particle_vec::ParticleVec, simulation::Simulation}}};
+
+// headroom the car needs above a block, so blocks are not stacked too closely
+const CLEARANCE: f32 = 1.5;
+
+// how far below its surface ground laid by add_ground still pushes particles back out the top
+const GROUND_THICKNESS: f32 = 0.5;
+
+// how far below the cursor a checkpoint reaches, for blocks that end with the car lower than the cursor, e.g. in water
+const CHECKPOINT_DEPTH: f32 = 0.5;
+
+// how far particles can get from the level before they are removed, e.g. debris that fell off it
+const DESPAWN_MARGIN: f32 = 20.0;
+
+// candidate blocks tried while planning before giving up on avoiding overlaps
+const MAX_ATTEMPTS: usize = 400;
+
+// times an operation may fail to fit at one position (with different params) before it is ruled out there
+const MAX_ATTEMPTS_PER_OPERATION: usize = 3;
 
 pub struct LevelBuilder {
     level_builder_operations_registry: LevelBuilderOperationRegistry,
+    level_file: LevelFile, // the last level built, ready to export
+    path: Vec<Vec2>, // the cursor at the start of the last level built and after each block
+    reroll_unsolvable: Option<(LevelChecker, usize)>, // checker and max tries, to keep generating levels from a seed until one can be finished
 }
 
 impl LevelBuilder {
     pub fn new(level_builder_operations_registry: LevelBuilderOperationRegistry) -> Self {
         Self {
             level_builder_operations_registry,
+            level_file: LevelFile::default(),
+            path: vec![],
+            reroll_unsolvable: None,
         }
     }
+
+    /// Have generate_level_from_seed keep generating levels until the level checker can finish one, giving up after max_tries
+    pub fn set_reroll_unsolvable(&mut self, level_checker: LevelChecker, max_tries: usize) -> &mut Self {
+        self.reroll_unsolvable = Some((level_checker, max_tries));
+        self
+    }
+
+    /// The last level generated or loaded, with every block parameter resolved
+    pub fn level_file(&self) -> &LevelFile {
+        &self.level_file
+    }
+
+    /// The route through the last level built, which the level checker measures progress along
+    pub fn path(&self) -> &[Vec2] {
+        &self.path
+    }
 }
 
 pub struct LevelBuilderContext<'a> {
@@ -24,6 +65,7 @@ pub struct LevelBuilderContext<'a> {
     pub operations: Vec<Box<dyn LevelBuilderOperation + Send + Sync>>,
     pub is_first: bool,
     pub is_last: bool,
+    pub aabbs: Vec<Aabb2d>, // space occupied by each block so far, as reported by its operation
     pub rng: &'a mut Pcg64,
     pub entity_system: &'a mut EntitySystem,
     pub sim: &'a mut Simulation,
@@ -42,73 +84,357 @@ impl<'a> LevelBuilderContext<'a> {
             operations: vec![],
             is_first: true,
             is_last: false,
+            aabbs: vec![],
             rng,
             entity_system,
             sim
         }
     }
+
+    /// Would a block occupying aabb run into an earlier block or the headroom above it? The previous block is skipped as it always touches the next one.
+    pub fn intersects_existing(&self, aabb: &Aabb2d) -> bool {
+        let num_earlier = self.aabbs.len().saturating_sub(1);
+        self.intersects(aabb, &self.aabbs[..num_earlier])
+    }
+
+    /// Would anything occupying aabb run into any block so far, including the previous one, or the headroom above them?
+    pub fn intersects_any(&self, aabb: &Aabb2d) -> bool {
+        self.intersects(aabb, &self.aabbs)
+    }
+
+    fn intersects(&self, aabb: &Aabb2d, blocks: &[Aabb2d]) -> bool {
+        let margin = Vec2::new(self.particle_template.radius * 2.0, self.particle_template.radius * 2.0);
+        let with_clearance = |aabb: &Aabb2d| Aabb2d { min: aabb.min, max: aabb.max + Vec2::new(0.0, CLEARANCE) }.grow(-margin);
+
+        let aabb = with_clearance(aabb);
+        blocks.iter().any(|existing| with_clearance(existing).intersects(&aabb))
+    }
+
+    fn save_cursor(&self) -> LevelBuilderCursor {
+        LevelBuilderCursor {
+            cursor: self.cursor,
+            x_direction: self.x_direction,
+            x_direction_changed: self.x_direction_changed,
+            particle_template: self.particle_template,
+        }
+    }
+
+    fn restore_cursor(&mut self, saved: LevelBuilderCursor) {
+        self.cursor = saved.cursor;
+        self.x_direction = saved.x_direction;
+        self.x_direction_changed = saved.x_direction_changed;
+        self.particle_template = saved.particle_template;
+    }
+
+    /// Drop a checkpoint at the cursor, reaching up high enough to catch the car driving past
+    pub fn add_checkpoint(&mut self) {
+        let half_width = self.particle_template.radius * 2.0;
+        let aabb = Aabb2d {
+            min: self.cursor + Vec2::new(-half_width, -CHECKPOINT_DEPTH),
+            max: self.cursor + Vec2::new(half_width, CLEARANCE * 2.0),
+        };
+        let respawn_position = self.cursor + Vec2::new(0.0, 1.0); // the car starts this high above the spawn
+        let trigger = self.entity_system.trigger_entity_system.push(TriggerEntity::new(TriggerShape::Aabb(aabb)));
+        self.entity_system.checkpoint_entity_system.push(CheckpointEntity::new(trigger, respawn_position));
+    }
+
+    /// Lay static ground along a polyline as segment colliders rather than particles, with the points in the order the car drives over them.
+    /// The ground is solid on the car's right, e.g. below a floor and behind the walls of a pit, so it can't be driven through from above.
+    /// The segments are raised by the particle radius so they sit flush with the tops of blocks built from particles.
+    pub fn add_ground(&mut self, points: &[Vec2]) {
+        let raise = Vec2::new(0.0, self.particle_template.radius);
+        for pair in points.windows(2) {
+            // one sided segments are solid on the right of a -> b, so swap them round when building right to left
+            let (a, b) = if self.x_direction < 0.0 { (pair[1], pair[0]) } else { (pair[0], pair[1]) };
+            let mut collider = Collider::one_sided_segment(a + raise, b + raise, GROUND_THICKNESS);
+            collider
+                .set_friction(self.particle_template.s_friction, self.particle_template.k_friction)
+                .set_collision_layer(self.particle_template.collision_layer, self.particle_template.collision_mask)
+                .set_colour(self.particle_template.colour);
+            self.sim.add_collider(collider);
+        }
+    }
+
+    // bounds of the particles and colliders added since the start indices, or just the cursor if there are none
+    fn block_aabb(&self, particle_vec_start_index: usize, collider_start_index: usize) -> Aabb2d {
+        let positions: Vec<Vec2> = self.sim.particles.0[particle_vec_start_index..].iter().map(|p| p.pos).collect();
+        let colliders = &self.sim.colliders[collider_start_index..];
+        let aabb = if positions.is_empty() {
+            Aabb2d::from_point_cloud(&[self.cursor])
+        } else {
+            Aabb2d::from_point_cloud(&positions)
+        };
+        colliders.iter().fold(aabb, |aabb, collider| aabb.merge(&collider.aabb))
+    }
+}
+
+/// The parts of LevelBuilderContext that operations move on, saved so blocks can be tried out and undone while planning
+#[derive(Clone, Copy)]
+struct LevelBuilderCursor {
+    cursor: Vec2,
+    x_direction: f32,
+    x_direction_changed: bool,
+    particle_template: Particle,
+}
+
+struct PlannedBlock {
+    operation: Box<dyn LevelBuilderOperation + Send + Sync>,
+    params: LevelBuilderParams,
+    before: LevelBuilderCursor,
+    is_last: bool,
+    progress: LevelProgress,
 }
 
 impl LevelBuilder {
     pub fn generate_level_based_on_date(&mut self, entity_system: &mut EntitySystem, particle_vec: &mut ParticleVec, sim: &mut Simulation) {
-        // set a random seed used for level generation based on todays date. Each day we get a new map to try
-        let mut rng = Random::seed_from_beginning_of_day(); //seed_from_beginning_of_week(); //car_scene.rng;
+        // Each day we get a new map to try
+        self.generate_level_from_seed(&LevelSeed::today(), entity_system, particle_vec, sim);
+    }
+
+    /// Returns how the level checker got on, if rerolling unsolvable levels
+    pub fn generate_level_from_seed(&mut self, seed: &LevelSeed, entity_system: &mut EntitySystem, particle_vec: &mut ParticleVec, sim: &mut Simulation) -> Option<LevelCheckResult> {
+        let mut rng = seed.rng();
         
         let mut level_builder_context = LevelBuilderContext::new(entity_system, particle_vec, sim, &mut rng);
-        self.generate(&mut level_builder_context, 3); //10); //10);
+        let goal = *LevelGoal::default().set_num_blocks(3).set_min_distance(20.0);
+        match self.reroll_unsolvable {
+            Some((level_checker, max_tries)) => Some(self.generate_solvable(&mut level_builder_context, &goal, &level_checker, max_tries)),
+            None => {
+                self.generate(&mut level_builder_context, &goal);
+                None
+            }
+        }
+    }
+
+    /// Rebuild a level from a level file, e.g. one exported with level_file() and then hand tweaked.
+    /// Nothing is built if the level file is not valid.
+    pub fn generate_level_from_file(&mut self, level_file: &LevelFile, entity_system: &mut EntitySystem, particle_vec: &mut ParticleVec, sim: &mut Simulation) -> io::Result<()> {
+        level_file.validate()?;
+
+        // resolve every block before building any of them, so a mistyped operation doesn't leave half a level
+        let mut operations = vec![];
+        for block in level_file.blocks.iter() {
+            let Some(operation) = self.level_builder_operations_registry.find(&block.operation) else {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unknown level block operation '{}'", block.operation)));
+            };
+            let params = LevelBuilderParams::from_values(&operation.params(), &block.params)
+                .map_err(|e| io::Error::new(e.kind(), format!("{} in {}", e, block.operation)))?;
+            operations.push((operation.box_clone(), params));
+        }
+
+        // only used for things that are not parameters, such as jitter, so a fixed seed keeps loading repeatable
+        let mut rng = Pcg64::seed_from_u64(0);
 
-        // todo: we should push the seed and # level blocks into the event system
+        let mut level_builder_context = LevelBuilderContext::new(entity_system, particle_vec, sim, &mut rng);
+        self.level_file = LevelFile::default();
+        self.path = vec![level_builder_context.cursor];
+
+        let num_blocks = operations.len();
+        for (bi, (operation, params)) in operations.into_iter().enumerate() {
+            level_builder_context.is_first = bi == 0;
+            level_builder_context.is_last = bi == (num_blocks - 1);
+            self.execute_operation(&mut level_builder_context, operation, params);
+        }
+
+        level_file.create_in_simulation(level_builder_context.sim)?;
+        self.level_file.particles = level_file.particles.clone();
+        self.level_file.constraints = level_file.constraints.clone();
+        Ok(())
     }
 
-    pub fn generate(&mut self, level_builder_context: &mut LevelBuilderContext, num_blocks: i32) -> &mut Self {
+    pub fn generate(&mut self, level_builder_context: &mut LevelBuilderContext, goal: &LevelGoal) -> &mut Self {
         // Algorithm to generate a level
         // 1. Set cursor to origin. This is where the car will spawn (well, a bit behind)
-        // 2. Generate a block, which will adjust the cursor
+        // 2. Plan blocks, trying each one out to see where it moves the cursor and what space it takes up.
+        //    Blocks that overlap earlier ones are rejected, and if nothing fits we backtrack to the previous block.
+        //    Once the goal is met the next block is the last one, which the FinishOperation takes.
+        // 3. Build the planned blocks
 
-        // currently I spawn an amount of blocks. It might be better to keep spawning blocks till we get a certain distance? or a combination? 
-        for bi in 0..num_blocks {
-            level_builder_context.is_first = bi == 0;
-            level_builder_context.is_last = bi == (num_blocks - 1);
+        self.level_file = LevelFile::default();
+        level_builder_context.aabbs.clear();
+        let start = level_builder_context.save_cursor();
 
-            // 1. Create a pair of "spawn change" and a operation.
-            let mut spawn_chance_operations = vec![];
-            for op in self.level_builder_operations_registry.iter() {
-                spawn_chance_operations.push((op.as_ref().default_spawn_chance(), op.as_ref().box_clone()))
-            }
+        let mut plan: Vec<PlannedBlock> = vec![];
+        let mut failed: Vec<Vec<String>> = vec![vec![]]; // type names of operations that did not fit, for each block in the plan
+        let mut attempts = 0;
 
-            // 2. Give each operation a chance to mutate "spawn_chance_operations".
-            for op in self.level_builder_operations_registry.iter() {
-                op.as_ref().prepare(level_builder_context, &mut spawn_chance_operations);
+        while !plan.last().is_some_and(|block| block.is_last) {
+            let bi = plan.len();
+            let mut progress = LevelProgress::default();
+            for block in plan.iter() {
+                progress.add(&block.progress);
             }
 
+            level_builder_context.is_first = bi == 0;
+            level_builder_context.is_last = bi > 0 && goal.is_met(&progress);
+
+            // 1-2. Work out the chance of spawning each operation
+            let remaining_difficulty = goal.remaining_difficulty(&progress);
+            let mut spawn_chance_operations = self.spawn_chance_operations(level_builder_context, &failed[bi], remaining_difficulty);
+
             // 3. Select an operation
-            let mut spawn_chance_total = 0.0;
-            for (chance, _) in &spawn_chance_operations {
-                spawn_chance_total += chance;
+            let mut spawn_chance_total: f32 = spawn_chance_operations.iter().map(|(chance, _)| chance).sum();
+            if spawn_chance_total <= 0.0 && bi > 0 && !level_builder_context.is_last && remaining_difficulty.is_some() {
+                // we can't afford anything else, so finish up
+                level_builder_context.is_last = true;
+                spawn_chance_operations = self.spawn_chance_operations(level_builder_context, &failed[bi], remaining_difficulty);
+                spawn_chance_total = spawn_chance_operations.iter().map(|(chance, _)| chance).sum();
             }
             if spawn_chance_total <= 0.0 {
-                // nothing to spawn!
+                // nothing fits here, so undo the previous block and try something else there
+                let Some(previous) = plan.pop() else {
+                    break;
+                };
+                level_builder_context.restore_cursor(previous.before);
+                level_builder_context.aabbs.pop();
+                failed.pop();
+                failed[bi - 1].push(previous.operation.type_name().to_owned());
                 continue;
             }
 
-            // 4. Find the selected operation and execute it
+            // 4. Find the selected operation and try it out
             let mut spawn_value = level_builder_context.rng.random_range(0.0..spawn_chance_total);
-            for (chance, operation) in &spawn_chance_operations {
+            for (chance, operation) in spawn_chance_operations {
                 spawn_value -= chance;
                 if spawn_value <= 0.0 {
                     // pick this item!
-                    level_builder_context.operations.push(operation.box_clone());
-                    operation.execute(level_builder_context);
+                    let params = LevelBuilderParams::sample(&operation.params(), level_builder_context.rng);
+                    let before = level_builder_context.save_cursor();
+                    let aabb = Self::try_operation(level_builder_context, operation.as_ref(), &params);
+                    attempts += 1;
+
+                    // once we run out of attempts, accept overlaps rather than fail to build a level
+                    if attempts < MAX_ATTEMPTS && level_builder_context.intersects_existing(&aabb) {
+                        level_builder_context.restore_cursor(before);
+                        failed[bi].push(operation.type_name().to_owned());
+                    } else {
+                        let progress = LevelProgress {
+                            num_blocks: 1,
+                            distance: (level_builder_context.cursor.x - before.cursor.x).abs(),
+                            time: (level_builder_context.cursor - before.cursor).magnitude() / ESTIMATED_CAR_SPEED + operation.estimated_time(&params),
+                            difficulty: operation.difficulty(),
+                        };
+                        level_builder_context.aabbs.push(aabb);
+                        plan.push(PlannedBlock { operation, params, before, is_last: level_builder_context.is_last, progress });
+                        failed.push(vec![]);
+                    }
                     break;
                 }
             }
         }
 
+        // 5. Build it
+        level_builder_context.restore_cursor(start);
+        level_builder_context.aabbs.clear();
+        self.path = vec![level_builder_context.cursor];
+        let num_planned = plan.len();
+        for (bi, block) in plan.into_iter().enumerate() {
+            level_builder_context.is_first = bi == 0;
+            level_builder_context.is_last = bi == (num_planned - 1);
+            self.execute_operation(level_builder_context, block.operation, block.params);
+        }
+
         // let particle system know all static particles have been built - can we move this into create_in_particle_sim?
         //level_builder_context.particle_sim.notify_particle_container_changed();
 
         self
     }
+
+    /// Generate levels until the level checker can finish one, up to max_tries. Each try carries on with the rng so it builds something different.
+    /// The last level generated is kept either way, and its check result returned.
+    pub fn generate_solvable(&mut self, level_builder_context: &mut LevelBuilderContext, goal: &LevelGoal, level_checker: &LevelChecker, max_tries: usize) -> LevelCheckResult {
+        // anything already in the level before we start, to go back to after a failed try
+        let start = level_builder_context.save_cursor();
+        let entity_system = level_builder_context.entity_system.clone();
+        let particle_vec = level_builder_context.particle_vec.clone();
+        let sim = level_builder_context.sim.clone();
+        let num_operations = level_builder_context.operations.len();
+
+        let mut tries = 0;
+        loop {
+            self.generate(level_builder_context, goal);
+            tries += 1;
+
+            let result = level_checker.check(&self.path, level_builder_context.entity_system, level_builder_context.particle_vec, level_builder_context.sim);
+            if result.is_solved() || tries >= max_tries {
+                return result;
+            }
+
+            level_builder_context.restore_cursor(start);
+            *level_builder_context.entity_system = entity_system.clone();
+            *level_builder_context.particle_vec = particle_vec.clone();
+            *level_builder_context.sim = sim.clone();
+            level_builder_context.operations.truncate(num_operations);
+        }
+    }
+
+    // 1. Create a pair of "spawn change" and a operation.
+    // 2. Give each operation a chance to mutate "spawn_chance_operations".
+    // Then rule out operations that have failed to fit too often, or that we can't afford.
+    fn spawn_chance_operations(&self, level_builder_context: &mut LevelBuilderContext, failed: &[String], remaining_difficulty: Option<f32>) -> Vec<(f32, Box<dyn LevelBuilderOperation + Send + Sync>)> {
+        let mut spawn_chance_operations = vec![];
+        for op in self.level_builder_operations_registry.iter() {
+            spawn_chance_operations.push((op.as_ref().default_spawn_chance(), op.as_ref().box_clone()))
+        }
+
+        for op in self.level_builder_operations_registry.iter() {
+            op.as_ref().prepare(level_builder_context, &mut spawn_chance_operations);
+        }
+
+        for op_chance in spawn_chance_operations.iter_mut() {
+            if failed.iter().filter(|name| name.as_str() == op_chance.1.type_name()).count() >= MAX_ATTEMPTS_PER_OPERATION {
+                op_chance.0 = 0.0;
+            }
+            if remaining_difficulty.is_some_and(|remaining| op_chance.1.difficulty() > remaining) {
+                op_chance.0 = 0.0;
+            }
+        }
+
+        spawn_chance_operations
+    }
+
+    // Build an operation in a scratch simulation and ask it the space it takes up. The context's cursor is moved on as if it was built.
+    fn try_operation(level_builder_context: &mut LevelBuilderContext, operation: &(dyn LevelBuilderOperation + Send + Sync), params: &LevelBuilderParams) -> Aabb2d {
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        let mut rng = Pcg64::seed_from_u64(0);
+
+        let mut scratch_context = LevelBuilderContext::new(&mut entity_system, &mut particle_vec, &mut sim, &mut rng);
+        scratch_context.restore_cursor(level_builder_context.save_cursor());
+        scratch_context.is_first = level_builder_context.is_first;
+        scratch_context.is_last = level_builder_context.is_last;
+
+        operation.execute(&mut scratch_context, params);
+        let aabb = operation.aabb(&scratch_context, params, scratch_context.block_aabb(0, 0));
+
+        level_builder_context.restore_cursor(scratch_context.save_cursor());
+        aabb
+    }
+
+    fn execute_operation(&mut self, level_builder_context: &mut LevelBuilderContext, operation: Box<dyn LevelBuilderOperation + Send + Sync>, params: LevelBuilderParams) {
+        let particle_vec_start_index = level_builder_context.sim.particles.len();
+        let collider_start_index = level_builder_context.sim.colliders.len();
+        operation.execute(level_builder_context, &params);
+        let aabb = operation.aabb(level_builder_context, &params, level_builder_context.block_aabb(particle_vec_start_index, collider_start_index));
+        level_builder_context.aabbs.push(aabb);
+        self.path.push(level_builder_context.cursor);
+
+        let checkpoint_entity_system = &mut level_builder_context.entity_system.checkpoint_entity_system;
+        checkpoint_entity_system.level_bottom = checkpoint_entity_system.level_bottom.min(aabb.min.y);
+        let block_bounds = aabb.grow(Vec2::new(DESPAWN_MARGIN, DESPAWN_MARGIN));
+        let despawn_bounds = &mut level_builder_context.entity_system.despawn_bounds;
+        *despawn_bounds = Some(despawn_bounds.map_or(block_bounds, |bounds| bounds.merge(&block_bounds)));
+        if operation.has_checkpoint() {
+            level_builder_context.add_checkpoint();
+        }
+
+        self.level_file.blocks.push(LevelBlock {
+            operation: operation.type_name().to_owned(),
+            params: params.values,
+        });
+        level_builder_context.operations.push(operation);
+    }
 }
 
 
@@ -119,15 +445,12 @@ impl Default for LevelBuilder {
         // here is our registry
         //
         // things to try:
-        // - a jelly draw bridge you drive into and it falls over
-        // - a flexible curved pipe that changes direction and flips the car over at the same time
         // - a big ball you drive onto and keep it rolling forwards to get to the other side
         // - an elevator
         // - a steep incline with toothed or flexible ground to give you grip to get up step. (or change the car tyres to be spiked)
         // - some cloth you need to drive under/tear through
         //
         // instead of picking random numbers in a range, pick a random integer and just quantize the number eg. pick a number and then * by 0.5 to get 0.5, 1.0, 1.5, 2.0 as random distances. this might provide more "variety" through less choice.
-        // we should keep a bounding box for each operation applied to help work out if a block can be used instead of using x_direction_changed for example
         registry.register(SpawnOperation {});
         registry.register(FinishOperation {});
         registry.register(HillOperation {});
@@ -140,10 +463,261 @@ impl Default for LevelBuilder {
         registry.register(FluidFunnel {});
         registry.register(DropDirectionReverse {});
         registry.register(ElevatorOperation {});
-        
-
-        //registry.register(JellyCube {});
+        registry.register(WindmillOperation {});
+        registry.register(ClothCurtainOperation {});
+        registry.register(BoulderGapOperation {});
+        registry.register(ToothedInclineOperation {});
+        registry.register(JellyCube {});
+        registry.register(JellyDrawbridgeOperation {});
+        registry.register(FlexiblePipeOperation {});
  
         LevelBuilder::new(registry)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{engine::app::{camera::Camera, input_action::InputAction}, game::entity::entities::car_entity::CarEntity};
+
+    /// A single block between a spawn and a straight, built for a level block's tests
+    pub(crate) struct TestLevel {
+        pub entity_system: EntitySystem,
+        pub particle_vec: ParticleVec,
+        pub sim: Simulation,
+        pub level_builder: LevelBuilder,
+        camera: Camera,
+        steps: usize,
+    }
+
+    impl TestLevel {
+        pub fn new(block: LevelBlock) -> Self {
+            let block_named = |operation: &str| LevelBlock { operation: operation.to_owned(), ..Default::default() };
+            let level_file = LevelFile { blocks: vec![block_named("SpawnOperation"), block, block_named("StraightLevelBlock")], ..Default::default() };
+
+            let mut entity_system = EntitySystem::new();
+            let mut particle_vec = ParticleVec::new();
+            let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+            let mut level_builder = LevelBuilder::default();
+            level_builder.generate_level_from_file(&level_file, &mut entity_system, &mut particle_vec, &mut sim).unwrap();
+
+            Self {
+                entity_system,
+                particle_vec,
+                sim,
+                level_builder,
+                camera: Camera::headless(1.0),
+                steps: 0,
+            }
+        }
+
+        /// The block with its default params
+        pub fn from_operation(operation: &str) -> Self {
+            Self::new(LevelBlock { operation: operation.to_owned(), ..Default::default() })
+        }
+
+        pub fn step(&mut self, steps: usize) {
+            let time_delta = 0.005;
+            for _ in 0..steps {
+                self.entity_system.step_simulation(&mut self.sim, time_delta);
+                self.entity_system.update(&mut self.particle_vec, &mut self.sim, &mut self.camera, time_delta, self.steps as f32 * time_delta);
+                self.steps += 1;
+            }
+        }
+
+        /// Drop a car in at the spawn and hold the accelerator until it gets past x, giving up after max_steps.
+        /// Returns where the car got to, so how fast the car is doesn't change where a test checks on it.
+        pub fn drive_past(&mut self, x: f32, max_steps: usize) -> Vec2 {
+            let car = CarEntity::new(&mut self.particle_vec, &mut self.sim, Vec2::new(0.0, 1.0));
+            self.entity_system.car_entity_system.push(car);
+            self.entity_system.handle_action(InputAction::Accelerate, true);
+
+            for _ in 0..max_steps {
+                self.step(1);
+                if self.car_position().x > x {
+                    break;
+                }
+            }
+            self.car_position()
+        }
+
+        pub fn car_position(&self) -> Vec2 {
+            self.entity_system.car_entity_system.0[0].get_camera_look_at_position(&self.sim.particles)
+        }
+    }
+
+    #[test]
+    fn test_generate_level_from_file() {
+        let registry = || {
+            let mut registry = LevelBuilderOperationRegistry::new();
+            registry.register(SpawnOperation {});
+            registry.register(FinishOperation {});
+            registry.register(HillOperation {});
+            registry.register(SaggyBridgeOperation {});
+            registry.register(StraightLevelBlock {});
+            registry.register(CliffOperation {});
+            registry.register(ElevatorOperation {});
+            registry
+        };
+
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut level_builder = LevelBuilder::new(registry());
+        level_builder.generate(&mut LevelBuilderContext::new(&mut entity_system, &mut particle_vec, &mut sim, &mut rng), LevelGoal::default().set_num_blocks(6));
+
+        // round trip through json, as a designer would
+        let json = serde_json::to_string(level_builder.level_file()).unwrap();
+        let level_file: LevelFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(level_file.blocks.len(), 6);
+        assert_eq!(level_file.blocks[0].operation, "SpawnOperation");
+
+        let mut loaded_entity_system = EntitySystem::new();
+        let mut loaded_particle_vec = ParticleVec::new();
+        let mut loaded_sim = Simulation::new(Pcg64::seed_from_u64(0));
+        let mut loaded_level_builder = LevelBuilder::new(registry());
+        loaded_level_builder.generate_level_from_file(&level_file, &mut loaded_entity_system, &mut loaded_particle_vec, &mut loaded_sim).unwrap();
+
+        assert_eq!(loaded_level_builder.level_file(), &level_file);
+        assert_eq!(loaded_sim.particles.len(), sim.particles.len());
+        for i in 0..sim.particles.len() {
+            assert_eq!(loaded_sim.particles[i].pos, sim.particles[i].pos);
+        }
+        assert_eq!(loaded_sim.distance_constraints.0.len(), sim.distance_constraints.0.len());
+
+        // a mistyped operation is an error rather than a different level
+        let mut mistyped = level_file.clone();
+        mistyped.blocks[1].operation = "HilOperation".to_owned();
+        let mut mistyped_sim = Simulation::new(Pcg64::seed_from_u64(0));
+        let error = LevelBuilder::new(registry()).generate_level_from_file(&mistyped, &mut EntitySystem::new(), &mut ParticleVec::new(), &mut mistyped_sim).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(mistyped_sim.particles.len(), 0);
+    }
+
+    #[test]
+    fn test_despawn_particles_off_the_level() {
+        let mut level = TestLevel::from_operation("StraightLevelBlock");
+        let num_level_particles = level.sim.particles.len();
+
+        // debris far below the level goes, the car stays even when it falls off too
+        level.sim.add_particle(*Particle::default().set_pos(Vec2::new(0.0, -100.0)));
+        level.drive_past(0.0, 1);
+        let car_particles = level.sim.particles.len() - num_level_particles;
+        level.entity_system.car_entity_system.0[0].respawn(&mut level.sim.particles, Vec2::new(0.0, -100.0));
+        level.step(1);
+
+        assert_eq!(level.sim.particles.len(), num_level_particles + car_particles);
+        assert!(level.car_position().y < -90.0, "{:?}", level.car_position());
+    }
+
+    #[test]
+    fn test_reroll_unsolvable() {
+        let registry = || {
+            let mut registry = LevelBuilderOperationRegistry::new();
+            registry.register(SpawnOperation {});
+            registry.register(FinishOperation {});
+            registry.register(StraightLevelBlock {});
+            registry
+        };
+        let seed = LevelSeed::Text("reroll".to_owned());
+
+        // a flat level is finished on the first try
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(seed.rng());
+        let mut level_builder = LevelBuilder::new(registry());
+        level_builder.set_reroll_unsolvable(LevelChecker::default(), 3);
+        let result = level_builder.generate_level_from_seed(&seed, &mut entity_system, &mut particle_vec, &mut sim);
+        assert!(result.is_some_and(|result| result.is_solved()), "{:?}", result);
+
+        // a bot with no time can't finish anything, so every try is used up and only the last level is kept
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(seed.rng());
+        let mut level_builder = LevelBuilder::new(registry());
+        level_builder.set_reroll_unsolvable(*LevelChecker::default().set_time_limit(0.0), 3);
+        let result = level_builder.generate_level_from_seed(&seed, &mut entity_system, &mut particle_vec, &mut sim);
+        assert!(result.is_some_and(|result| !result.is_solved()), "{:?}", result);
+        assert_eq!(entity_system.finish_entity_system.entities.len(), 1);
+        assert_eq!(level_builder.path().len(), level_builder.level_file().blocks.len() + 1);
+
+        // without rerolling there is nothing to report
+        let mut level_builder = LevelBuilder::new(registry());
+        assert!(level_builder.generate_level_from_seed(&seed, &mut EntitySystem::new(), &mut ParticleVec::new(), &mut Simulation::new(seed.rng())).is_none());
+    }
+
+    #[test]
+    fn test_block_aabb_covers_moving_parts() {
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        let mut rng = Pcg64::seed_from_u64(0);
+        let mut level_builder_context = LevelBuilderContext::new(&mut entity_system, &mut particle_vec, &mut sim, &mut rng);
+
+        // the paddles are built pointing down and round the sides, but reach the top of the circle as they turn
+        let operation = WindmillOperation {};
+        let params = LevelBuilderParams::from_values(&operation.params(), &Default::default()).unwrap();
+        let aabb = LevelBuilder::try_operation(&mut level_builder_context, &operation, &params);
+        let top = params.get("radius") * 2.0 + params.get("tip_height");
+        assert!(aabb.max.y >= top - 0.001, "{:?}", aabb);
+        assert_eq!(level_builder_context.sim.particles.len(), 0);
+    }
+
+    #[test]
+    fn test_default_params_in_range() {
+        // level files leave out parameters that take the default, so every default has to be a value a level file could give
+        for operation in LevelBuilder::default().level_builder_operations_registry.iter() {
+            assert!(LevelBuilderParams::from_values(&operation.params(), &Default::default()).is_ok(), "{}", operation.type_name());
+        }
+    }
+
+    #[test]
+    fn test_generate_without_overlaps() {
+        for seed in 0..20 {
+            let mut entity_system = EntitySystem::new();
+            let mut particle_vec = ParticleVec::new();
+            let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+            let mut rng = Pcg64::seed_from_u64(seed);
+            let mut level_builder = LevelBuilder::default();
+            let mut level_builder_context = LevelBuilderContext::new(&mut entity_system, &mut particle_vec, &mut sim, &mut rng);
+            level_builder.generate(&mut level_builder_context, LevelGoal::default().set_num_blocks(10));
+
+            let blocks = &level_builder.level_file().blocks;
+            assert_eq!(blocks.len(), 10);
+            assert_eq!(blocks[0].operation, "SpawnOperation");
+            assert_eq!(blocks[9].operation, "FinishOperation");
+
+            // each block is checked against the blocks before it, as it was when planned
+            let aabbs = level_builder_context.aabbs.clone();
+            assert_eq!(aabbs.len(), 10);
+            for i in 0..aabbs.len() {
+                level_builder_context.aabbs = aabbs[..i].to_vec();
+                assert!(!level_builder_context.intersects_existing(&aabbs[i]), "seed {} block {} overlaps", seed, i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_to_goal() {
+        for seed in 0..10 {
+            let mut entity_system = EntitySystem::new();
+            let mut particle_vec = ParticleVec::new();
+            let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+            let mut rng = Pcg64::seed_from_u64(seed);
+            let mut level_builder = LevelBuilder::default();
+            let mut level_builder_context = LevelBuilderContext::new(&mut entity_system, &mut particle_vec, &mut sim, &mut rng);
+            level_builder.generate(&mut level_builder_context, LevelGoal::default().set_min_distance(30.0).set_difficulty_budget(8.0));
+
+            let blocks = &level_builder.level_file().blocks;
+            assert_eq!(blocks[0].operation, "SpawnOperation");
+            assert_eq!(blocks[blocks.len() - 1].operation, "FinishOperation");
+
+            let difficulty: f32 = blocks.iter().map(|block| level_builder.level_builder_operations_registry.find(&block.operation).unwrap().difficulty()).sum();
+            assert!(difficulty <= 8.0);
+
+            // blocks span (at least) the distance the cursor moves across them, give or take a particle
+            let distance: f32 = level_builder_context.aabbs.iter().map(|aabb| aabb.max.x - aabb.min.x).sum();
+            assert!(distance >= 30.0 - blocks.len() as f32 * level_builder_context.particle_template.radius * 2.0);
+        }
+    }
+}
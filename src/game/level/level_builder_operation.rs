@@ -1,4 +1,6 @@
-use super::level_builder::LevelBuilderContext;
+use crate::core::math::aabb2d::Aabb2d;
+
+use super::{level_builder::LevelBuilderContext, level_builder_params::{LevelBuilderParam, LevelBuilderParams}};
 
 
 pub trait LevelBuilderOperation {
@@ -10,8 +12,34 @@ pub trait LevelBuilderOperation {
         1.0
     }
 
+    /// Named parameters the generator samples before calling execute. Level files record the resolved values.
+    fn params(&self) -> Vec<LevelBuilderParam> {
+        vec![]
+    }
+
+    /// Cost against a LevelGoal's difficulty budget
+    fn difficulty(&self) -> f32 {
+        1.0
+    }
+
+    /// Time taken by this block on top of driving through it, e.g. waiting for an elevator
+    fn estimated_time(&self, _params: &LevelBuilderParams) -> f32 {
+        0.0
+    }
+
+    /// Should a checkpoint be dropped where the block ends?
+    fn has_checkpoint(&self) -> bool {
+        true
+    }
+
+    /// Space the block takes up, given the bounds of the particles and colliders it built. The cursor has already been moved on to the end of the block.
+    /// Override to make room for anything else, e.g. parts that move once the level is running.
+    fn aabb(&self, _level_builder_context: &LevelBuilderContext, _params: &LevelBuilderParams, built: Aabb2d) -> Aabb2d {
+        built
+    }
+
     fn prepare(&self, _level_builder_context: &mut LevelBuilderContext, _level_builder_operations: &mut Vec<(f32, Box<dyn LevelBuilderOperation + Send + Sync>)>) {
     }
 
-    fn execute(&self, level_builder_context: &mut LevelBuilderContext);
+    fn execute(&self, level_builder_context: &mut LevelBuilderContext, params: &LevelBuilderParams);
 }
\ No newline at end of file
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::ops::RangeInclusive;
+
+use rand::Rng;
+use rand_pcg::Pcg64;
+
+/// Declares a named parameter of a LevelBuilderOperation, the range the generator samples it from and
+/// the value used when a level file does not give one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelBuilderParam {
+    pub name: &'static str,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+    pub integer: bool, // sampled as a whole number, e.g. a count of segments
+}
+
+impl LevelBuilderParam {
+    pub fn new(name: &'static str, range: RangeInclusive<f32>, default: f32) -> Self {
+        debug_assert!(range.start() <= range.end());
+        Self {
+            name,
+            min: *range.start(),
+            max: *range.end(),
+            default,
+            integer: false,
+        }
+    }
+
+    pub fn set_integer(&mut self, integer: bool) -> &mut Self {
+        self.integer = integer;
+        self
+    }
+
+    pub fn sample(&self, rng: &mut Pcg64) -> f32 {
+        if self.integer {
+            rng.random_range(self.min as i32..=self.max as i32) as f32
+        } else {
+            rng.random_range(self.min..=self.max)
+        }
+    }
+}
+
+/// A resolved set of parameter values, handed to LevelBuilderOperation::execute
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LevelBuilderParams {
+    pub values: BTreeMap<String, f32>,
+    defaults: BTreeMap<String, f32>, // declared defaults, for anything without a value
+}
+
+impl LevelBuilderParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pick a value for every parameter from its range, in declaration order
+    pub fn sample(schema: &[LevelBuilderParam], rng: &mut Pcg64) -> Self {
+        let mut params = Self::declare(schema);
+        for param in schema {
+            params.set(param.name, param.sample(rng));
+        }
+        params
+    }
+
+    /// Use the given values (e.g. from a level file), falling back to defaults for anything missing.
+    /// Values the schema does not declare, or outside a parameter's range, are an error so a mistake in a level file doesn't go unnoticed.
+    pub fn from_values(schema: &[LevelBuilderParam], values: &BTreeMap<String, f32>) -> io::Result<Self> {
+        if let Some(name) = values.keys().find(|name| !schema.iter().any(|param| param.name == name.as_str())) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unknown level builder parameter '{}'", name)));
+        }
+
+        let mut params = Self::declare(schema);
+        for param in schema {
+            let value = values.get(param.name).copied().unwrap_or(param.default);
+            let value = if param.integer { value.round() } else { value };
+            if !(param.min..=param.max).contains(&value) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("level builder parameter '{}' is {}, outside {}..={}", param.name, value, param.min, param.max)));
+            }
+            params.set(param.name, value);
+        }
+        Ok(params)
+    }
+
+    fn declare(schema: &[LevelBuilderParam]) -> Self {
+        Self {
+            values: BTreeMap::new(),
+            defaults: schema.iter().map(|param| (param.name.to_owned(), param.default)).collect(),
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: f32) -> &mut Self {
+        self.values.insert(name.to_owned(), value);
+        self
+    }
+
+    /// The value of a parameter, or its declared default if it has none.
+    /// Reading a parameter the operation never declared is a bug in the operation, so panics.
+    pub fn get(&self, name: &str) -> f32 {
+        match self.values.get(name).or(self.defaults.get(name)) {
+            Some(value) => *value,
+            None => panic!("level builder parameter '{}' was not declared", name),
+        }
+    }
+
+    pub fn get_int(&self, name: &str) -> i32 {
+        self.get(name).round() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_params_resolve() {
+        let schema = [
+            LevelBuilderParam::new("width", 5.0..=15.0, 10.0),
+            *LevelBuilderParam::new("segments", 2.0..=4.0, 3.0).set_integer(true),
+        ];
+
+        let mut rng = Pcg64::seed_from_u64(0);
+        for _ in 0..20 {
+            let params = LevelBuilderParams::sample(&schema, &mut rng);
+            assert!((5.0..=15.0).contains(&params.get("width")));
+            assert!((2..=4).contains(&params.get_int("segments")));
+            assert_eq!(params.get("segments").fract(), 0.0);
+        }
+
+        let params = LevelBuilderParams::from_values(&schema, &BTreeMap::from([("width".to_owned(), 12.0)])).unwrap();
+        assert_eq!(params.get("width"), 12.0);
+        assert_eq!(params.get_int("segments"), 3);
+
+        // mistakes in a level file are errors rather than quietly taking the default
+        assert!(LevelBuilderParams::from_values(&schema, &BTreeMap::from([("widht".to_owned(), 12.0)])).is_err());
+        assert!(LevelBuilderParams::from_values(&schema, &BTreeMap::from([("width".to_owned(), 20.0)])).is_err());
+        assert!(LevelBuilderParams::from_values(&schema, &BTreeMap::from([("segments".to_owned(), 5.0)])).is_err());
+    }
+
+    #[test]
+    fn test_params_fall_back() {
+        let schema = [LevelBuilderParam::new("width", 5.0..=15.0, 10.0)];
+        let mut params = LevelBuilderParams::from_values(&schema, &BTreeMap::new()).unwrap();
+        params.values.clear();
+        assert_eq!(params.get("width"), 10.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "undeclared")]
+    fn test_params_undeclared() {
+        let schema = [LevelBuilderParam::new("width", 5.0..=15.0, 10.0)];
+        LevelBuilderParams::from_values(&schema, &BTreeMap::new()).unwrap().get("undeclared");
+    }
+}
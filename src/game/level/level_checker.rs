@@ -0,0 +1,251 @@
+use crate::{core::math::vec2::Vec2, engine::app::{camera::Camera, input_action::InputAction}, game::entity::{entities::{car_entity::CarEntity, checkpoint_entity::FALL_OUT_DISTANCE}, entity_system::EntitySystem}, simulation::particles::{particle_vec::ParticleVec, simulation::Simulation}};
+
+/// What the bot is pressing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BotInput {
+    None,
+    Left,
+    Right,
+}
+
+impl BotInput {
+    fn action(&self) -> Option<InputAction> {
+        match self {
+            BotInput::None => None,
+            BotInput::Left => Some(InputAction::Reverse),
+            BotInput::Right => Some(InputAction::Accelerate),
+        }
+    }
+}
+
+/// How the bot decides what to press
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BotPolicy {
+    HoldRight,
+    // every interval (in seconds), try each input for the interval and keep whichever gets furthest along the level
+    Search { interval: f32 },
+}
+
+/// What happened when the bot drove a level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelCheckResult {
+    pub finish_time: Option<f32>, // in seconds, if the finish was reached
+    pub progress: f32, // furthest distance along the level path reached, in m
+    pub path_length: f32,
+}
+
+impl LevelCheckResult {
+    pub fn is_solved(&self) -> bool {
+        self.finish_time.is_some()
+    }
+}
+
+/// Drives a car through a level headlessly to find out if it can be finished.
+/// The level is cloned so the one passed in is left untouched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelChecker {
+    pub policy: BotPolicy,
+    pub time_limit: f32, // in seconds of simulation time
+    pub time_delta: f32,
+    pub car_origin: Vec2,
+}
+
+impl Default for LevelChecker {
+    fn default() -> Self {
+        Self {
+            policy: BotPolicy::HoldRight,
+            time_limit: 60.0,
+            time_delta: 0.005, // same as the game
+            car_origin: Vec2::new(0.0, 1.0), // same as the game
+        }
+    }
+}
+
+// one attempt at driving the level
+#[derive(Clone)]
+struct BotRun {
+    entity_system: EntitySystem,
+    particle_vec: ParticleVec,
+    sim: Simulation,
+    input: BotInput,
+    time: f32,
+    failed: bool, // fell out of the level or the simulation blew up
+}
+
+impl BotRun {
+    fn finished(&self) -> bool {
+        self.entity_system.car_entity_system.0.iter().any(|car| car.game_ended)
+    }
+
+    fn car_pos(&self) -> Vec2 {
+        self.entity_system.car_entity_system.0[0].get_camera_look_at_position(&self.sim.particles)
+    }
+
+    fn set_input(&mut self, input: BotInput) {
+        if let Some(action) = self.input.action() {
+            self.entity_system.handle_action(action, false);
+        }
+        if let Some(action) = input.action() {
+            self.entity_system.handle_action(action, true);
+        }
+        self.input = input;
+    }
+}
+
+impl LevelChecker {
+    pub fn set_policy(&mut self, policy: BotPolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn set_time_limit(&mut self, time_limit: f32) -> &mut Self {
+        self.time_limit = time_limit;
+        self
+    }
+
+    pub fn set_car_origin(&mut self, car_origin: Vec2) -> &mut Self {
+        self.car_origin = car_origin;
+        self
+    }
+
+    /// Drive a car through a level, built without a car, with path being LevelBuilder::path
+    pub fn check(&self, path: &[Vec2], entity_system: &EntitySystem, particle_vec: &ParticleVec, sim: &Simulation) -> LevelCheckResult {
+        let mut run = BotRun {
+            entity_system: entity_system.clone(),
+            particle_vec: particle_vec.clone(),
+            sim: sim.clone(),
+            input: BotInput::None,
+            time: 0.0,
+            failed: false,
+        };
+        let car = CarEntity::new(&mut run.particle_vec, &mut run.sim, self.car_origin);
+        run.entity_system.car_entity_system.push(car);
+
+        let fall_out_y = path.iter().map(|p| p.y).fold(self.car_origin.y, f32::min) - FALL_OUT_DISTANCE;
+        let mut camera = Camera::headless(1.0);
+        let mut progress: f32 = 0.0;
+
+        while run.time < self.time_limit && !run.finished() && !run.failed {
+            match self.policy {
+                BotPolicy::HoldRight => {
+                    run.set_input(BotInput::Right);
+                    self.step(&mut run, self.time_limit, fall_out_y, &mut camera);
+                }
+                BotPolicy::Search { interval } => {
+                    // try each input from the same state, preferring to keep moving right when they do equally well
+                    let mut best: Option<(f32, BotRun)> = None;
+                    for input in [BotInput::Right, BotInput::None, BotInput::Left] {
+                        let mut candidate = run.clone();
+                        candidate.set_input(input);
+                        self.step(&mut candidate, interval, fall_out_y, &mut camera);
+
+                        let score = if candidate.failed {
+                            f32::NEG_INFINITY
+                        } else if candidate.finished() {
+                            f32::INFINITY
+                        } else {
+                            Self::distance_along_path(path, candidate.car_pos())
+                        };
+                        if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+                            best = Some((score, candidate));
+                        }
+                    }
+                    run = best.unwrap().1;
+                }
+            }
+
+            if !run.failed {
+                progress = progress.max(Self::distance_along_path(path, run.car_pos()));
+            }
+        }
+
+        let path_length = Self::path_length(path);
+        LevelCheckResult {
+            finish_time: if run.finished() { Some(run.time) } else { None },
+            progress: if run.finished() { path_length } else { progress },
+            path_length,
+        }
+    }
+
+    // simulate for duration seconds, or until the car finishes or falls out of the level
+    fn step(&self, run: &mut BotRun, duration: f32, fall_out_y: f32, camera: &mut Camera) {
+        let end_time = (run.time + duration).min(self.time_limit);
+        while run.time < end_time && !run.finished() {
+            run.entity_system.step_simulation(&mut run.sim, self.time_delta);
+            run.time += self.time_delta;
+            run.entity_system.update(&mut run.particle_vec, &mut run.sim, camera, self.time_delta, run.time);
+
+            let pos = run.car_pos();
+            if !pos.x.is_finite() || !pos.y.is_finite() || pos.y < fall_out_y {
+                run.failed = true;
+                return;
+            }
+        }
+    }
+
+    fn path_length(path: &[Vec2]) -> f32 {
+        path.windows(2).map(|segment| (segment[1] - segment[0]).magnitude()).sum()
+    }
+
+    // project pos onto the closest point of the path and return how far along the path that is
+    fn distance_along_path(path: &[Vec2], pos: Vec2) -> f32 {
+        let mut best_distance_sq = f32::MAX;
+        let mut best_along = 0.0;
+        let mut along = 0.0;
+
+        for segment in path.windows(2) {
+            let delta = segment[1] - segment[0];
+            let length_sq = delta.dot(delta);
+            let t = if length_sq > 0.0 { ((pos - segment[0]).dot(delta) / length_sq).clamp(0.0, 1.0) } else { 0.0 };
+            let closest = segment[0] + delta * t;
+            let distance_sq = (pos - closest).dot(pos - closest);
+            if distance_sq < best_distance_sq {
+                best_distance_sq = distance_sq;
+                best_along = along + length_sq.sqrt() * t;
+            }
+            along += length_sq.sqrt();
+        }
+
+        best_along
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+    use crate::game::level::{level_builder::LevelBuilder, level_file::{LevelBlock, LevelFile}};
+
+    #[test]
+    fn test_distance_along_path() {
+        let path = [Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0), Vec2::new(4.0, -3.0)];
+        assert_eq!(LevelChecker::path_length(&path), 7.0);
+        assert_eq!(LevelChecker::distance_along_path(&path, Vec2::new(2.0, 1.0)), 2.0);
+        assert_eq!(LevelChecker::distance_along_path(&path, Vec2::new(5.0, -1.0)), 5.0);
+        assert_eq!(LevelChecker::distance_along_path(&path, Vec2::new(-1.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn test_check_straight_level() {
+        let block = |operation: &str| LevelBlock { operation: operation.to_owned(), ..Default::default() };
+        let level_file = LevelFile { blocks: vec![block("SpawnOperation"), block("StraightLevelBlock"), block("FinishOperation")], ..Default::default() };
+
+        let mut entity_system = EntitySystem::new();
+        let mut particle_vec = ParticleVec::new();
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        let mut level_builder = LevelBuilder::default();
+        level_builder.generate_level_from_file(&level_file, &mut entity_system, &mut particle_vec, &mut sim).unwrap();
+
+        let result = LevelChecker::default().set_time_limit(20.0).check(level_builder.path(), &entity_system, &particle_vec, &sim);
+        assert!(result.is_solved(), "{:?}", result);
+        assert_eq!(result.progress, result.path_length);
+
+        // there is not enough time to get to the finish
+        let result = LevelChecker::default().set_policy(BotPolicy::Search { interval: 0.5 }).set_time_limit(1.0).check(level_builder.path(), &entity_system, &particle_vec, &sim);
+        assert!(result.progress < result.path_length);
+
+        // the level itself is untouched
+        assert!(entity_system.car_entity_system.0.is_empty());
+    }
+}
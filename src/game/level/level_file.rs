@@ -0,0 +1,149 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+
+use crate::{core::math::{vec2::Vec2, vec4::Vec4}, simulation::{constraints::{distance_constraint::DistanceConstraint, spring_constraint::SpringConstraint}, particles::{particle::Particle, simulation::Simulation}}};
+
+/// A level described as data rather than code, so a generated level can be saved, hand tweaked and shared.
+/// Blocks are replayed through the LevelBuilderOperation with the same type name, using the recorded parameters instead of the RNG.
+/// Raw particles and constraints are then added on top.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct LevelFile {
+    pub blocks: Vec<LevelBlock>,
+    #[serde(default)]
+    pub particles: Vec<LevelParticle>,
+    #[serde(default)]
+    pub constraints: Vec<LevelConstraint>,
+}
+
+/// One application of a LevelBuilderOperation. Missing parameters take the default from the operation's schema.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct LevelBlock {
+    pub operation: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+}
+
+/// A hand placed particle. A mass of 0 makes it static.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LevelParticle {
+    pub pos: [f32; 2],
+    pub radius: f32,
+    pub mass: f32,
+    pub colour: [f32; 4],
+}
+
+/// A constraint between two of the level's raw particles, indexed into LevelFile::particles.
+/// The rest length is the distance between the particles as placed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum LevelConstraint {
+    Distance { i1: usize, i2: usize },
+    Spring { i1: usize, i2: usize, stiffness: f32 },
+}
+
+impl LevelFile {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let level_file: Self = serde_json::from_str(&json)?;
+        level_file.validate()?;
+        Ok(level_file)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    /// Check the constraints only join particles the file has, as the file may have been edited by hand
+    pub fn validate(&self) -> io::Result<()> {
+        for c in self.constraints.iter() {
+            let (LevelConstraint::Distance { i1, i2 } | LevelConstraint::Spring { i1, i2, .. }) = *c;
+            if i1 >= self.particles.len() || i2 >= self.particles.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("constraint {:?} joins a particle out of the {} in the level file", c, self.particles.len())));
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the raw particles and constraints to the simulation. Nothing is added if the level file is not valid.
+    pub fn create_in_simulation(&self, sim: &mut Simulation) -> io::Result<()> {
+        self.validate()?;
+        let particle_vec_start_index = sim.particles.len();
+
+        for p in self.particles.iter() {
+            let mut particle = *Particle::default()
+                .set_pos(Vec2::new(p.pos[0], p.pos[1]))
+                .set_radius(p.radius)
+                .set_colour(Vec4::new(p.colour[0], p.colour[1], p.colour[2], p.colour[3]));
+            if p.mass == 0.0 {
+                particle.set_static(true);
+            } else {
+                particle.set_mass(p.mass);
+            }
+            sim.add_particle(particle);
+        }
+
+        for c in self.constraints.iter() {
+            match *c {
+                LevelConstraint::Distance { i1, i2 } => {
+                    sim.add_distance_constraint(DistanceConstraint::from_particles(particle_vec_start_index + i1, particle_vec_start_index + i2, &sim.particles));
+                }
+                LevelConstraint::Spring { i1, i2, stiffness } => {
+                    sim.add_spring_constraint(SpringConstraint::from_particles(particle_vec_start_index + i1, particle_vec_start_index + i2, &sim.particles, stiffness));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    #[test]
+    fn test_level_file_round_trip() {
+        let level_file = LevelFile {
+            blocks: vec![LevelBlock { operation: "StraightLevelBlock".to_owned(), params: BTreeMap::from([("width".to_owned(), 7.5), ("height".to_owned(), -1.0)]) }],
+            particles: vec![
+                LevelParticle { pos: [0.0, 0.0], radius: 0.1, mass: 0.0, colour: [1.0, 1.0, 1.0, 1.0] },
+                LevelParticle { pos: [0.0, -1.0], radius: 0.1, mass: 1.0, colour: [1.0, 0.0, 0.0, 1.0] },
+            ],
+            constraints: vec![LevelConstraint::Distance { i1: 0, i2: 1 }],
+        };
+
+        let json = serde_json::to_string(&level_file).unwrap();
+        assert_eq!(serde_json::from_str::<LevelFile>(&json).unwrap(), level_file);
+
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        sim.add_particle(Particle::default());
+        level_file.create_in_simulation(&mut sim).unwrap();
+
+        assert_eq!(sim.particles.len(), 3);
+        assert!(sim.particles[1].is_static);
+        assert_eq!(sim.particles[2].mass, 1.0);
+        assert_eq!((sim.distance_constraints.0[0].i1, sim.distance_constraints.0[0].i2), (1, 2));
+        assert_eq!(sim.distance_constraints.0[0].d, 1.0);
+    }
+
+    #[test]
+    fn test_constraint_out_of_range() {
+        let particle = LevelParticle { pos: [0.0, 0.0], radius: 0.1, mass: 1.0, colour: [1.0, 1.0, 1.0, 1.0] };
+        let level_file = LevelFile { particles: vec![particle, particle], constraints: vec![LevelConstraint::Spring { i1: 0, i2: 2, stiffness: 1.0 }], ..Default::default() };
+
+        let path = std::env::temp_dir().join("planck_test_level_file_out_of_range.json");
+        let path = path.to_str().unwrap();
+        level_file.save(path).unwrap();
+        assert_eq!(LevelFile::load(path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let _ = std::fs::remove_file(path);
+
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        sim.add_particle(Particle::default());
+        sim.add_particle(Particle::default());
+        assert!(level_file.create_in_simulation(&mut sim).is_err());
+        assert_eq!(sim.particles.len(), 2);
+    }
+}
@@ -0,0 +1,111 @@
+// average speed we expect the car to drive at, for estimating how long a level takes
+pub const ESTIMATED_CAR_SPEED: f32 = 4.0;
+
+/// When the level builder should stop adding blocks and place the finish.
+/// All of the minimums have to be reached, and the difficulty budget (if any) spent, before the level is finished.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelGoal {
+    pub num_blocks: usize, // including spawn and finish
+    pub min_distance: f32, // horizontal distance travelled, in m
+    pub target_time: f32, // estimated completion time, in seconds
+    pub difficulty_budget: Option<f32>, // sum of LevelBuilderOperation::difficulty to spend
+    pub max_blocks: usize, // finish regardless once we get this far
+}
+
+impl Default for LevelGoal {
+    fn default() -> Self {
+        Self {
+            num_blocks: 0,
+            min_distance: 0.0,
+            target_time: 0.0,
+            difficulty_budget: None,
+            max_blocks: 50,
+        }
+    }
+}
+
+impl LevelGoal {
+    pub fn set_num_blocks(&mut self, num_blocks: usize) -> &mut Self {
+        self.num_blocks = num_blocks;
+        self
+    }
+
+    pub fn set_min_distance(&mut self, min_distance: f32) -> &mut Self {
+        self.min_distance = min_distance;
+        self
+    }
+
+    pub fn set_target_time(&mut self, target_time: f32) -> &mut Self {
+        self.target_time = target_time;
+        self
+    }
+
+    pub fn set_difficulty_budget(&mut self, difficulty_budget: f32) -> &mut Self {
+        self.difficulty_budget = Some(difficulty_budget);
+        self
+    }
+
+    pub fn set_max_blocks(&mut self, max_blocks: usize) -> &mut Self {
+        self.max_blocks = max_blocks;
+        self
+    }
+
+    /// Should the next block be the last? Counts the next block towards num_blocks.
+    pub fn is_met(&self, progress: &LevelProgress) -> bool {
+        if progress.num_blocks + 1 >= self.max_blocks {
+            return true;
+        }
+
+        progress.num_blocks + 1 >= self.num_blocks
+            && progress.distance >= self.min_distance
+            && progress.time >= self.target_time
+            && self.remaining_difficulty(progress).is_none_or(|remaining| remaining <= 0.0)
+    }
+
+    pub fn remaining_difficulty(&self, progress: &LevelProgress) -> Option<f32> {
+        self.difficulty_budget.map(|budget| budget - progress.difficulty)
+    }
+}
+
+/// How far through a LevelGoal the blocks built so far get us
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LevelProgress {
+    pub num_blocks: usize,
+    pub distance: f32,
+    pub time: f32,
+    pub difficulty: f32,
+}
+
+impl LevelProgress {
+    pub fn add(&mut self, other: &LevelProgress) -> &mut Self {
+        self.num_blocks += other.num_blocks;
+        self.distance += other.distance;
+        self.time += other.time;
+        self.difficulty += other.difficulty;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_goal_is_met() {
+        let goal = *LevelGoal::default().set_num_blocks(3).set_min_distance(10.0).set_difficulty_budget(2.0);
+
+        let mut progress = LevelProgress { num_blocks: 2, distance: 12.0, time: 3.0, difficulty: 1.0 };
+        assert!(!goal.is_met(&progress));
+        assert_eq!(goal.remaining_difficulty(&progress), Some(1.0));
+
+        progress.difficulty = 2.5;
+        assert!(goal.is_met(&progress));
+
+        progress.distance = 5.0;
+        assert!(!goal.is_met(&progress));
+
+        // the block cap wins over everything else
+        progress.num_blocks = 49;
+        assert!(goal.is_met(&progress));
+    }
+}
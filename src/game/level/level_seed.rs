@@ -0,0 +1,119 @@
+use std::fmt;
+
+use chrono::{NaiveDate, Utc};
+use now::DateTimeNow;
+use rand_pcg::Pcg64;
+
+use crate::core::math::random::Random;
+
+const WEEK_PREFIX: &str = "week-";
+const TEXT_PREFIX: &str = "text-";
+
+/// Which level to play. Everyone playing the same seed gets the same level, so it doubles as the leaderboard key.
+/// The share code (see Display) is what players pass around, on the command line or typed into the UI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LevelSeed {
+    Daily(NaiveDate),
+    Weekly(NaiveDate), // the monday the week starts on
+    Text(String), // whitespace is replaced with '_' so the share code stays one word
+}
+
+impl LevelSeed {
+    pub fn today() -> Self {
+        LevelSeed::Daily(Utc::now().date_naive())
+    }
+
+    pub fn this_week() -> Self {
+        Self::week_of(Utc::now().date_naive())
+    }
+
+    pub fn week_of(date: NaiveDate) -> Self {
+        LevelSeed::Weekly(date.and_hms_opt(0, 0, 0).unwrap().and_utc().beginning_of_week().date_naive())
+    }
+
+    pub fn text(text: &str) -> Self {
+        LevelSeed::Text(text.split_whitespace().collect::<Vec<_>>().join("_"))
+    }
+
+    /// Parse a share code. "today" and "week" are shorthand for the current daily and weekly levels,
+    /// a date (YYYY-MM-DD) is that day's level, and anything else is taken as a free text seed.
+    pub fn from_share_code(code: &str) -> Self {
+        let code = code.trim();
+        let parse_date = |date: &str| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok();
+
+        if code == "today" {
+            return Self::today();
+        }
+        if code == "week" {
+            return Self::this_week();
+        }
+        if let Some(date) = parse_date(code) {
+            return LevelSeed::Daily(date);
+        }
+        if let Some(date) = code.strip_prefix(WEEK_PREFIX).and_then(parse_date) {
+            return Self::week_of(date);
+        }
+        Self::text(code.strip_prefix(TEXT_PREFIX).unwrap_or(code))
+    }
+
+    /// A fresh generator for this seed. The level builder and the simulation each take their own.
+    pub fn rng(&self) -> Pcg64 {
+        match self {
+            LevelSeed::Daily(date) => Random::seed_from_date(*date),
+            LevelSeed::Weekly(date) => Random::seed_from_week_of(*date),
+            LevelSeed::Text(text) => Random::seed_from_str(text),
+        }
+    }
+}
+
+impl Default for LevelSeed {
+    fn default() -> Self {
+        Self::today()
+    }
+}
+
+/// The share code. Daily seeds are just the date, as that is what the leaderboard has always used.
+impl fmt::Display for LevelSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelSeed::Daily(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            LevelSeed::Weekly(date) => write!(f, "{}{}", WEEK_PREFIX, date.format("%Y-%m-%d")),
+            LevelSeed::Text(text) => write!(f, "{}{}", TEXT_PREFIX, text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+    use rand_seeder::Seeder;
+
+    #[test]
+    fn test_share_code_round_trip() {
+        let seeds = [
+            LevelSeed::Daily(NaiveDate::from_ymd_opt(2025, 3, 14).unwrap()),
+            LevelSeed::week_of(NaiveDate::from_ymd_opt(2025, 3, 14).unwrap()),
+            LevelSeed::text("bumpy  ride"),
+        ];
+        for seed in seeds {
+            assert_eq!(LevelSeed::from_share_code(&seed.to_string()), seed);
+        }
+
+        assert_eq!(LevelSeed::week_of(NaiveDate::from_ymd_opt(2025, 3, 14).unwrap()).to_string(), "week-2025-03-10");
+        assert_eq!(LevelSeed::from_share_code("bumpy ride"), LevelSeed::Text("bumpy_ride".to_owned()));
+        assert!(matches!(LevelSeed::from_share_code("today"), LevelSeed::Daily(_)));
+    }
+
+    #[test]
+    fn test_seed_matches_random() {
+        // the daily and weekly levels are the same as before seeds could be picked, when they were seeded from the time
+        // at the start of the day or week. A fixed time keeps both sides on the same day
+        let now = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap().and_hms_opt(13, 45, 0).unwrap().and_utc();
+        let mut day_rng: Pcg64 = Seeder::from(now.beginning_of_day()).into_rng();
+        let mut week_rng: Pcg64 = Seeder::from(now.beginning_of_week()).into_rng();
+        assert_eq!(LevelSeed::Daily(now.date_naive()).rng().next_u64(), day_rng.next_u64());
+        assert_eq!(LevelSeed::week_of(now.date_naive()).rng().next_u64(), week_rng.next_u64());
+        assert_ne!(LevelSeed::text("a").rng().next_u64(), LevelSeed::text("b").rng().next_u64());
+    }
+}
@@ -1,4 +1,9 @@
 pub mod level_builder;
 pub mod level_builder_operation;
 pub mod level_builder_operation_registry;
+pub mod level_builder_params;
+pub mod level_checker;
+pub mod level_file;
+pub mod level_goal;
+pub mod level_seed;
 pub mod level_blocks;
@@ -0,0 +1,179 @@
+use chrono::{Days, NaiveDate, Utc};
+
+use crate::{engine::app::input_action::{InputAction, InputBindings}, game::level::level_seed::LevelSeed, simulation::particles::simulation_demos::SimulationDemos};
+
+// how many days back the level select goes, not counting today
+const PAST_DAYS: u64 = 6;
+
+/// Something that can be played from the level select
+#[derive(Debug, Clone, PartialEq)]
+pub enum Level {
+    Seed(LevelSeed),
+    Demo(&'static str), // one of SimulationDemos::SCENES
+}
+
+impl Level {
+    pub fn label(&self) -> String {
+        match self {
+            Level::Seed(seed) if *seed == LevelSeed::today() => format!("Today ({})", seed),
+            Level::Seed(seed) if *seed == LevelSeed::this_week() => "This week".to_owned(),
+            Level::Seed(seed) => seed.to_string(),
+            Level::Demo(scene) => format!("Demo: {}", scene.replace('_', " ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuItem {
+    Resume,
+    Restart,
+    LevelSelect,
+    Controls,
+    MainMenu,
+    Play(Level),
+    Rebind { action: InputAction, bound_to: String }, // bound_to lists the keys and buttons for the action
+    Back,
+}
+
+impl MenuItem {
+    pub fn label(&self) -> String {
+        match self {
+            MenuItem::Resume => "Resume".to_owned(),
+            MenuItem::Restart => "Restart".to_owned(),
+            MenuItem::LevelSelect => "Level Select".to_owned(),
+            MenuItem::Controls => "Controls".to_owned(),
+            MenuItem::MainMenu => "Main Menu".to_owned(),
+            MenuItem::Play(level) => level.label(),
+            MenuItem::Rebind { action, bound_to } => format!("{}: {}", action.label(), bound_to),
+            MenuItem::Back => "Back".to_owned(),
+        }
+    }
+}
+
+/// A list of items to pick from with the menu actions, up and down to move and select to pick
+#[derive(Debug, Clone, PartialEq)]
+pub struct Menu {
+    pub title: String,
+    pub items: Vec<MenuItem>,
+    pub selected: usize,
+}
+
+impl Menu {
+    pub fn new(title: &str, items: Vec<MenuItem>) -> Self {
+        Self {
+            title: title.to_owned(),
+            items,
+            selected: 0,
+        }
+    }
+
+    pub fn main_menu() -> Self {
+        Self::new("Main Menu", vec![
+            MenuItem::Play(Level::Seed(LevelSeed::today())),
+            MenuItem::LevelSelect,
+            MenuItem::Controls,
+        ])
+    }
+
+    pub fn pause_menu() -> Self {
+        Self::new("Paused", vec![
+            MenuItem::Resume,
+            MenuItem::Restart,
+            MenuItem::LevelSelect,
+            MenuItem::Controls,
+            MenuItem::MainMenu,
+        ])
+    }
+
+    pub fn level_select() -> Self {
+        Self::level_select_from(Utc::now().date_naive())
+    }
+
+    /// Todays level, the days before it, this weeks level and the demo scenes
+    pub fn level_select_from(today: NaiveDate) -> Self {
+        let mut items: Vec<MenuItem> = (0..=PAST_DAYS)
+            .filter_map(|days| today.checked_sub_days(Days::new(days)))
+            .map(|date| MenuItem::Play(Level::Seed(LevelSeed::Daily(date))))
+            .collect();
+        items.push(MenuItem::Play(Level::Seed(LevelSeed::week_of(today))));
+        items.extend(SimulationDemos::SCENES.iter().copied().map(|scene| MenuItem::Play(Level::Demo(scene))));
+        items.push(MenuItem::Back);
+        Self::new("Level Select", items)
+    }
+
+    /// Each action with what it is bound to, picking one waits for a new key or button for it
+    pub fn controls(bindings: &InputBindings) -> Self {
+        let mut items: Vec<MenuItem> = InputAction::ALL.iter().map(|&action| {
+            let keys = bindings.keys_for(action).into_iter().map(|key| key.label());
+            let buttons = bindings.buttons_for(action).into_iter().map(|button| format!("{:?}", button));
+            MenuItem::Rebind { action, bound_to: keys.chain(buttons).collect::<Vec<_>>().join(", ") }
+        }).collect();
+        items.push(MenuItem::Back);
+        Self::new("Controls", items)
+    }
+
+    pub fn selected_item(&self) -> Option<&MenuItem> {
+        self.items.get(self.selected)
+    }
+
+    /// Move the selection on an action, returning the item if it was picked. The selection wraps around at either end.
+    pub fn handle_action(&mut self, action: InputAction) -> Option<MenuItem> {
+        if self.items.is_empty() {
+            return None;
+        }
+
+        match action {
+            InputAction::MenuUp => {
+                self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+                None
+            }
+            InputAction::MenuDown => {
+                self.selected = (self.selected + 1) % self.items.len();
+                None
+            }
+            InputAction::MenuSelect => self.selected_item().cloned(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_navigation() {
+        let mut menu = Menu::pause_menu();
+        assert_eq!(menu.handle_action(InputAction::MenuUp), None);
+        assert_eq!(menu.selected_item(), Some(&MenuItem::MainMenu));
+        assert_eq!(menu.handle_action(InputAction::MenuDown), None);
+        assert_eq!(menu.handle_action(InputAction::MenuDown), None);
+        assert_eq!(menu.handle_action(InputAction::Accelerate), None);
+        assert_eq!(menu.handle_action(InputAction::MenuSelect), Some(MenuItem::Restart));
+    }
+
+    #[test]
+    fn test_level_select() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+        let menu = Menu::level_select_from(today);
+        assert_eq!(menu.items[0], MenuItem::Play(Level::Seed(LevelSeed::Daily(today))));
+        assert!(menu.items.contains(&MenuItem::Play(Level::Seed(LevelSeed::week_of(today)))));
+        assert!(menu.items.contains(&MenuItem::Play(Level::Demo("rope"))));
+        assert_eq!(menu.items.last(), Some(&MenuItem::Back));
+
+        // the days count back from today
+        let dates: Vec<_> = menu.items.iter().filter_map(|item| match item {
+            MenuItem::Play(Level::Seed(LevelSeed::Daily(date))) => Some(*date),
+            _ => None,
+        }).collect();
+        assert_eq!(dates.len(), PAST_DAYS as usize + 1);
+        assert!(dates.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn test_controls() {
+        let menu = Menu::controls(&InputBindings::default());
+        assert_eq!(menu.items[0].label(), "Accelerate: X, RightTrigger");
+        assert_eq!(menu.items.len(), InputAction::ALL.len() + 1);
+    }
+}
@@ -6,4 +6,6 @@ pub mod irc;
 pub mod leaderboard;
 pub mod ui;
 pub mod game_state;
-pub mod settings;
\ No newline at end of file
+pub mod settings;
+pub mod ghost_car;
+pub mod menu;
\ No newline at end of file
@@ -1,10 +1,15 @@
 use serde::{Serialize, Deserialize};
+use crate::engine::app::input_action::InputBindings;
 use std::fs;
 use std::path::Path;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Settings {
     pub player_name: Option<String>,
+    #[serde(default)]
+    pub respawn_at_checkpoint: bool, // put the car back at the last checkpoint when it falls out of the level or gets stuck
+    #[serde(default)]
+    pub bindings: InputBindings,
 }
 
 impl Settings {
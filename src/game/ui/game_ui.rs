@@ -1,27 +1,47 @@
 use iced::{Element, Theme};
 use crate::game::game_state::GameState;
 use crate::game::leaderboard::LeaderboardEntry;
+use crate::game::menu::Menu;
 use crate::game::ui::hud::hud_view;
 use crate::game::ui::leaderboard::leaderboard_view;
+use crate::game::ui::menu::menu_view;
 use crate::game::ui::name_entry::name_entry_view;
 
+/// Time the car passed a checkpoint, and how far ahead (negative) or behind (positive) of the personal best it was
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitTime {
+    pub checkpoint: usize,
+    pub time: f32,
+    pub delta: Option<f32>, // None if the personal best didn't pass this checkpoint
+}
+
 #[derive(Debug, Clone)]
 pub struct GameUI {
     pub(crate) fps: i32,
     pub(crate) total_time: f32,
+    pub(crate) split_times: Vec<SplitTime>,
     pub(crate) game_state: GameState,
     pub(crate) leaderboard_results: Vec<LeaderboardEntry>,
     pub(crate) name_input: String,
+    pub(crate) seed: String, // share code of the level being played
+    pub(crate) seed_input: String,
+    pub(crate) menu: Menu, // shown in the menu states
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     UpdateFps(i32),
     UpdateTime(f32),
+    UpdateSplitTimes(Vec<SplitTime>),
     UpdateGameState(GameState),
     UpdateLeaderboardResults(Vec<LeaderboardEntry>),
     UpdateNameInput(String),
     SubmitName,
+    UpdateSeed(String),
+    UpdateSeedInput(String),
+    SubmitSeed,
+    UpdateMenu(Menu),
+    SelectMenuItem(usize),
 }
 
 impl GameUI {
@@ -29,9 +49,13 @@ impl GameUI {
         Self {
             fps: 60,
             total_time: 0.0,
+            split_times: Vec::new(),
             game_state: GameState::Playing,
             leaderboard_results: Vec::new(),
             name_input: String::new(),
+            seed: String::new(),
+            seed_input: String::new(),
+            menu: Menu::main_menu(),
         }
     }
 
@@ -39,10 +63,16 @@ impl GameUI {
         match message {
             Message::UpdateFps(fps) => self.fps = fps,
             Message::UpdateTime(time) => self.total_time = time,
+            Message::UpdateSplitTimes(split_times) => self.split_times = split_times,
             Message::UpdateGameState(state) => self.game_state = state,
             Message::UpdateLeaderboardResults(results) => self.leaderboard_results = results,
             Message::UpdateNameInput(name) => self.name_input = name,
             Message::SubmitName => {} // Handled by Game
+            Message::UpdateSeed(seed) => self.seed = seed,
+            Message::UpdateSeedInput(seed) => self.seed_input = seed,
+            Message::SubmitSeed => {} // Handled by Game
+            Message::UpdateMenu(menu) => self.menu = menu,
+            Message::SelectMenuItem(_) => {} // Handled by Game
         }
     }
 
@@ -51,6 +81,7 @@ impl GameUI {
             GameState::NameEntry => name_entry_view(self),
             GameState::Finished => leaderboard_view(self),
             GameState::Playing => hud_view(self),
+            GameState::MainMenu | GameState::LevelSelect | GameState::Paused | GameState::Controls => menu_view(self),
         }
     }
 }
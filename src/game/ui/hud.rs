@@ -1,22 +1,44 @@
-use iced::widget::{column, text, container};
+use iced::widget::{column, row, text, container};
 use iced::{Color, Element, Length, Theme, Alignment};
 use super::game_ui::{Message, GameUI};
 
 pub fn hud_view(ui: &GameUI) -> Element<'_, Message, Theme, iced::Renderer> {
-    container(
-        column![
-            text(format!("FPS: {}", ui.fps))
-                .size(20)
-                .color(Color::WHITE),
-            text(format!("Time: {:.2}s", ui.total_time))
-                .size(20)
-                .color(Color::WHITE),
-        ]
-        .padding(10)
-    )
+    let mut hud_col = column![
+        text(format!("FPS: {}", ui.fps))
+            .size(20)
+            .color(Color::WHITE),
+        text(format!("Time: {:.2}s", ui.total_time))
+            .size(20)
+            .color(Color::WHITE),
+        text(format!("Seed: {}", ui.seed))
+            .size(20)
+            .color(Color::WHITE),
+    ]
+    .padding(10);
+
+    // the last checkpoint passed, green if ahead of the personal best and red if behind
+    if let Some(split_time) = ui.split_times.last() {
+        let (delta_text, delta_col) = match split_time.delta {
+            Some(delta) if delta <= 0.0 => (format!(" ({:+.2}s)", delta), Color::from_rgb(0.0, 1.0, 0.0)),
+            Some(delta) => (format!(" ({:+.2}s)", delta), Color::from_rgb(1.0, 0.3, 0.3)),
+            None => (String::new(), Color::WHITE),
+        };
+        hud_col = hud_col.push(
+            row![
+                text(format!("Checkpoint {}: {:.2}s", split_time.checkpoint + 1, split_time.time))
+                    .size(20)
+                    .color(Color::WHITE),
+                text(delta_text)
+                    .size(20)
+                    .color(delta_col),
+            ]
+        );
+    }
+
+    container(hud_col)
     .width(Length::Fill)
     .height(Length::Fill)
     .align_x(Alignment::Start)
     .align_y(Alignment::Start)
     .into()
-}
+}
\ No newline at end of file
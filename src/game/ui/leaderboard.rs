@@ -1,4 +1,4 @@
-use iced::widget::{column, text, row, container};
+use iced::widget::{column, text, text_input, button, row, container};
 use iced::{Color, Element, Length, Theme, Alignment};
 use super::game_ui::{Message, GameUI};
 
@@ -37,6 +37,17 @@ pub fn leaderboard_view(ui: &GameUI) -> Element<'_, Message, Theme, iced::Render
         }
     }
 
+    let seed_input = text_input("today, week, 2025-03-14 or any text...", &ui.seed_input)
+        .on_input(Message::UpdateSeedInput)
+        .on_submit(Message::SubmitSeed)
+        .padding(10)
+        .size(20)
+        .width(Length::Fixed(300.0));
+
+    let seed_button = button(text("Play Seed").size(20))
+        .padding(10)
+        .on_press(Message::SubmitSeed);
+
     container(
         column![
             text(format!("Final Time: {:.2}s", ui.total_time))
@@ -56,9 +67,18 @@ pub fn leaderboard_view(ui: &GameUI) -> Element<'_, Message, Theme, iced::Render
                         ..Default::default()
                     }
                 }),
-            text("Press 'r' to retry")
+            text(format!("Seed: {}", ui.seed))
+                .size(22)
+                .color(Color::WHITE),
+            text("Press 'r' to retry or Esc for the menu")
                 .size(22)
                 .color(Color::from_rgb(0.6, 0.6, 1.0)),
+            row![
+                seed_input,
+                seed_button,
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
         ]
         .spacing(30)
         .align_x(Alignment::Center)
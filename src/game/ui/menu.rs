@@ -0,0 +1,54 @@
+use iced::widget::{column, text, button, container};
+use iced::{Color, Element, Length, Theme, Alignment};
+use super::game_ui::{Message, GameUI};
+
+// long menus like the level select only show this many items around the selected one
+const VISIBLE_ITEMS: usize = 12;
+
+pub fn menu_view(ui: &GameUI) -> Element<'_, Message, Theme, iced::Renderer> {
+    let menu = &ui.menu;
+    let first = menu.selected.saturating_sub(VISIBLE_ITEMS / 2).min(menu.items.len().saturating_sub(VISIBLE_ITEMS));
+
+    let mut items_col = column![].spacing(8).align_x(Alignment::Center);
+    for (i, item) in menu.items.iter().enumerate().skip(first).take(VISIBLE_ITEMS) {
+        let is_selected = i == menu.selected;
+        items_col = items_col.push(
+            button(text(item.label()).size(22).width(Length::Fill).align_x(Alignment::Center))
+                .padding(10)
+                .width(Length::Fixed(300.0))
+                .on_press(Message::SelectMenuItem(i))
+                .style(move |theme: &Theme, status| {
+                    let mut style = button::primary(theme, status);
+                    if !is_selected {
+                        style.background = Some(iced::Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.5)));
+                    }
+                    style
+                })
+        );
+    }
+
+    container(
+        column![
+            text(&menu.title)
+                .size(40)
+                .color(Color::WHITE),
+            items_col,
+            text("Up/Down to choose, Enter to select, Esc to go back")
+                .size(18)
+                .color(Color::from_rgb(0.6, 0.6, 1.0)),
+        ]
+        .spacing(30)
+        .align_x(Alignment::Center)
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .center_x(Length::Fill)
+    .center_y(Length::Fill)
+    .style(|_theme: &Theme| {
+        container::Style {
+            background: Some(iced::Background::Color(Color::from_rgba(0.05, 0.05, 0.1, 0.7))),
+            ..Default::default()
+        }
+    })
+    .into()
+}
\ No newline at end of file
@@ -1,4 +1,5 @@
 pub mod game_ui;
 pub mod hud;
 pub mod leaderboard;
+pub mod menu;
 pub mod name_entry;
@@ -0,0 +1,151 @@
+use crate::{core::math::{aabb2d::Aabb2d, vec2::Vec2, vec4::Vec4}, simulation::particles::particle::{Particle, COLLISION_LAYER_DEFAULT, COLLISION_MASK_ALL}};
+
+use super::{polygon_collider::PolygonCollider, sdf_grid_collider::SdfGridCollider, segment_collider::SegmentCollider};
+
+#[derive(Clone)]
+pub enum ColliderShape {
+    Segment(SegmentCollider),
+    Polygon(PolygonCollider),
+    SdfGrid(SdfGridCollider),
+}
+
+/// Static (immovable) collision geometry. Particles collide against these
+/// without the level needing to be built out of static particles.
+#[derive(Clone)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    pub aabb: Aabb2d,
+
+    /// Combined with the particles s_friction (see Particle::s_friction)
+    pub s_friction: f32,
+
+    /// Combined with the particles k_friction (see Particle::k_friction)
+    pub k_friction: f32,
+
+    /// Same as Particle::collision_layer and Particle::collision_mask
+    pub collision_layer: u32,
+    pub collision_mask: u32,
+
+    pub colour: Vec4,
+}
+
+impl Collider {
+    pub fn new(shape: ColliderShape) -> Self {
+        let aabb = match &shape {
+            ColliderShape::Segment(s) => s.get_aabb(),
+            ColliderShape::Polygon(p) => p.get_aabb(),
+            ColliderShape::SdfGrid(g) => g.get_aabb(),
+        };
+
+        Self {
+            shape,
+            aabb,
+            s_friction: 1.0,
+            k_friction: 1.0,
+            collision_layer: COLLISION_LAYER_DEFAULT,
+            collision_mask: COLLISION_MASK_ALL,
+            colour: Vec4::WHITE,
+        }
+    }
+
+    pub fn segment(a: Vec2, b: Vec2) -> Self {
+        Self::new(ColliderShape::Segment(SegmentCollider::new(a, b)))
+    }
+
+    /// See SegmentCollider::one_sided
+    pub fn one_sided_segment(a: Vec2, b: Vec2, thickness: f32) -> Self {
+        Self::new(ColliderShape::Segment(SegmentCollider::one_sided(a, b, thickness)))
+    }
+
+    pub fn polygon(vertices: Vec<Vec2>) -> Self {
+        Self::new(ColliderShape::Polygon(PolygonCollider::new(vertices)))
+    }
+
+    pub fn sdf_grid(grid: SdfGridCollider) -> Self {
+        Self::new(ColliderShape::SdfGrid(grid))
+    }
+
+    pub fn set_friction(&mut self, s_friction: f32, k_friction: f32) -> &mut Self {
+        self.s_friction = s_friction;
+        self.k_friction = k_friction;
+        self
+    }
+
+    pub fn set_collision_layer(&mut self, collision_layer: u32, collision_mask: u32) -> &mut Self {
+        self.collision_layer = collision_layer;
+        self.collision_mask = collision_mask;
+        self
+    }
+
+    pub fn collides_with(&self, particle: &Particle) -> bool {
+        (self.collision_layer & particle.collision_mask) != 0 && (particle.collision_layer & self.collision_mask) != 0
+    }
+
+    pub fn set_colour(&mut self, colour: Vec4) -> &mut Self {
+        self.colour = colour;
+        self
+    }
+
+    /// Signed distance from the surface (negative inside) and the direction that moves a point away from the surface
+    pub fn signed_distance(&self, point: Vec2) -> (f32, Vec2) {
+        match &self.shape {
+            ColliderShape::Segment(s) => s.signed_distance(point),
+            ColliderShape::Polygon(p) => p.signed_distance(point),
+            ColliderShape::SdfGrid(g) => g.signed_distance(point),
+        }
+    }
+
+    /// Points along the surface, used to draw the collider
+    pub fn outline_points(&self, spacing: f32) -> Vec<Vec2> {
+        match &self.shape {
+            ColliderShape::Segment(s) => s.outline_points(spacing),
+            ColliderShape::Polygon(p) => p.outline_points(spacing),
+            ColliderShape::SdfGrid(g) => g.outline_points(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+    use crate::simulation::particles::simulation::Simulation;
+    use super::*;
+
+    #[test]
+    fn collision_layers() {
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        sim.add_collider(Collider::segment(Vec2::new(-10.0, 0.0), Vec2::new(10.0, 0.0)));
+        sim.add_particle(*Particle::default().set_pos(Vec2::new(-1.0, 0.5)).set_mass(1.0));
+        sim.add_particle(*Particle::default().set_pos(Vec2::new(1.0, 0.5)).set_mass(1.0).set_collision_layer(0x0, COLLISION_MASK_ALL));
+
+        let time_delta = 0.005;
+        for _ in 0..200 {
+            sim.pre_solve(time_delta);
+            sim.solve(time_delta, 3, 0);
+            sim.post_solve(time_delta);
+        }
+
+        // the particle on layer zero falls straight through the ground
+        assert!(sim.particles[0].pos.y > 0.0);
+        assert!(sim.particles[1].pos.y < -1.0);
+    }
+
+    #[test]
+    fn one_sided_segment_stops_tunnelling() {
+        let mut sim = Simulation::new(Pcg64::seed_from_u64(0));
+        sim.add_collider(Collider::one_sided_segment(Vec2::new(-10.0, 0.0), Vec2::new(10.0, 0.0), 0.5));
+        let mut particle = *Particle::default().set_pos(Vec2::new(0.0, 0.2)).set_mass(1.0);
+        particle.vel = Vec2::new(0.0, -50.0); // far enough in a step to end up below the segment
+        sim.add_particle(particle);
+
+        let time_delta = 0.005;
+        for _ in 0..50 {
+            sim.pre_solve(time_delta);
+            sim.solve(time_delta, 3, 0);
+            sim.post_solve(time_delta);
+        }
+
+        assert!(sim.particles[0].pos.y > 0.0, "{:?}", sim.particles[0].pos);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod collider;
+pub mod segment_collider;
+pub mod polygon_collider;
+pub mod sdf_grid_collider;
\ No newline at end of file
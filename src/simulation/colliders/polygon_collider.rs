@@ -0,0 +1,109 @@
+use crate::core::math::{aabb2d::Aabb2d, vec2::Vec2};
+
+use super::segment_collider::SegmentCollider;
+
+/// A static convex polygon. Vertices are wound counter-clockwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolygonCollider {
+    pub vertices: Vec<Vec2>,
+}
+
+impl PolygonCollider {
+    pub fn new(vertices: Vec<Vec2>) -> Self {
+        debug_assert!(vertices.len() >= 3, "A polygon collider needs at least 3 vertices.");
+        Self { vertices }
+    }
+
+    pub fn from_aabb(aabb: Aabb2d) -> Self {
+        Self::new(vec![
+            aabb.min,
+            Vec2::new(aabb.max.x, aabb.min.y),
+            aabb.max,
+            Vec2::new(aabb.min.x, aabb.max.y),
+        ])
+    }
+
+    fn edge(&self, i: usize) -> SegmentCollider {
+        SegmentCollider::new(self.vertices[i], self.vertices[(i + 1) % self.vertices.len()])
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        for i in 0..self.vertices.len() {
+            let edge = self.edge(i);
+            let ab = edge.b - edge.a;
+            let ap = point - edge.a;
+            // For a CCW polygon the inside is always to the left of each edge
+            if ab.x * ap.y - ab.y * ap.x < 0.0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns the signed distance (negative inside) and the outward direction to push a point out of the polygon.
+    pub fn signed_distance(&self, point: Vec2) -> (f32, Vec2) {
+        let mut closest = self.vertices[0];
+        let mut closest_dist2 = f32::MAX;
+        let mut closest_edge = 0;
+        for i in 0..self.vertices.len() {
+            let c = self.edge(i).closest_point(point);
+            let dist2 = (point - c).magnitude2();
+            if dist2 < closest_dist2 {
+                closest_dist2 = dist2;
+                closest = c;
+                closest_edge = i;
+            }
+        }
+
+        let dist = closest_dist2.sqrt();
+        if dist < f32::EPSILON {
+            // On the surface, so push out along the edge normal
+            let edge = self.edge(closest_edge);
+            let ab = (edge.b - edge.a).normalize();
+            return (0.0, Vec2::new(ab.y, -ab.x));
+        }
+
+        if self.contains_point(point) {
+            (-dist, (closest - point) / dist)
+        } else {
+            (dist, (point - closest) / dist)
+        }
+    }
+
+    pub fn get_aabb(&self) -> Aabb2d {
+        Aabb2d::from_point_cloud(&self.vertices)
+    }
+
+    pub fn outline_points(&self, spacing: f32) -> Vec<Vec2> {
+        let mut points = vec![];
+        for i in 0..self.vertices.len() {
+            let mut edge_points = self.edge(i).outline_points(spacing);
+            edge_points.pop(); // the next edge starts on this point
+            points.append(&mut edge_points);
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> PolygonCollider {
+        PolygonCollider::from_aabb(Aabb2d { min: Vec2::new(0.0, 0.0), max: Vec2::new(2.0, 2.0) })
+    }
+
+    #[test]
+    fn signed_distance_inside() {
+        let (d, n) = unit_box().signed_distance(Vec2::new(1.0, 1.8));
+        assert!((d + 0.2).abs() < 0.0001);
+        assert_eq!(n, Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn signed_distance_outside() {
+        let (d, n) = unit_box().signed_distance(Vec2::new(-1.0, 1.0));
+        assert_eq!(d, 1.0);
+        assert_eq!(n, Vec2::new(-1.0, 0.0));
+    }
+}
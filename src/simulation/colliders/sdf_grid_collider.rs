@@ -0,0 +1,115 @@
+use crate::core::math::{aabb2d::Aabb2d, vec2::Vec2};
+
+/// A static collider described by a sampled signed distance field.
+/// Distances are stored row by row (x first) with a sample every `cell_size` metres starting at `origin`.
+/// Negative distances are inside the solid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfGridCollider {
+    pub origin: Vec2,
+    pub cell_size: f32,
+    pub width: usize,
+    pub height: usize,
+    pub distances: Vec<f32>,
+}
+
+impl SdfGridCollider {
+    pub fn new(origin: Vec2, cell_size: f32, width: usize, height: usize, distances: Vec<f32>) -> Self {
+        debug_assert!(width >= 2 && height >= 2);
+        debug_assert!(distances.len() == width * height);
+        Self { origin, cell_size, width, height, distances }
+    }
+
+    /// Build a grid by sampling the given signed distance function at each grid point
+    pub fn from_fn<F: Fn(Vec2) -> f32>(origin: Vec2, cell_size: f32, width: usize, height: usize, f: F) -> Self {
+        let mut distances = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                distances.push(f(origin + Vec2::new(x as f32, y as f32) * cell_size));
+            }
+        }
+        Self::new(origin, cell_size, width, height, distances)
+    }
+
+    fn value(&self, x: usize, y: usize) -> f32 {
+        self.distances[y * self.width + x]
+    }
+
+    /// Bilinearly interpolated distance. Returns None outside of the grid.
+    pub fn sample(&self, point: Vec2) -> Option<f32> {
+        let local = (point - self.origin) / self.cell_size;
+        if local.x < 0.0 || local.y < 0.0 {
+            return None;
+        }
+
+        let x0 = local.x.floor() as usize;
+        let y0 = local.y.floor() as usize;
+        if x0 + 1 >= self.width || y0 + 1 >= self.height {
+            return None;
+        }
+
+        let tx = local.x - x0 as f32;
+        let ty = local.y - y0 as f32;
+        let bottom = self.value(x0, y0) * (1.0 - tx) + self.value(x0 + 1, y0) * tx;
+        let top = self.value(x0, y0 + 1) * (1.0 - tx) + self.value(x0 + 1, y0 + 1) * tx;
+        Some(bottom * (1.0 - ty) + top * ty)
+    }
+
+    /// Returns the signed distance and gradient of the field at a point, or f32::MAX if the point is outside the grid.
+    pub fn signed_distance(&self, point: Vec2) -> (f32, Vec2) {
+        let d = match self.sample(point) {
+            Some(d) => d,
+            None => return (f32::MAX, Vec2::zero()),
+        };
+
+        // Central differences, falling back to the centre sample at the grid edges
+        let h = self.cell_size * 0.5;
+        let dx1 = self.sample(point + Vec2::new(h, 0.0)).unwrap_or(d);
+        let dx0 = self.sample(point - Vec2::new(h, 0.0)).unwrap_or(d);
+        let dy1 = self.sample(point + Vec2::new(0.0, h)).unwrap_or(d);
+        let dy0 = self.sample(point - Vec2::new(0.0, h)).unwrap_or(d);
+        let gradient = Vec2::new(dx1 - dx0, dy1 - dy0).normalize();
+
+        (d, gradient)
+    }
+
+    pub fn get_aabb(&self) -> Aabb2d {
+        Aabb2d {
+            min: self.origin,
+            max: self.origin + Vec2::new((self.width - 1) as f32, (self.height - 1) as f32) * self.cell_size,
+        }
+    }
+
+    /// Grid points that lie just inside the surface
+    pub fn outline_points(&self) -> Vec<Vec2> {
+        let mut points = vec![];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let d = self.value(x, y);
+                if d <= 0.0 && d > -self.cell_size {
+                    points.push(self.origin + Vec2::new(x as f32, y as f32) * self.cell_size);
+                }
+            }
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_flat_ground() {
+        // Ground surface at y = 1
+        let grid = SdfGridCollider::from_fn(Vec2::new(0.0, 0.0), 0.5, 10, 10, |p| p.y - 1.0);
+        let (d, n) = grid.signed_distance(Vec2::new(2.0, 1.5));
+        assert!((d - 0.5).abs() < 0.0001);
+        assert!((n.y - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn sample_outside_grid() {
+        let grid = SdfGridCollider::from_fn(Vec2::new(0.0, 0.0), 0.5, 10, 10, |p| p.y - 1.0);
+        assert_eq!(grid.sample(Vec2::new(-1.0, 1.0)), None);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::core::math::{aabb2d::Aabb2d, vec2::Vec2};
+
+/// A static line segment particles can collide against, from either side unless it is one sided.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentCollider {
+    pub a: Vec2,
+    pub b: Vec2,
+
+    /// Some(thickness) makes the segment one sided: the left of a -> b is outside, and points up to thickness
+    /// behind the segment are inside it and pushed back out the front, so fast particles can't pass through
+    pub thickness: Option<f32>,
+}
+
+impl SegmentCollider {
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self { a, b, thickness: None }
+    }
+
+    pub fn one_sided(a: Vec2, b: Vec2, thickness: f32) -> Self {
+        Self { a, b, thickness: Some(thickness) }
+    }
+
+    // left of a -> b
+    fn normal(&self) -> Vec2 {
+        let ab = (self.b - self.a).normalize();
+        Vec2::new(-ab.y, ab.x)
+    }
+
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let ab = self.b - self.a;
+        let len2 = ab.dot(ab);
+        if len2 < f32::EPSILON {
+            return self.a;
+        }
+        let t = ((point - self.a).dot(ab) / len2).clamp(0.0, 1.0);
+        self.a + ab * t
+    }
+
+    /// Returns the distance from the segment (negative behind a one sided segment) and the direction to push a point away from it.
+    pub fn signed_distance(&self, point: Vec2) -> (f32, Vec2) {
+        let delta = point - self.closest_point(point);
+        let dist = delta.magnitude();
+        if dist < f32::EPSILON {
+            // Sitting exactly on the segment, so push out along the segment normal
+            return (0.0, self.normal());
+        }
+
+        if let Some(thickness) = self.thickness {
+            // only straight behind the segment, past the ends is up to the segments either side
+            let ab = self.b - self.a;
+            let t = (point - self.a).dot(ab) / ab.dot(ab);
+            let n = self.normal();
+            let depth = -delta.dot(n);
+            if depth > 0.0 && depth <= thickness && (0.0..=1.0).contains(&t) {
+                // behind the segment, so back out the front rather than further through
+                return (-depth, n);
+            }
+        }
+        (dist, delta / dist)
+    }
+
+    pub fn get_aabb(&self) -> Aabb2d {
+        match self.thickness {
+            Some(thickness) => {
+                let behind = self.normal() * -thickness;
+                Aabb2d::from_point_cloud(&[self.a, self.b, self.a + behind, self.b + behind])
+            }
+            None => Aabb2d::from_point_cloud(&[self.a, self.b]),
+        }
+    }
+
+    pub fn outline_points(&self, spacing: f32) -> Vec<Vec2> {
+        let delta = self.b - self.a;
+        let divisions = ((delta.magnitude() / spacing) as usize).max(1);
+        (0..=divisions).map(|i| self.a + delta * (i as f32 / divisions as f32)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_distance_above() {
+        let s = SegmentCollider::new(Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+        let (d, n) = s.signed_distance(Vec2::new(5.0, 2.0));
+        assert_eq!(d, 2.0);
+        assert_eq!(n, Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn signed_distance_one_sided() {
+        let s = SegmentCollider::one_sided(Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0), 1.0);
+        assert_eq!(s.signed_distance(Vec2::new(5.0, 2.0)), (2.0, Vec2::new(0.0, 1.0)));
+
+        // a point that went through the segment comes back out the top
+        let (d, n) = s.signed_distance(Vec2::new(5.0, -0.5));
+        assert_eq!(d, -0.5);
+        assert_eq!(n, Vec2::new(0.0, 1.0));
+
+        // but not from deeper than the segment is thick, or from past its ends
+        assert_eq!(s.signed_distance(Vec2::new(5.0, -2.0)), (2.0, Vec2::new(0.0, -1.0)));
+        assert!(s.signed_distance(Vec2::new(10.3, -0.4)).0 > 0.0);
+    }
+
+    #[test]
+    fn signed_distance_past_end() {
+        let s = SegmentCollider::new(Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+        let (d, n) = s.signed_distance(Vec2::new(13.0, 0.0));
+        assert_eq!(d, 3.0);
+        assert_eq!(n, Vec2::new(1.0, 0.0));
+    }
+}
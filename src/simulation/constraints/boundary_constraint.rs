@@ -1,5 +1,6 @@
 use crate::{core::math::vec2::Vec2, simulation::particles::{particle::Phase, particle_vec::ParticleVec}};
 
+#[derive(Clone)]
 pub struct BoundaryConstraint {
     pub index: usize,
     pub value: f32,
@@ -112,6 +113,7 @@ impl BoundaryConstraint {
     }
 }
 
+#[derive(Clone)]
 pub struct BoundaryConstraintVec(pub Vec<BoundaryConstraint>);
 
 impl BoundaryConstraintVec {
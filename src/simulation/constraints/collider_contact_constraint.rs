@@ -0,0 +1,155 @@
+use crate::simulation::{colliders::collider::Collider, particles::particle_vec::ParticleVec};
+
+/// Contact between a particle and a static Collider. Like the BoundaryConstraint
+/// this pushes the particle out along the surface normal and applies friction.
+#[derive(Clone)]
+pub struct ColliderContactConstraint {
+    pub index: usize,
+    pub collider: usize,
+    pub stable: bool,
+}
+
+impl ColliderContactConstraint {
+    pub fn new(index: usize, collider: usize, stable: bool) -> Self {
+        Self {
+            index,
+            collider,
+            stable,
+        }
+    }
+
+    pub fn project(&self, estimates: &mut ParticleVec, counts: &[usize], colliders: &[Collider]) {
+        let p = &mut estimates[self.index];
+        let collider = &colliders[self.collider];
+
+        // Quit if no longer valid
+        let (dist, n) = collider.signed_distance(p.pos_guess);
+        if dist >= p.radius {
+            return;
+        }
+
+        // Move the particle back into a valid spot
+        let correction = n * (p.radius - dist);
+        p.pos_guess += correction;
+        if self.stable {
+            p.pos += correction;
+            return;
+        }
+
+        // Apply friction
+        let dp = (p.pos_guess - p.pos) / (counts[self.index] as f32);
+        let dpt = dp - dp.dot(n) * n;
+        let ldpt = dpt.magnitude();
+
+        if ldpt < f32::EPSILON {
+            return;
+        }
+
+        let s_fric = (p.s_friction * collider.s_friction).sqrt();
+        let k_fric = (p.k_friction * collider.k_friction).sqrt();
+
+        // Choose between static and kinetic friction
+        if ldpt < s_fric * p.radius {
+            p.pos_guess -= dpt;
+        } else {
+            p.pos_guess -= dpt * f32::min(k_fric * p.radius / ldpt, 1.);
+        }
+    }
+
+    pub fn update_counts(&self, counts: &mut [usize]) {
+        counts[self.index] += 1;
+    }
+}
+
+#[derive(Clone)]
+pub struct ColliderContactConstraintVec(pub Vec<ColliderContactConstraint>);
+
+impl ColliderContactConstraintVec {
+    pub fn new() -> Self {
+        Self(vec![])
+    }
+
+    pub fn update_counts(&self, counts: &mut [usize]) {
+        for c in &self.0 {
+            c.update_counts(counts);
+        }
+    }
+
+    pub fn solve(&self, particles: &mut ParticleVec, counts: &[usize], colliders: &[Collider]) {
+        for c in &self.0 {
+            c.project(particles, counts, colliders);
+        }
+    }
+
+    pub fn push(&mut self, c: ColliderContactConstraint) {
+        self.0.push(c);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, ColliderContactConstraint> {
+        self.0.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Default for ColliderContactConstraintVec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::math::vec2::Vec2, simulation::particles::particle::Particle};
+
+    #[test]
+    fn test_pushed_out_of_segment() {
+        let mut particles = ParticleVec::new();
+
+        let mut p = Particle::default();
+        p.pos = Vec2::new(0.0, 0.6);
+        p.pos_guess = Vec2::new(0.0, 0.2);
+        p.imass = 1.0;
+        particles.push(p);
+
+        let colliders = vec![Collider::segment(Vec2::new(-5.0, 0.0), Vec2::new(5.0, 0.0))];
+        let constraint = ColliderContactConstraint::new(0, 0, false);
+        let counts = vec![1];
+
+        constraint.project(&mut particles, &counts, &colliders);
+
+        assert!((particles[0].pos_guess.y - p.radius).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_friction_stops_sliding() {
+        let mut particles = ParticleVec::new();
+
+        let mut p = Particle::default();
+        p.pos = Vec2::new(0.0, 0.5);
+        p.pos_guess = Vec2::new(0.01, 0.45);
+        p.imass = 1.0;
+        p.s_friction = 1.0;
+        p.k_friction = 1.0;
+        particles.push(p);
+
+        let colliders = vec![Collider::segment(Vec2::new(-5.0, 0.0), Vec2::new(5.0, 0.0))];
+        let constraint = ColliderContactConstraint::new(0, 0, false);
+        let counts = vec![1];
+
+        constraint.project(&mut particles, &counts, &colliders);
+
+        assert!(particles[0].pos_guess.x.abs() < 0.0001);
+    }
+}
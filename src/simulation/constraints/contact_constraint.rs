@@ -1,6 +1,7 @@
 use crate::simulation::particles::particle_vec::ParticleVec;
 
 
+#[derive(Clone)]
 pub struct ContactConstraint {
     pub i1: usize,
     pub i2: usize,
@@ -30,8 +31,9 @@ impl ContactConstraint {
         let particle_diam = p1.radius + p2.radius;
         let mag = dist - particle_diam;
 
-        // Previous iterations have moved particles out of collision
-        if mag > 0.0 {
+        // Previous iterations have moved particles out of collision.
+        // Particles exactly on top of each other (e.g. both pushed in to a boundary corner) have no direction to separate in.
+        if mag > 0.0 || dist == 0.0 {
             return;
         }
 
@@ -56,6 +58,7 @@ impl ContactConstraint {
     }
 }
 
+#[derive(Clone)]
 pub struct ContactConstraintVec(pub Vec<ContactConstraint>);
 
 impl ContactConstraintVec {
@@ -1,12 +1,15 @@
-use crate::simulation::particles::particle_vec::ParticleVec;
+use crate::simulation::particles::particle_vec::{ParticleRemap, ParticleVec};
 
 
+#[derive(Clone)]
 pub struct DistanceConstraint {
     pub d: f32,
     pub i1: usize,
     pub i2: usize,
     pub stable: bool,
     pub enabled: bool,
+    pub max_strain: Option<f32>, // snap when stretched by more than this fraction of d, e.g. 0.5 for half as long again
+    pub broken: bool,
 }
 
 impl DistanceConstraint {
@@ -19,9 +22,16 @@ impl DistanceConstraint {
             i2,
             stable,
             enabled: true,
+            max_strain: None,
+            broken: false,
         }
     }
 
+    pub fn set_max_strain(&mut self, max_strain: f32) -> &mut Self {
+        self.max_strain = Some(max_strain);
+        self
+    }
+
     pub fn from_particles(i1: usize, i2: usize, particles: &ParticleVec) -> Self {
         let d = (particles[i1].pos - particles[i2].pos).magnitude();
         Self::new(d, i1, i2, false)
@@ -54,11 +64,46 @@ impl DistanceConstraint {
     }
 
     pub fn update_counts(&self, counts: &mut Vec<usize>) {
+        if !self.enabled {
+            return;
+        }
         counts[self.i1] += 1;
         counts[self.i2] += 1;
     }
+
+    /// Snap if stretched past max_strain. A broken constraint is disabled for good.
+    pub fn break_if_overstretched(&mut self, particles: &ParticleVec) {
+        let Some(max_strain) = self.max_strain else {
+            return;
+        };
+        if !self.enabled {
+            return;
+        }
+
+        let dist = (particles[self.i1].pos - particles[self.i2].pos).magnitude();
+        if dist > self.d * (1.0 + max_strain) {
+            self.enabled = false;
+            self.broken = true;
+        }
+    }
+
+    /// A constraint that lost one of its particles is disabled for good, rather than removed, so constraint ids stay valid
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        match (remap.index(self.i1), remap.index(self.i2)) {
+            (Some(i1), Some(i2)) => {
+                self.i1 = i1;
+                self.i2 = i2;
+            }
+            _ => {
+                self.enabled = false;
+                self.i1 = usize::MAX;
+                self.i2 = usize::MAX;
+            }
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct DistanceConstraintVec(pub Vec<DistanceConstraint>);
 
 impl DistanceConstraintVec {
@@ -81,6 +126,18 @@ impl DistanceConstraintVec {
     pub fn push(&mut self, c: DistanceConstraint) {
         self.0.push(c);
     }
+
+    pub fn break_overstretched(&mut self, particles: &ParticleVec) {
+        for c in self.0.iter_mut() {
+            c.break_if_overstretched(particles);
+        }
+    }
+
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        for c in self.0.iter_mut() {
+            c.remap_particles(remap);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -115,4 +172,47 @@ mod tests {
         let new_dist = (particles[0].pos_guess - particles[1].pos_guess).magnitude();
         assert_eq!(new_dist, 10.0); // Should not have moved
     }
+
+    #[test]
+    fn test_distance_break() {
+        let mut particles = ParticleVec::from([Particle::default(), Particle::default(), Particle::default()]);
+        particles[1].pos = Vec2::new(1.0, 0.0);
+        particles[2].pos = Vec2::new(2.0, 0.0);
+
+        let mut constraints = DistanceConstraintVec::new();
+        constraints.push(DistanceConstraint::new(1.0, 0, 1, false).set_max_strain(0.5).clone());
+        constraints.push(DistanceConstraint::new(1.0, 1, 2, false));
+
+        // stretched by 40%, which holds
+        particles[1].pos.x = 1.4;
+        constraints.break_overstretched(&particles);
+        assert!(constraints.0[0].enabled);
+
+        // stretched by 60%, which snaps the first. The second can't break.
+        particles[1].pos.x = 1.6;
+        particles[2].pos.x = 10.0;
+        constraints.break_overstretched(&particles);
+        assert!(!constraints.0[0].enabled && constraints.0[0].broken);
+        assert!(constraints.0[1].enabled && !constraints.0[1].broken);
+    }
+
+    #[test]
+    fn test_distance_remap() {
+        let mut particles = ParticleVec::from([Particle::default(), Particle::default(), Particle::default()]);
+        let mut constraints = DistanceConstraintVec::new();
+        constraints.push(DistanceConstraint::new(1.0, 1, 2, false));
+        constraints.push(DistanceConstraint::new(1.0, 0, 2, false));
+
+        let remap = particles.remove(&[0]);
+        constraints.remap_particles(&remap);
+
+        assert!(constraints.0[0].enabled);
+        assert_eq!((constraints.0[0].i1, constraints.0[0].i2), (0, 1));
+
+        // Lost a particle, so it is disabled and no longer counted
+        assert!(!constraints.0[1].enabled);
+        let mut counts = vec![0; particles.len()];
+        constraints.update_counts(&mut counts);
+        assert_eq!(counts, vec![1, 1]);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,213 @@
+use crate::{core::math::vec2::Vec2, simulation::particles::{body::Body, particle::Phase, particle_vec::ParticleVec}};
+
+// Number of fluid contacts at which a solid particle counts as fully submerged
+const SUBMERGED_CONTACTS: f32 = 4.0;
+
+/// Fluid and solid particles are coupled when closer than this many times their combined radius.
+/// Fluid particles at rest sit further apart than a diameter, so touching alone misses most of the fluid.
+pub const FLUID_COUPLING_RANGE: f32 = 2.0;
+
+/// Two-way contact between a fluid particle and a solid particle.
+/// Pushes the two apart like a ContactConstraint, then drags them towards a common velocity.
+/// The buoyancy of the fluid is applied as a force at the end of each step (see FluidCouplingConstraintVec::apply_buoyancy).
+#[derive(Clone)]
+pub struct FluidCouplingConstraint {
+    pub fluid: usize,
+    pub solid: usize,
+    pub buoyancy: f32,
+    pub drag: f32,
+    pub stable: bool,
+}
+
+impl FluidCouplingConstraint {
+    pub fn new(fluid: usize, solid: usize, buoyancy: f32, drag: f32, stable: bool) -> Self {
+        Self {
+            fluid,
+            solid,
+            buoyancy,
+            drag,
+            stable,
+        }
+    }
+
+    pub fn project(&self, estimates: &mut ParticleVec, counts: &[usize]) {
+        let pf = estimates[self.fluid];
+        let ps = estimates[self.solid];
+
+        let w_sum = pf.tmass + ps.tmass;
+        if w_sum == 0.0 {
+            return;
+        }
+
+        let diff = pf.get_p(self.stable) - ps.get_p(self.stable);
+        let dist = diff.magnitude();
+        let mag = dist - (pf.radius + ps.radius);
+
+        // Pressure: push overlapping particles apart, weighted by mass so heavy solids move less
+        let mut dp = Vec2::new(0.0, 0.0);
+        if mag < 0.0 && dist > f32::EPSILON {
+            dp = (mag / w_sum / dist) * diff;
+        }
+        let mut dpf = -pf.tmass * dp;
+        let mut dps = ps.tmass * dp;
+
+        // Viscous drag: remove some of the relative motion between the two particles this step
+        if !self.stable {
+            let rel = (pf.pos_guess - pf.pos) - (ps.pos_guess - ps.pos);
+            let correction = rel * self.drag;
+            dpf -= correction * (pf.tmass / w_sum);
+            dps += correction * (ps.tmass / w_sum);
+        }
+
+        let dpf = dpf / counts[self.fluid] as f32;
+        let dps = dps / counts[self.solid] as f32;
+
+        estimates[self.fluid].pos_guess += dpf;
+        estimates[self.solid].pos_guess += dps;
+
+        if self.stable {
+            estimates[self.fluid].pos += dpf;
+            estimates[self.solid].pos += dps;
+        }
+    }
+
+    pub fn update_counts(&self, counts: &mut [usize]) {
+        counts[self.fluid] += 1;
+        counts[self.solid] += 1;
+    }
+}
+
+/// The constraints, and the number of fluid contacts of each particle kept from step to step to save allocating it in apply_buoyancy
+#[derive(Clone)]
+pub struct FluidCouplingConstraintVec(pub Vec<FluidCouplingConstraint>, Vec<usize>);
+
+impl FluidCouplingConstraintVec {
+    pub fn new() -> Self {
+        Self(vec![], vec![])
+    }
+
+    pub fn update_counts(&self, counts: &mut [usize]) {
+        for c in &self.0 {
+            c.update_counts(counts);
+        }
+    }
+
+    pub fn solve(&self, particles: &mut ParticleVec, counts: &[usize]) {
+        for c in &self.0 {
+            c.project(particles, counts);
+        }
+    }
+
+    /// Archimedes: push each solid particle up by the weight of the fluid it displaces and push the fluid down by the same amount.
+    /// Only the outside of a rigid body touches the fluid, so its surface particles also carry the lift for the particles inside.
+    /// Particle forces are accelerations, so the reaction is scaled by the mass ratio.
+    pub fn apply_buoyancy(&mut self, particles: &mut ParticleVec, bodies: &[Body], gravity: Vec2) {
+        let contacts = &mut self.1;
+        contacts.clear();
+        contacts.resize(particles.len(), 0);
+        for c in &self.0 {
+            contacts[c.solid] += 1;
+        }
+
+        for c in &self.0 {
+            let pf = particles[c.fluid];
+            let ps = particles[c.solid];
+            if ps.imass == 0.0 || pf.imass == 0.0 {
+                continue;
+            }
+
+            let n = contacts[c.solid] as f32;
+            let submerged = f32::min(n / SUBMERGED_CONTACTS, 1.0);
+
+            // Mass of fluid that would fill the space taken by the solid particle
+            let displaced_mass = (1.0 / pf.imass) * (ps.radius * ps.radius) / (pf.radius * pf.radius);
+            let mut lift = -gravity * (c.buoyancy * displaced_mass * submerged / n);
+
+            if ps.phase == Phase::Solid && ps.body >= 0 && (ps.body as usize) < bodies.len() {
+                let body_particles = bodies[ps.body as usize].particle_indicies.len() as f32;
+                let surface_particles = (4.0 * body_particles.sqrt() - 4.0).clamp(1.0, body_particles);
+                lift *= body_particles / surface_particles;
+            }
+
+            particles[c.solid].force += lift * ps.imass;
+            particles[c.fluid].force -= lift * pf.imass;
+        }
+    }
+
+    pub fn push(&mut self, c: FluidCouplingConstraint) {
+        self.0.push(c);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, FluidCouplingConstraint> {
+        self.0.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Default for FluidCouplingConstraintVec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::particles::particle::{Particle, Phase};
+
+    fn fluid_and_solid(solid_mass: f32) -> ParticleVec {
+        let mut particles = ParticleVec::new();
+
+        let mut fluid = *Particle::default().set_pos(Vec2::new(0.0, 0.0)).set_mass(1.0).set_phase(Phase::Fluid);
+        fluid.pos_guess = fluid.pos;
+        particles.push(fluid);
+
+        let mut solid = *Particle::default().set_pos(Vec2::new(0.0, 0.8)).set_mass(solid_mass);
+        solid.pos_guess = solid.pos;
+        particles.push(solid);
+
+        particles
+    }
+
+    #[test]
+    fn test_buoyancy_lifts_light_solid() {
+        let mut particles = fluid_and_solid(0.5);
+
+        let mut constraints = FluidCouplingConstraintVec::new();
+        constraints.push(FluidCouplingConstraint::new(0, 1, 1.0, 0.0, false));
+        constraints.apply_buoyancy(&mut particles, &[], Vec2::new(0.0, -9.8));
+
+        assert!(particles[1].force.y > 0.0);
+        assert!(particles[0].force.y < 0.0);
+
+        // Momentum is conserved
+        let total = particles[0].force.y / particles[0].imass + particles[1].force.y / particles[1].imass;
+        assert!(total.abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_drag_matches_velocities() {
+        let mut particles = fluid_and_solid(1.0);
+        particles[1].pos -= Vec2::new(0.5, 0.0);
+        particles[0].scale_mass();
+        particles[1].scale_mass();
+
+        let c = FluidCouplingConstraint::new(0, 1, 0.0, 1.0, false);
+        c.project(&mut particles, &vec![1, 1]);
+
+        let vf = particles[0].pos_guess - particles[0].pos;
+        let vs = particles[1].pos_guess - particles[1].pos;
+        assert!((vf.x - vs.x).abs() < 0.0001);
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::f32::consts::PI;
 
-use crate::{core::math::{vec2::Vec2, vec3::Vec3}, simulation::particles::{particle::{Particle, Phase}, particle_vec::ParticleVec}};
+use crate::{core::math::{vec2::Vec2, vec3::Vec3}, simulation::particles::{particle::{Particle, Phase}, particle_vec::{ParticleRemap, ParticleVec}}};
 
 const H: f32 = 2.0;
 const H2: f32 = 4.0;
@@ -17,6 +17,7 @@ const E_P: f32 = 4.0;
 const DQ_P: f32 = 0.2;
 
 
+#[derive(Clone)]
 pub struct GasConstraint {
     pub p0: f32,
     pub neighbors: Vec<Vec<usize>>,
@@ -71,6 +72,10 @@ impl GasConstraint {
                     if p_j.imass == 0.0 {
                         continue;
                     }
+                    // Ignore particles on layers that don't collide
+                    if !p_i.collides_with(&p_j) {
+                        continue;
+                    }
                     let r = p_i.pos_guess - p_j.pos_guess;
                     let rlen2 = r.dot(r); //glm::dot(r, r);
                     if rlen2 < H2 {
@@ -195,8 +200,22 @@ impl GasConstraint {
         self.deltas.push(Vec2::new(0.0, 0.0));
         self.ps.push(index);
     }
+
+    pub fn remove_particle(&mut self, index: usize) {
+        self.neighbors.remove(index);
+        self.deltas.remove(index);
+        self.ps.remove(index);
+    }
+
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        remap.indices(&mut self.ps);
+        self.lambdas = self.lambdas.iter().filter_map(|(&i, &lambda)| remap.index(i).map(|i| (i, lambda))).collect();
+        self.neighbors = vec![Vec::<usize>::new(); self.ps.len()];
+        self.deltas = vec![Vec2::new(0.0, 0.0); self.ps.len()];
+    }
 }
 
+#[derive(Clone)]
 pub struct GasConstraintVec(pub Vec<GasConstraint>);
 
 impl GasConstraintVec {
@@ -220,6 +239,12 @@ impl GasConstraintVec {
         self.0.push(c);
     }
 
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        for c in self.0.iter_mut() {
+            c.remap_particles(remap);
+        }
+    }
+
     pub fn clear(&mut self) {
         self.0.clear();
     }
@@ -6,4 +6,6 @@ pub mod total_fluid_constraint;
 pub mod contact_constraint;
 pub mod gas_constraint;
 pub mod spring_constraint;
-pub mod volume_constraint;
\ No newline at end of file
+pub mod volume_constraint;
+pub mod collider_contact_constraint;
+pub mod fluid_coupling_constraint;
\ No newline at end of file
@@ -1,9 +1,10 @@
 use crate::{core::math::vec2::Vec2, simulation::particles::{body::Body, particle::Particle, particle_vec::ParticleVec}};
 
 
+#[derive(Clone)]
 pub struct RigidContactConstraint {
-   i1: usize,
-   i2: usize, 
+   pub i1: usize,
+   pub i2: usize, 
 
    n: Vec2,
    d: f32,
@@ -31,7 +32,7 @@ impl RigidContactConstraint {
             let x12 = p2.get_p(self.stable) - p1.get_p(self.stable);
             let len = x12.magnitude();
             let d = (p1.radius + p2.radius) - len;
-            if d < f32::EPSILON {
+            if d < f32::EPSILON || len == 0.0 {
                 return;
             }
             self.n = x12 / len;
@@ -142,6 +143,7 @@ impl RigidContactConstraint {
     }
 }
 
+#[derive(Clone)]
 pub struct RigidContactConstraintVec(pub Vec<RigidContactConstraint>);
 
 impl RigidContactConstraintVec {
@@ -1,5 +1,6 @@
-use crate::simulation::particles::particle_vec::ParticleVec;
+use crate::simulation::particles::particle_vec::{ParticleRemap, ParticleVec};
 
+#[derive(Clone)]
 pub struct SpringConstraint {
     pub d: f32,
     pub stiffness: f32,
@@ -73,11 +74,30 @@ impl SpringConstraint {
     }
 
     pub fn update_counts(&self, counts: &mut Vec<usize>) {
+        if !self.enabled {
+            return;
+        }
         counts[self.i1] += 1;
         counts[self.i2] += 1;
     }
+
+    /// A constraint that lost one of its particles is disabled for good, rather than removed, so constraint ids stay valid
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        match (remap.index(self.i1), remap.index(self.i2)) {
+            (Some(i1), Some(i2)) => {
+                self.i1 = i1;
+                self.i2 = i2;
+            }
+            _ => {
+                self.enabled = false;
+                self.i1 = usize::MAX;
+                self.i2 = usize::MAX;
+            }
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct SpringConstraintVec(pub Vec<SpringConstraint>);
 
 impl SpringConstraintVec {
@@ -100,6 +120,12 @@ impl SpringConstraintVec {
     pub fn push(&mut self, c: SpringConstraint) {
         self.0.push(c);
     }
+
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        for c in self.0.iter_mut() {
+            c.remap_particles(remap);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,14 +1,14 @@
 use std::collections::HashMap;
 use std::f32::consts::PI;
 
-use crate::{core::math::vec2::Vec2, simulation::particles::{particle::Phase, particle_vec::ParticleVec}};
+use crate::{core::math::vec2::Vec2, simulation::particles::{fluid_parameters::FluidParameters, particle::Phase, particle_vec::{ParticleRemap, ParticleVec}}};
 
 const H: f32 = 2.0;
 const H2: f32 = 4.0;
 const H6: f32 = 64.0;
 const H9: f32 = 512.0;
 
-const S_SOLID: f32 = 0.0; // Fluid-solid coupling constant
+const S_SOLID: f32 = 0.0; // Default fluid-solid coupling constant
 const RELAXATION: f32 = 0.01; // Epsilon in gamma correction denominator
 
 // Pressure terms
@@ -17,12 +17,33 @@ const E_P: f32 = 4.0;
 const DQ_P: f32 = 0.2;
 
 
+#[derive(Clone)]
 pub struct TotalFluidConstraint {
     pub p0: f32,
     pub neighbors: Vec<Vec<usize>>,
     pub ps: Vec<usize>,
     pub deltas: Vec<Vec2>,
     pub lambdas: HashMap<usize, f32>,
+
+    /// How much solid particles count towards the fluid density
+    pub s_solid: f32,
+
+    /// Scales the weight of displaced fluid pushing up on solids. Zero disables two-way coupling.
+    pub buoyancy: f32,
+
+    /// Fraction of the relative velocity between fluid and solid removed each iteration
+    pub drag: f32,
+
+    /// Akinci style cohesion and curvature minimisation
+    pub surface_tension: f32,
+
+    /// XSPH viscosity
+    pub viscosity: f32,
+
+    // scratch space for apply_velocity_forces, kept to save allocating it every step
+    slots: Vec<usize>, // particle index -> position in ps, only meaningful where ps has the particle there
+    normals: Vec<Vec2>,
+    dvs: Vec<Vec2>,
 }
 
 fn init_neighbours_and_deltas_for_size(size: usize) -> (Vec<Vec<usize>>, Vec<Vec2>) {
@@ -59,9 +80,32 @@ impl TotalFluidConstraint {
             ps,
             deltas,
             lambdas: HashMap::new(),
+            s_solid: S_SOLID,
+            buoyancy: 0.0,
+            drag: 0.0,
+            surface_tension: 0.0,
+            viscosity: 0.0,
+            slots: vec![],
+            normals: vec![],
+            dvs: vec![],
         }
     }
 
+    pub fn from_parameters(parameters: &FluidParameters, particles: &Vec<usize>) -> Self {
+        let mut c = Self::new(parameters.density, particles);
+        c.s_solid = parameters.s_solid;
+        c.buoyancy = parameters.buoyancy;
+        c.drag = parameters.drag;
+        c.surface_tension = parameters.surface_tension;
+        c.viscosity = parameters.viscosity;
+        c
+    }
+
+    /// Two-way coupled fluids push on solids using FluidCouplingConstraints instead of plain ContactConstraints
+    pub fn is_two_way_coupled(&self) -> bool {
+        self.buoyancy > 0.0 || self.drag > 0.0
+    }
+
     pub fn project(&mut self, estimates: &mut ParticleVec, counts: &Vec<usize>) {
         // Find neighboring particles and estimate pi for each particle
         self.lambdas.clear();
@@ -83,6 +127,10 @@ impl TotalFluidConstraint {
                     if p_j.imass == 0.0 {
                         continue;
                     }
+                    // Ignore particles on layers that don't collide
+                    if !p_i.collides_with(&p_j) {
+                        continue;
+                    }
                     let r = p_i.pos_guess - p_j.pos_guess;
                     let rlen2 = r.dot(r); //glm::dot(r, r);
                     if rlen2 < H2 {
@@ -91,7 +139,7 @@ impl TotalFluidConstraint {
                         self.neighbors[k].push(j);
                         let mut incr = poly6(rlen2) / p_j.imass;
                         if p_j.phase == Phase::Solid {
-                            incr *= S_SOLID;
+                            incr *= self.s_solid;
                         }
                         pi += incr;
 
@@ -157,6 +205,74 @@ impl TotalFluidConstraint {
         // do nothing
     }
 
+    /// Surface tension and viscosity work on velocities, so run these once the solver has updated them.
+    /// Only fluid particles of this constraint affect each other, found from the neighbours of the last projection.
+    pub fn apply_velocity_forces(&mut self, estimates: &mut ParticleVec, time_delta: f32) {
+        if self.surface_tension == 0.0 && self.viscosity == 0.0 {
+            return;
+        }
+
+        self.slots.resize(estimates.len(), 0);
+        for (a, &i) in self.ps.iter().enumerate() {
+            self.slots[i] = a;
+        }
+        let (ps, slots, neighbors) = (&self.ps, &self.slots, &self.neighbors);
+        let slot = |j: usize| slots.get(j).copied().filter(|&b| ps.get(b) == Some(&j));
+
+        // Surface normals, scaled so they are largest at the surface and vanish inside the fluid (Akinci et al. 2013)
+        self.normals.clear();
+        self.normals.resize(ps.len(), Vec2::new(0.0, 0.0));
+        if self.surface_tension != 0.0 {
+            for (a, &i) in ps.iter().enumerate() {
+                for &j in neighbors[a].iter() {
+                    if i == j || slot(j).is_none() {
+                        continue;
+                    }
+                    let r = estimates[i].pos - estimates[j].pos;
+                    let rlen = r.magnitude();
+                    if rlen < H {
+                        self.normals[a] += H * spiky_grad(&r, rlen) / (estimates[j].imass * self.p0);
+                    }
+                }
+            }
+        }
+
+        self.dvs.clear();
+        self.dvs.resize(ps.len(), Vec2::new(0.0, 0.0));
+        for (a, &i) in ps.iter().enumerate() {
+            for &j in neighbors[a].iter() {
+                let Some(b) = slot(j) else {
+                    continue;
+                };
+                if i == j {
+                    continue;
+                }
+                let r = estimates[i].pos - estimates[j].pos;
+                let rlen = r.magnitude();
+                if rlen >= H {
+                    continue;
+                }
+
+                if self.surface_tension != 0.0 {
+                    let mut cohesion = Vec2::new(0.0, 0.0);
+                    if rlen > 0.0 {
+                        cohesion = (cohesion_kernel(rlen) / estimates[j].imass) * (r / rlen);
+                    }
+                    let curvature = self.normals[a] - self.normals[b];
+                    self.dvs[a] -= self.surface_tension * (cohesion + curvature) * time_delta;
+                }
+
+                if self.viscosity != 0.0 {
+                    self.dvs[a] += self.viscosity * poly6(rlen * rlen) * (estimates[j].vel - estimates[i].vel);
+                }
+            }
+        }
+
+        for (a, &i) in ps.iter().enumerate() {
+            estimates[i].vel += self.dvs[a];
+        }
+    }
+
     pub fn grad(&self, estimates: &mut ParticleVec, k: usize, j: usize) -> Vec2 {
         let i = self.ps[k];
         let p_i = estimates[i]; // todo: make ref
@@ -172,7 +288,7 @@ impl TotalFluidConstraint {
             let p_j = estimates[self.neighbors[k][x]]; // todo: make ref
             let r = p_i.pos_guess - p_j.pos_guess;
             let rlen = r.magnitude(); //glm::length(r);
-            let mult = if p_j.phase == Phase::Solid { S_SOLID } else { 1.0 };
+            let mult = if p_j.phase == Phase::Solid { self.s_solid } else { 1.0 };
             out += mult * spiky_grad(&r, rlen);
         }
 
@@ -207,8 +323,18 @@ impl TotalFluidConstraint {
             self.deltas = deltas;
     //    }
     }
+
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        remap.indices(&mut self.ps);
+        self.lambdas = self.lambdas.iter().filter_map(|(&i, &lambda)| remap.index(i).map(|i| (i, lambda))).collect();
+
+        let (neighbors, deltas) = init_neighbours_and_deltas_for_size(self.ps.len());
+        self.neighbors = neighbors;
+        self.deltas = deltas;
+    }
 }
 
+#[derive(Clone)]
 pub struct TotalFluidConstraintVec(pub Vec<TotalFluidConstraint>);
 
 impl TotalFluidConstraintVec {
@@ -232,6 +358,12 @@ impl TotalFluidConstraintVec {
         self.0.push(c);
     }
 
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        for c in self.0.iter_mut() {
+            c.remap_particles(remap);
+        }
+    }
+
     pub fn clear(&mut self) {
         self.0.clear();
     }
@@ -270,6 +402,20 @@ pub fn poly6(r2: f32) -> f32 {
 //    return (H-r) / (H*H);
 }
 
+// Cohesion kernel from Akinci et al. 2013 - attracts particles further than H / 2 apart and repels closer ones
+pub fn cohesion_kernel(r: f32) -> f32 {
+    if r >= H || r <= 0.0 {
+        return 0.0;
+    }
+    let term = (H - r) * (H - r) * (H - r) * r * r * r;
+    let c = 32.0 / (PI * H9);
+    if 2.0 * r > H {
+        c * term
+    } else {
+        c * (2.0 * term - H6 / 64.0)
+    }
+}
+
 pub fn spiky_grad(r: &Vec2, rlen2: f32) -> Vec2 {
     if rlen2 >= H {
         return Vec2::new(0.0, 0.0);
@@ -280,3 +426,39 @@ pub fn spiky_grad(r: &Vec2, rlen2: f32) -> Vec2 {
     return -r.normalize() * (45.0 / (PI * H6)) * (H - rlen2) * (H - rlen2);
 //    return -r / (H*H*rlen);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::particles::particle::Particle;
+
+    #[test]
+    fn test_cohesion_kernel_attracts_and_repels() {
+        assert!(cohesion_kernel(H * 0.1) < 0.0);
+        assert!(cohesion_kernel(H * 0.75) > 0.0);
+        assert_eq!(cohesion_kernel(H), 0.0);
+    }
+
+    #[test]
+    fn test_viscosity_blends_velocities() {
+        let mut particles = ParticleVec::new();
+
+        let mut p1 = *Particle::default().set_pos(Vec2::new(0.0, 0.0)).set_mass_2(1.0);
+        p1.pos_guess = p1.pos;
+        p1.vel = Vec2::new(1.0, 0.0);
+        particles.push(p1);
+
+        let mut p2 = *Particle::default().set_pos(Vec2::new(0.5, 0.0)).set_mass_2(1.0);
+        p2.pos_guess = p2.pos;
+        p2.vel = Vec2::new(-1.0, 0.0);
+        particles.push(p2);
+
+        // the particles find each other as neighbours while the constraint is projected
+        let mut c = TotalFluidConstraint::from_parameters(FluidParameters::new(1.0).set_viscosity(1.0), &vec![0, 1]);
+        c.project(&mut particles, &vec![0; 2]);
+        c.apply_velocity_forces(&mut particles, 0.01);
+
+        assert!(particles[0].vel.x < 1.0 && particles[0].vel.x > 0.0);
+        assert!((particles[0].vel.x + particles[1].vel.x).abs() < 0.0001);
+    }
+}
@@ -1,5 +1,6 @@
-use crate::{core::math::vec2::Vec2, simulation::particles::particle_vec::ParticleVec};
+use crate::{core::math::vec2::Vec2, simulation::particles::particle_vec::{ParticleRemap, ParticleVec}};
 
+#[derive(Clone)]
 pub struct VolumeConstraint {
     pub rest_volume: f32,
     pub compliance: f32,
@@ -83,8 +84,19 @@ impl VolumeConstraint {
             counts[idx] += 1;
         }
     }
+
+    /// The rest volume no longer means anything once the outline loses a particle, so the constraint is disabled for good
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        if self.particle_indices.iter().any(|&i| remap.is_removed(i)) {
+            self.enabled = false;
+            self.particle_indices.clear();
+        } else {
+            remap.indices(&mut self.particle_indices);
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct VolumeConstraintVec(pub Vec<VolumeConstraint>);
 
 impl VolumeConstraintVec {
@@ -108,6 +120,12 @@ impl VolumeConstraintVec {
         self.0.push(c);
     }
 
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        for c in self.0.iter_mut() {
+            c.remap_particles(remap);
+        }
+    }
+
     pub fn clear(&mut self) {
         self.0.clear();
     }
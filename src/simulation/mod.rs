@@ -1,2 +1,3 @@
 pub mod particles;
 pub mod constraints;
+pub mod colliders;
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 use std::f32::consts::PI;
 
-use crate::{core::math::vec2::Vec2, simulation::particles::{particle_vec::ParticleVec, sdf_data::SdfData}};
+use crate::{core::math::vec2::Vec2, simulation::particles::{particle_vec::{ParticleRemap, ParticleVec}, sdf_data::SdfData}};
 
+#[derive(Clone)]
 pub struct Body {
     pub particle_indicies: Vec<usize>,
     pub center: Vec2,
@@ -44,6 +45,7 @@ impl Body {
         // Recompute angle delta guess
         self.angle = 0.0;
         let mut prev = 0.0;
+        let mut angle_mass = 0.0; // particles at the center don't contribute to the angle
         for i in 0..self.particle_indicies.len() {
             let index = self.particle_indicies[i];
 
@@ -76,8 +78,11 @@ impl Body {
             prev = next;
             next /= p.imass;
             self.angle += next;
+            angle_mass += 1.0 / p.imass;
+        }
+        if angle_mass > 0.0 {
+            self.angle /= angle_mass;
         }
-        self.angle *= self.imass;
     }
 
     pub fn compute_rs(&mut self, estimates: &ParticleVec) {
@@ -95,6 +100,77 @@ impl Body {
         self.imass = 1.0 / imass;
     }
 
+    /// Remove a particle, e.g. when it melts. The remaining particles keep their current shape.
+    pub fn remove_particle(&mut self, index: usize, estimates: &ParticleVec) {
+        self.remove_particles(&[index], estimates);
+    }
+
+    pub fn remove_particles(&mut self, indices: &[usize], estimates: &ParticleVec) {
+        self.particle_indicies.retain(|i| !indices.contains(i));
+        for index in indices {
+            self.rs.remove(index);
+            self.sdf.remove(index);
+        }
+        self.reshape(estimates);
+    }
+
+    /// Update indices after particles were removed from the simulation. Removed particles need taking out with remove_particles first.
+    pub fn remap_particles(&mut self, remap: &ParticleRemap) {
+        remap.indices(&mut self.particle_indicies);
+        self.rs = self.rs.iter().filter_map(|(&i, &r)| remap.index(i).map(|i| (i, r))).collect();
+        self.sdf = self.sdf.iter().filter_map(|(&i, &sdf)| remap.index(i).map(|i| (i, sdf))).collect();
+    }
+
+    /// Add a particle, e.g. when it freezes on to the body
+    pub fn add_particle(&mut self, index: usize, sdf: SdfData, estimates: &ParticleVec) {
+        self.particle_indicies.push(index);
+        self.sdf.insert(index, sdf);
+        self.reshape(estimates);
+    }
+
+    // Make the current particle positions the rest shape of the body
+    fn reshape(&mut self, estimates: &ParticleVec) {
+        if self.particle_indicies.is_empty() {
+            self.rs.clear();
+            return;
+        }
+
+        let mut total_mass = 0.0;
+        for i in 0..self.particle_indicies.len() {
+            total_mass += 1.0 / estimates[self.particle_indicies[i]].imass;
+        }
+        self.imass = 1.0 / total_mass;
+
+        // A single particle has nothing to rotate around
+        if self.particle_indicies.len() == 1 {
+            let index = self.particle_indicies[0];
+            self.center = estimates[index].pos;
+            self.angle = 0.0;
+            self.rs.clear();
+            self.rs.insert(index, Vec2::new(0.0, 0.0));
+            return;
+        }
+
+        self.update_com(estimates, false);
+
+        // The SDF is stored in the rest frame, which is about to become the current frame
+        let angle = self.angle;
+        for sdf in self.sdf.values_mut() {
+            sdf.rotate(angle);
+        }
+        self.angle = 0.0;
+
+        self.compute_rs(estimates);
+        self.imass = 1.0 / total_mass;
+
+        // A particle at (or within rounding of) the center has no meaningful angle, update_com skips zero rs
+        for (&idx, r) in self.rs.iter_mut() {
+            if r.magnitude() < 0.01 * estimates[idx].radius {
+                *r = Vec2::new(0.0, 0.0);
+            }
+        }
+    }
+
     pub fn for_each_particle<F>(&self, mut f: F)
     where
         F: FnMut(usize),
@@ -3,6 +3,7 @@ use rand_pcg::Pcg64;
 
 use crate::{core::math::vec2::Vec2, simulation::{constraints::total_fluid_constraint::TotalFluidConstraintVec, particles::{particle::{Particle, Phase}, particle_vec::ParticleVec}}};
 
+#[derive(Clone)]
 pub struct FluidEmitter {
     posn: Vec2,
     particles_per_sec: f32,
@@ -10,6 +11,7 @@ pub struct FluidEmitter {
     total_timer: f32,
     fluid_index: usize, // TotalFluidConstraint *m_fs;
     rng: Pcg64,
+    temperature: f32,
     // glm::dvec2 m_posn;
     // double m_particles_per_sec;
     // double timer;
@@ -27,9 +29,15 @@ impl FluidEmitter {
             total_timer: 0.0,
             fluid_index,
             rng,
+            temperature: 20.0,
         }
     }
 
+    pub fn set_temperature(&mut self, temperature: f32) -> &mut Self {
+        self.temperature = temperature;
+        self
+    }
+
 
     pub fn tick(&mut self, estimates: &mut ParticleVec, secs: f32, global_standard_total_fluid_constraints: &mut TotalFluidConstraintVec) { 
         //let mut i = global_standard_total_fluid_constraints[self.fluid_index].ps.len()-1;
@@ -108,7 +116,7 @@ impl FluidEmitter {
                 let particle_diam = 0.5;
                 let particle_rad = particle_diam / 2.0;
 
-                let mut p = *Particle::default().set_radius(particle_rad).set_pos(self.posn).set_mass_2(1.0).set_phase(Phase::Fluid);
+                let mut p = *Particle::default().set_radius(particle_rad).set_pos(self.posn).set_mass_2(1.0).set_phase(Phase::Fluid).set_temperature(self.temperature);
 
                 let r1: f32 = self.rng.random();
 
@@ -0,0 +1,59 @@
+// Per-fluid settings passed to Simulation::create_fluid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FluidParameters {
+    /// Rest density. Higher density lets particles get closer together.
+    pub density: f32,
+
+    /// How much solid particles count towards the fluid density
+    pub s_solid: f32,
+
+    /// Scales the weight of displaced fluid pushing up on solids. Zero disables two-way coupling.
+    pub buoyancy: f32,
+
+    /// Fraction of the relative velocity between fluid and solid removed each iteration
+    pub drag: f32,
+
+    /// Akinci style cohesion and curvature minimisation. Pulls the fluid into droplets.
+    pub surface_tension: f32,
+
+    /// XSPH viscosity. Blends each particles velocity with its neighbours. Around 0.01 for water, higher for honey or slime.
+    pub viscosity: f32,
+}
+
+impl FluidParameters {
+    pub fn new(density: f32) -> Self {
+        Self {
+            density,
+            s_solid: 0.0,
+            buoyancy: 0.0,
+            drag: 0.0,
+            surface_tension: 0.0,
+            viscosity: 0.0,
+        }
+    }
+
+    pub fn set_solid_coupling(&mut self, s_solid: f32) -> &mut Self {
+        self.s_solid = s_solid;
+        self
+    }
+
+    pub fn set_buoyancy(&mut self, buoyancy: f32) -> &mut Self {
+        self.buoyancy = buoyancy;
+        self
+    }
+
+    pub fn set_drag(&mut self, drag: f32) -> &mut Self {
+        self.drag = drag;
+        self
+    }
+
+    pub fn set_surface_tension(&mut self, surface_tension: f32) -> &mut Self {
+        self.surface_tension = surface_tension;
+        self
+    }
+
+    pub fn set_viscosity(&mut self, viscosity: f32) -> &mut Self {
+        self.viscosity = viscosity;
+        self
+    }
+}
@@ -10,5 +10,7 @@ pub mod body;
 pub mod sdf_data;
 pub mod open_smoke_emitter;
 pub mod fluid_emitter;
+pub mod fluid_parameters;
+pub mod phase_transition;
 pub mod simulation_demos;
 pub mod spatial_hash;
\ No newline at end of file
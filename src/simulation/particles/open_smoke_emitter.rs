@@ -2,6 +2,7 @@ use std::f32::consts::PI;
 
 use crate::{core::math::vec2::Vec2, simulation::{constraints::gas_constraint::GasConstraintVec, particles::{particle::{Particle, Phase}, particle_vec::ParticleVec}}};
 
+#[derive(Clone)]
 pub struct OpenSmokeEmitter {
 
     pub posn: Vec2,
@@ -16,6 +16,16 @@ pub enum Phase {
     Gas,
 }
 
+/// The layer particles are on unless told otherwise
+pub const COLLISION_LAYER_DEFAULT: u32 = 0x1;
+
+/// Soft bodies made from chains or rings of particles, which would otherwise jam against their own neighbours.
+/// Particles on this layer don't collide with each other but still collide with everything else.
+pub const COLLISION_LAYER_SOFT_BODY: u32 = 0x2;
+
+/// Mask to collide with every layer
+pub const COLLISION_MASK_ALL: u32 = u32::MAX;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Particle {
     pub index: usize, // for debugging
@@ -47,6 +57,11 @@ pub struct Particle {
     pub force: Vec2,
     pub body: isize, // body (if any) this particle belongs to, for disabling collisions
 
+    /// Bitfield of the layers this particle is on. A layer of zero collides with nothing.
+    pub collision_layer: u32,
+    /// Bitfield of the layers this particle collides with. Both particles need to be on a layer the other collides with for them to collide.
+    pub collision_mask: u32,
+
     pub imass: f32, // inverse mass
     pub tmass: f32, // temporary height-scaled mass
 
@@ -60,6 +75,9 @@ pub struct Particle {
     /// drag/resistance is applied to the sliding motion, slowing it down but not necessarily stopping it instantly.
     pub k_friction: f32, // coeffs of friction
     pub t: f32,
+
+    /// Heat of the particle. Simulation::phase_transitions move particles between fluids, gases and bodies when this crosses a threshold.
+    pub temperature: f32,
 }
 
 impl Particle {
@@ -208,11 +226,27 @@ impl Particle {
         self
     }
 
+    pub fn set_temperature(&mut self, temperature: f32) -> &mut Self {
+        debug_assert!(!temperature.is_nan());
+        self.temperature = temperature;
+        self
+    }
+
     pub fn set_phase(&mut self, phase: Phase) -> &mut Self {
         self.phase = phase;
         self
     }
 
+    pub fn set_collision_layer(&mut self, collision_layer: u32, collision_mask: u32) -> &mut Self {
+        self.collision_layer = collision_layer;
+        self.collision_mask = collision_mask;
+        self
+    }
+
+    pub fn collides_with(&self, other: &Particle) -> bool {
+        (self.collision_layer & other.collision_mask) != 0 && (other.collision_layer & self.collision_mask) != 0
+    }
+
     pub fn get_sdf_data(&self, bodies: &Vec<Body>, idx: usize) -> SdfData {
         if self.phase != Phase::Solid || self.body < 0 {
             return SdfData::new(Vec2::new(0.0, 0.0), 0.0);
@@ -274,12 +308,15 @@ impl Default for Particle {
             pos_guess: Vec2::new(0.0, 0.0),
             force: Vec2::new(0.0, 0.0),
             body: -1,
+            collision_layer: COLLISION_LAYER_DEFAULT,
+            collision_mask: COLLISION_MASK_ALL,
 
             imass: 0.0,
             tmass: 0.0,
             s_friction: 0.0,
             k_friction: 0.0,
             t: 4.0,
+            temperature: 20.0,
         };
         s.set_mass_2(s.mass);
         s
@@ -301,4 +338,16 @@ mod tests {
         let p = Particle::default();
         assert_eq!(p.pos, Vec2::new(0.0, 0.0));
     }
+
+    #[test]
+    fn collision_layers() {
+        let p = Particle::default();
+        let soft_body = *Particle::default().set_collision_layer(COLLISION_LAYER_SOFT_BODY, !COLLISION_LAYER_SOFT_BODY);
+        let no_collisions = *Particle::default().set_collision_layer(0x0, COLLISION_MASK_ALL);
+
+        assert!(p.collides_with(&p));
+        assert!(p.collides_with(&soft_body) && soft_body.collides_with(&p));
+        assert!(!soft_body.collides_with(&soft_body));
+        assert!(!no_collisions.collides_with(&p) && !p.collides_with(&no_collisions));
+    }
 }
\ No newline at end of file
@@ -3,15 +3,84 @@ use std::{ops::{Index, IndexMut}, slice::{Iter, IterMut}};
 use crate::simulation::particles::particle::Particle;
 
 
-//pub struct ParticleHandle(pub usize);
-pub type ParticleHandle = usize;
+/// Refers to a particle in a ParticleVec. Removing particles moves the ones after them, so a handle also remembers
+/// the generation of the slot it was made for. Handles to particles that moved and were not brought up to date with
+/// ParticleRemap::handle are stale, see ParticleVec::is_valid. Handles to particles that stayed put are still good.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParticleHandle {
+    index: usize,
+    generation: u32,
+}
+
+impl ParticleHandle {
+    pub fn index(&self) -> usize {
+        self.index
+    }
 
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Where particles moved to after ParticleVec::remove. Anything holding particle indices or handles needs updating with this.
+pub struct ParticleRemap {
+    new_indices: Vec<Option<usize>>, // old index -> new index, None if removed
+    old_generations: Vec<u32>, // of each slot before the removal
+    new_generations: Vec<u32>, // of each slot after the removal
+}
+
+impl ParticleRemap {
+    /// The new index of a particle, None if it was removed
+    pub fn index(&self, old_index: usize) -> Option<usize> {
+        self.new_indices.get(old_index).copied().flatten()
+    }
 
-pub struct ParticleVec(pub Vec<Particle>);
+    /// The up to date handle of a particle, None if it was removed or the handle was already stale
+    pub fn handle(&self, handle: ParticleHandle) -> Option<ParticleHandle> {
+        if self.old_generations.get(handle.index) != Some(&handle.generation) {
+            return None;
+        }
+        self.index(handle.index).map(|index| ParticleHandle { index, generation: self.new_generations[index] })
+    }
+
+    /// Update a list of indices in place, dropping removed particles
+    pub fn indices(&self, indices: &mut Vec<usize>) {
+        indices.retain_mut(|i| match self.index(*i) {
+            Some(new_index) => {
+                *i = new_index;
+                true
+            }
+            None => false,
+        });
+    }
+
+    /// Update a list of handles in place, dropping removed particles
+    pub fn handles(&self, handles: &mut Vec<ParticleHandle>) {
+        handles.retain_mut(|h| match self.handle(*h) {
+            Some(new_handle) => {
+                *h = new_handle;
+                true
+            }
+            None => false,
+        });
+    }
+
+    pub fn is_removed(&self, old_index: usize) -> bool {
+        self.index(old_index).is_none()
+    }
+
+    pub fn removed_count(&self) -> usize {
+        self.new_indices.iter().filter(|i| i.is_none()).count()
+    }
+}
+
+
+#[derive(Clone)]
+pub struct ParticleVec(pub Vec<Particle>, Vec<u32>); // particles, generation of each slot (incremented each time the particle in it is removed or moved)
 
 impl<const N: usize> From<[Particle; N]> for ParticleVec {
     fn from(s: [Particle; N]) -> Self {
-        let mut se = Self(Vec::<Particle>::from(s));
+        let mut se = Self(Vec::<Particle>::from(s), vec![0; N]);
         
         // Update indicies of newly added particles.
         for i in 0..se.0.len() {
@@ -23,13 +92,11 @@ impl<const N: usize> From<[Particle; N]> for ParticleVec {
 
 impl ParticleVec {
     pub fn new() -> Self {
-        Self {
-            0: vec![],
-        }
+        Self(vec![], vec![])
     }
 
     pub fn clear(&mut self) {
-        self.0.clear();
+        self.truncate(0);
     }
 
     pub fn as_slice(&self) -> &[Particle] {
@@ -46,15 +113,18 @@ impl ParticleVec {
 
     pub fn push(&mut self, value: Particle) {
         self.0.push(*value.clone().set_index(self.0.len()));
+        self.add_slots();
     }
 
     pub fn truncate(&mut self, len: usize) {
+        self.retire_slots(len);
         self.0.truncate(len);
     }
 
     pub fn extend(&mut self, other_vec: &ParticleVec) {
         let index_start = self.0.len();
         self.0.extend(other_vec.0.clone()); // Is there a non-clone way to do this?
+        self.add_slots();
 
         // Update indicies of newly added particles.
         for i in index_start..self.0.len() {
@@ -62,6 +132,63 @@ impl ParticleVec {
         }
     }
 
+    /// A handle to the particle currently at index
+    pub fn handle(&self, index: usize) -> ParticleHandle {
+        ParticleHandle { index, generation: self.1[index] }
+    }
+
+    /// False if the particle was removed or moved since the handle was made (and it wasn't remapped)
+    pub fn is_valid(&self, handle: ParticleHandle) -> bool {
+        handle.index < self.0.len() && handle.generation == self.1[handle.index]
+    }
+
+    // make sure every particle has a slot. Slots are kept after their particle is removed, so a new particle in one gets a fresh generation.
+    fn add_slots(&mut self) {
+        if self.1.len() < self.0.len() {
+            self.1.resize(self.0.len(), 0);
+        }
+    }
+
+    // the particles from index on are about to be removed or moved, so handles to them go stale
+    fn retire_slots(&mut self, index: usize) {
+        for generation in self.1.iter_mut().take(self.0.len()).skip(index) {
+            *generation = generation.wrapping_add(1);
+        }
+    }
+
+    /// Remove particles, keeping the order of the rest. Everything else holding indices in to this needs updating with the returned remap.
+    pub fn remove(&mut self, indices: &[usize]) -> ParticleRemap {
+        let mut new_indices: Vec<Option<usize>> = (0..self.0.len()).map(Some).collect();
+        for &i in indices {
+            new_indices[i] = None;
+        }
+
+        for (next, new_index) in new_indices.iter_mut().flatten().enumerate() {
+            *new_index = next;
+        }
+
+        // everything before the first removed particle stays put
+        let old_generations = self.1.clone();
+        let first_moved = new_indices.iter().position(|i| i.is_none()).unwrap_or(self.0.len());
+        self.retire_slots(first_moved);
+
+        let mut old_index = 0;
+        self.0.retain(|_| {
+            let keep = new_indices[old_index].is_some();
+            old_index += 1;
+            keep
+        });
+        for i in 0..self.0.len() {
+            self.0[i].set_index(i);
+        }
+
+        ParticleRemap {
+            new_indices,
+            old_generations,
+            new_generations: self.1.clone(),
+        }
+    }
+
     pub fn iter(&self) -> Iter<'_, Particle> {
         self.0.iter()
     }
@@ -96,6 +223,22 @@ impl IndexMut<usize> for ParticleVec {
     }
 }
 
+impl Index<ParticleHandle> for ParticleVec {
+    type Output = Particle;
+
+    fn index(&self, handle: ParticleHandle) -> &Self::Output {
+        debug_assert!(self.is_valid(handle), "Stale particle handle {:?}", handle);
+        &self.0[handle.index]
+    }
+}
+
+impl IndexMut<ParticleHandle> for ParticleVec {
+    fn index_mut(&mut self, handle: ParticleHandle) -> &mut Self::Output {
+        debug_assert!(self.is_valid(handle), "Stale particle handle {:?}", handle);
+        &mut self.0[handle.index]
+    }
+}
+
 // impl Default for ParticleVec {
 //     fn default() -> Self {
 //         Self {
@@ -126,4 +269,53 @@ mod tests {
         let ps = ParticleVec::from([Particle::default(), Particle::default()]);
         assert_eq!(ps.len(), 2);
     }
+
+    #[test]
+    fn remove() {
+        let mut ps = ParticleVec::from([Particle::default(), Particle::default(), Particle::default(), Particle::default()]);
+        let h1 = ps.handle(1);
+        let h3 = ps.handle(3);
+
+        let remap = ps.remove(&[0, 2]);
+        assert_eq!(ps.len(), 2);
+        assert_eq!(ps[1].index, 1);
+        assert_eq!(remap.index(0), None);
+        assert_eq!(remap.index(1), Some(0));
+        assert_eq!(remap.index(3), Some(1));
+        assert_eq!(remap.removed_count(), 2);
+
+        let mut indices = vec![3, 2, 1];
+        remap.indices(&mut indices);
+        assert_eq!(indices, vec![1, 0]);
+
+        // Old handles are stale until remapped
+        assert!(!ps.is_valid(h3));
+        let h3 = remap.handle(h3).unwrap();
+        assert!(ps.is_valid(h3));
+        assert_eq!(h3.index(), 1);
+
+        // Remapping again with the next removal doesn't accept a handle that missed a remap
+        let remap = ps.remove(&[]);
+        assert_eq!(remap.handle(h1), None);
+        assert_eq!(remap.handle(h3).map(|h| h.index()), Some(1));
+    }
+
+    #[test]
+    fn remove_keeps_handles_that_stay_put() {
+        let mut ps = ParticleVec::from([Particle::default(), Particle::default(), Particle::default()]);
+        let h0 = ps.handle(0);
+        let h1 = ps.handle(1);
+
+        // particles before the ones removed don't move, so their handles are still good without a remap
+        ps.remove(&[1]);
+        assert!(ps.is_valid(h0));
+        assert!(!ps.is_valid(h1));
+
+        // a new particle in a slot that was emptied isn't mistaken for the one that was there
+        ps.truncate(1);
+        ps.push(Particle::default());
+        assert!(!ps.is_valid(h1));
+        assert_ne!(ps.handle(1), h1);
+        assert!(ps.is_valid(h0));
+    }
 }
\ No newline at end of file
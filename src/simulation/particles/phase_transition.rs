@@ -0,0 +1,73 @@
+use crate::core::math::vec4::Vec4;
+
+/// What simulates a particle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PhaseOwner {
+    /// Index into Simulation::global_standard_total_fluid_constraints
+    Fluid(usize),
+
+    /// Index into Simulation::global_standard_gas_constraints
+    Gas(usize),
+
+    /// Index into Simulation::bodies
+    Body(usize),
+
+    /// An immovable solid particle, e.g. lava that has cooled into rock
+    Static,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TemperatureThreshold {
+    Above(f32),
+    Below(f32),
+}
+
+impl TemperatureThreshold {
+    pub fn is_crossed(&self, temperature: f32) -> bool {
+        match *self {
+            TemperatureThreshold::Above(t) => temperature > t,
+            TemperatureThreshold::Below(t) => temperature < t,
+        }
+    }
+}
+
+/// Moves particles from one owner to another when their temperature crosses a threshold.
+/// For example: lava (a fluid) cooling below a temperature becomes static rock, or ice (a rigid body) warming up melts into water (a fluid).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseTransition {
+    pub from: PhaseOwner,
+    pub to: PhaseOwner,
+    pub threshold: TemperatureThreshold,
+
+    /// Particles are recoloured when they transition, if set
+    pub colour: Option<Vec4>,
+}
+
+impl PhaseTransition {
+    pub fn new(from: PhaseOwner, to: PhaseOwner, threshold: TemperatureThreshold) -> Self {
+        Self {
+            from,
+            to,
+            threshold,
+            colour: None,
+        }
+    }
+
+    pub fn set_colour(&mut self, colour: Vec4) -> &mut Self {
+        self.colour = Some(colour);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold() {
+        assert!(TemperatureThreshold::Below(100.0).is_crossed(99.0));
+        assert!(!TemperatureThreshold::Below(100.0).is_crossed(101.0));
+        assert!(TemperatureThreshold::Above(0.0).is_crossed(1.0));
+        assert!(!TemperatureThreshold::Above(0.0).is_crossed(-1.0));
+    }
+}
@@ -45,7 +45,7 @@ impl AdjacentSticks {
             //stick.set_particle_handles(particle_handles).set_length(dist);
             //stick_vec.push(stick);
 
-            let id = sim.add_distance_constraint(DistanceConstraint::new(dist, particle_handles[0], particle_handles[1], false));
+            let id = sim.add_distance_constraint(DistanceConstraint::new(dist, particle_handles[0].index(), particle_handles[1].index(), false));
             constraint_ids.push(id);
             //sim.add_spring_constraint(SpringConstraint::new(dist, 0.001, particle_handles[0], particle_handles[1], false));
         }
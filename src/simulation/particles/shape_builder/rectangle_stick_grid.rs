@@ -1,5 +1,3 @@
-use crate::simulation::particles::particle_vec::ParticleHandle;
-
 use super::rectangle::Rectangle;
 
 /// Takes a Rectangle and created stick constraints in a grid layout between them
@@ -23,11 +21,11 @@ impl RectangleStickGrid {
     //     shape_builder.add_constraint(constraint);
     // }
 
-    pub fn compute_particle_pairs(&self, particle_radius: f32, particle_vec_start_index: usize) -> Vec<[ParticleHandle; 2]> {
+    pub fn compute_particle_pairs(&self, particle_radius: f32, particle_vec_start_index: usize) -> Vec<[usize; 2]> {
         let radius = particle_radius; //shape_builder.particle_radius();
         //self.rectangle.apply_to_shape_builder(shape_builder);
 
-        let mut particle_pairs: Vec<[ParticleHandle; 2]> = vec![];
+        let mut particle_pairs: Vec<[usize; 2]> = vec![];
 
         let (x_divisions, y_divisions, _x_delta, _y_delta) = self.rectangle.get_divisions_and_deltas_for_radius(radius);
 
@@ -131,7 +131,7 @@ impl ShapeBuilder {
         (*particle_vec).extend(&self.particles);
 
         for i in start_index..particle_vec.len() {
-            self.particle_handles.push(i);
+            self.particle_handles.push(particle_vec.handle(i));
         }
         // let mut particle_handles = (*particle_vec).extend(&self.particles);
         // self.particle_handles.append(&mut particle_handles);
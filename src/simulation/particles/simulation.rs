@@ -1,10 +1,11 @@
 use std::isize;
 
 use rand_pcg::Pcg64;
-use crate::{core::math::vec2::Vec2, simulation::{constraints::{boundary_constraint::{BoundaryConstraint, BoundaryConstraintVec}, contact_constraint::{ContactConstraint, ContactConstraintVec}, distance_constraint::{DistanceConstraint, DistanceConstraintVec}, gas_constraint::{GasConstraint, GasConstraintVec}, rigid_contact_constraint::{RigidContactConstraint, RigidContactConstraintVec}, spring_constraint::{SpringConstraint, SpringConstraintVec}, total_fluid_constraint::{TotalFluidConstraint, TotalFluidConstraintVec}, total_shape_constraint::TotalShapeConstraint, volume_constraint::{VolumeConstraint, VolumeConstraintVec}}, particles::{body::Body, fluid_emitter::FluidEmitter, open_smoke_emitter::OpenSmokeEmitter, particle::{Particle, Phase}, particle_vec::ParticleVec, sdf_data::SdfData, spatial_hash::SpatialHash}}};
+use crate::{core::math::{aabb2d::Aabb2d, vec2::Vec2}, simulation::{colliders::collider::Collider, constraints::{boundary_constraint::{BoundaryConstraint, BoundaryConstraintVec}, collider_contact_constraint::{ColliderContactConstraint, ColliderContactConstraintVec}, contact_constraint::{ContactConstraint, ContactConstraintVec}, distance_constraint::{DistanceConstraint, DistanceConstraintVec}, fluid_coupling_constraint::{FluidCouplingConstraint, FluidCouplingConstraintVec, FLUID_COUPLING_RANGE}, gas_constraint::{GasConstraint, GasConstraintVec}, rigid_contact_constraint::{RigidContactConstraint, RigidContactConstraintVec}, spring_constraint::{SpringConstraint, SpringConstraintVec}, total_fluid_constraint::{TotalFluidConstraint, TotalFluidConstraintVec}, total_shape_constraint::TotalShapeConstraint, volume_constraint::{VolumeConstraint, VolumeConstraintVec}}, particles::{body::Body, fluid_emitter::FluidEmitter, fluid_parameters::FluidParameters, open_smoke_emitter::OpenSmokeEmitter, particle::{Particle, Phase}, particle_vec::{ParticleRemap, ParticleVec}, phase_transition::{PhaseOwner, PhaseTransition}, sdf_data::SdfData, spatial_hash::SpatialHash}}};
 
 
 
+#[derive(Clone)]
 pub struct Simulation {
     pub particles: ParticleVec,
     pub gravity: Vec2,
@@ -13,10 +14,13 @@ pub struct Simulation {
     pub y_boundaries: Vec2,
 
     pub bodies: Vec<Body>,
+    pub colliders: Vec<Collider>,
 
     pub contact_boundary_constraints: BoundaryConstraintVec,
+    pub contact_collider_constraints: ColliderContactConstraintVec,
     pub contact_rigid_contact_constraints: RigidContactConstraintVec,
     pub contact_contact_constraints: ContactConstraintVec,
+    pub contact_fluid_coupling_constraints: FluidCouplingConstraintVec,
 
     pub distance_constraints: DistanceConstraintVec,
     pub spring_constraints: SpringConstraintVec,
@@ -27,10 +31,19 @@ pub struct Simulation {
     pub smoke_emitters: Vec<OpenSmokeEmitter>,
     pub fluid_emitters: Vec<FluidEmitter>,
 
+    pub phase_transitions: Vec<PhaseTransition>,
+    pub ambient_temperature: f32,
+    pub ambient_heat_transfer: f32, // rate particles move towards the ambient temperature, 0 to disable
+    pub contact_heat_transfer: f32, // rate heat flows between touching particles, 0 to disable
+    pub stabilize: Vec<usize>, // particles that changed phase or were emitted last step
+
     pub counts: Vec<usize>,
     pub body_count: usize,
     
     pub rng: Pcg64,
+
+    /// Particles by where they were at the start of the last step. Empty until the first step.
+    pub spatial_hash: SpatialHash<usize, 1>,
 }
 
 impl Simulation {
@@ -43,11 +56,14 @@ impl Simulation {
             y_boundaries: Vec2::new(-100.0,100.0),
 
             bodies: vec![],
+            colliders: vec![],
 
             // CONTACT group:
             contact_boundary_constraints: BoundaryConstraintVec::new(),
+            contact_collider_constraints: ColliderContactConstraintVec::new(),
             contact_rigid_contact_constraints: RigidContactConstraintVec::new(),
             contact_contact_constraints: ContactConstraintVec::new(),
+            contact_fluid_coupling_constraints: FluidCouplingConstraintVec::new(),
             // CONTACT group end.
 
             distance_constraints: DistanceConstraintVec::new(),
@@ -59,9 +75,16 @@ impl Simulation {
             smoke_emitters: vec![],
             fluid_emitters: vec![],
 
+            phase_transitions: vec![],
+            ambient_temperature: 20.0,
+            ambient_heat_transfer: 0.0,
+            contact_heat_transfer: 0.0,
+            stabilize: vec![],
+
             counts: vec![],
             body_count: 0,
             rng,
+            spatial_hash: SpatialHash::new(),
         }
     }
 
@@ -69,8 +92,10 @@ impl Simulation {
         // https://github.com/ebirenbaum/ParticleSolver/blob/master/cpu/src/simulation.cpp
 
         debug_assert!(self.contact_boundary_constraints.len() == 0);
+        debug_assert!(self.contact_collider_constraints.is_empty());
         debug_assert!(self.contact_rigid_contact_constraints.len() == 0);
         debug_assert!(self.contact_contact_constraints.len() == 0);
+        debug_assert!(self.contact_fluid_coupling_constraints.is_empty());
         debug_assert!(self.counts.len() == 0);
 
         // Add all rigid body shape constraints
@@ -111,21 +136,48 @@ impl Simulation {
         // m_contactSolver.setupM(&m_particles, true);
 
 
-        // Use SpatialHash to speed up particle collision checking
-        // Ideally we keep spatial hash as part of self to save memory reallocation.
-        let mut spatial_hash = SpatialHash::<usize, 1>::new();
+        // Use SpatialHash to speed up particle collision checking.
+        // It is kept as part of self to save memory reallocation, and so it can be queried between steps.
+        let mut spatial_hash = std::mem::take(&mut self.spatial_hash);
+        spatial_hash.soft_clear();
         for i in 0..particle_count {
             let p = &mut self.particles[i];
             let aabb = p.get_aabb();
             spatial_hash.insert_aabb(aabb, i);
         }
 
+        // Map fluid particles to the two-way coupled fluid they belong to
+        let mut coupled_fluid = vec![usize::MAX; particle_count];
+        let mut has_coupled_fluid = false;
+        for (f, fluid) in self.global_standard_total_fluid_constraints.0.iter().enumerate() {
+            if fluid.is_two_way_coupled() {
+                has_coupled_fluid = true;
+                for &k in fluid.ps.iter() {
+                    coupled_fluid[k] = f;
+                }
+            }
+        }
+
+        // Fluid particles sit closer together than their radius, and emitters can spawn particles inside others.
+        // Separate these overlaps without adding velocity (stabilization), otherwise particles get shoved away.
+        let mut stable = vec![false; particle_count];
+        for &i in self.stabilize.iter() {
+            stable[i] = true;
+        }
+        self.stabilize.clear();
+
         // (6) For all particles
         for i in 0..particle_count {
             let p = &self.particles[i];
 
+            // Coupled fluids reach further than the particle radius. Assumes fluid and solid particles are of similar size.
+            let mut aabb = p.get_aabb();
+            if has_coupled_fluid && p.imass != 0.0 && (p.phase == Phase::Solid || coupled_fluid[i] != usize::MAX) {
+                aabb = aabb.grow(Vec2::new(p.radius, p.radius) * 2.0 * (FLUID_COUPLING_RANGE - 1.0));
+            }
+
             // (7) Find neighboring particles and solid contacts, naive solution
-            for j in spatial_hash.aabb_iter(p.get_aabb()) { //for j in (i + 1)..particle_count {
+            for j in spatial_hash.aabb_iter(aabb) { //for j in (i + 1)..particle_count {
                 if j <= i {
                     continue;
                 }
@@ -136,6 +188,10 @@ impl Simulation {
                 if p.imass == 0.0 && p2.imass == 0.0 {
                     continue;
                 }
+                // Skip collisions between particles on layers that don't collide
+                else if !p.collides_with(p2) {
+                    continue;
+                }
                 // Skip collisions betwee particles in the same rigid body
                 else if p.phase == Phase::Solid && p2.phase == Phase::Solid && p.body == p2.body && p.body != -1 {
                     continue;
@@ -144,17 +200,32 @@ impl Simulation {
                     // Collision happens when circles overlap
                     let dist = (p.pos_guess - p2.pos_guess).magnitude(); // todo: use mag2?
                     let particle_diam = p.radius + p2.radius;
-                    if dist < particle_diam - f32::EPSILON {
+
+                    // Fluids with two-way coupling push back on nearby solids with buoyancy and drag
+                    let (fluid, solid) = if p.phase == Phase::Fluid { (i, j) } else { (j, i) };
+                    let f = coupled_fluid[fluid];
+                    if f != usize::MAX && self.particles[solid].phase == Phase::Solid && self.particles[solid].imass != 0.0 {
+                        if dist < particle_diam * FLUID_COUPLING_RANGE {
+                            let fluid_constraint = &self.global_standard_total_fluid_constraints[f];
+                            self.contact_fluid_coupling_constraints.push(FluidCouplingConstraint::new(fluid, solid, fluid_constraint.buoyancy, fluid_constraint.drag, false));
+                        }
+                    } else if dist < particle_diam - f32::EPSILON {
+
+                        // Keep stabilizing until the overlap has mostly been pushed apart
+                        let pair_stable = stable[i] || stable[j];
+                        if pair_stable && dist < 0.9 * particle_diam {
+                            self.stabilize.push(if stable[i] { i } else { j });
+                        }
 
                         // Rigid contact constraints (which include friction) apply to solid-solid contact
                         if p.phase == Phase::Solid && p2.phase == Phase::Solid {
-                            self.contact_rigid_contact_constraints.push(RigidContactConstraint::new(i, j, false)); // constraints[CONTACT].append(new RigidContactConstraint(i, j, &m_bodies));
+                            self.contact_rigid_contact_constraints.push(RigidContactConstraint::new(i, j, pair_stable)); // constraints[CONTACT].append(new RigidContactConstraint(i, j, &m_bodies));
     // #ifdef USE_STABILIZATION
     //                         constraints[STABILIZATION].append(new RigidContactConstraint(i, j, &m_bodies, true));
     // #endif
                         // Regular contact constraints (which have no friction) apply to other solid-other contact
                         } else if p.phase == Phase::Solid || p2.phase == Phase::Solid {
-                            self.contact_contact_constraints.push(ContactConstraint::new(i, j, false));
+                            self.contact_contact_constraints.push(ContactConstraint::new(i, j, pair_stable));
                             // constraints[CONTACT].append(new ContactConstraint(i, j));
                         }
                     }
@@ -185,6 +256,20 @@ impl Simulation {
     //             constraints[STABILIZATION].append(new BoundaryConstraint(i, m_yBoundaries.y, false, false, true));
     // #endif
             }
+
+            // Find static collider contacts
+            if p.imass != 0.0 {
+                for (c, collider) in self.colliders.iter().enumerate() {
+                    if !collider.collides_with(p) || !collider.aabb.grow(Vec2::new(p.radius, p.radius)).contains_point(p.pos_guess) {
+                        continue;
+                    }
+
+                    let (dist, _) = collider.signed_distance(p.pos_guess);
+                    if dist < p.radius {
+                        self.contact_collider_constraints.push(ColliderContactConstraint::new(i, c, stable[i]));
+                    }
+                }
+            }
         }
         // (9) End for
 
@@ -207,7 +292,11 @@ impl Simulation {
         self.volume_constraints.update_counts(&mut self.counts);
         self.contact_rigid_contact_constraints.update_counts(&mut self.counts);
         self.contact_contact_constraints.update_counts(&mut self.counts);
+        self.contact_fluid_coupling_constraints.update_counts(&mut self.counts);
         self.contact_boundary_constraints.update_counts(&mut self.counts);
+        self.contact_collider_constraints.update_counts(&mut self.counts);
+
+        self.spatial_hash = spatial_hash;
 
         // update_counts_callback(self);
     }
@@ -251,7 +340,9 @@ impl Simulation {
             self.volume_constraints.solve(&mut self.particles, &self.counts, time_delta);
             self.contact_rigid_contact_constraints.solve(&mut self.particles, &self.counts, &self.bodies);
             self.contact_contact_constraints.solve(&mut self.particles, &self.counts);
+            self.contact_fluid_coupling_constraints.solve(&mut self.particles, &self.counts);
             self.contact_boundary_constraints.solve(&mut self.particles, &self.counts);
+            self.contact_collider_constraints.solve(&mut self.particles, &self.counts, &self.colliders);
             //solve_constraints_callback(self, time_delta);
 
         //     for (int j = 0; j < (int) NUM_CONSTRAINT_GROUPS; j++) {
@@ -286,11 +377,25 @@ impl Simulation {
         }
         // (28) End for
 
+        self.distance_constraints.break_overstretched(&self.particles);
+
+        // Surface tension and viscosity
+        for fluid in self.global_standard_total_fluid_constraints.0.iter_mut() {
+            fluid.apply_velocity_forces(&mut self.particles, time_delta);
+        }
+
+
+        self.transfer_heat(time_delta);
+
+        // Buoyancy is applied as a force during the next step
+        self.contact_fluid_coupling_constraints.apply_buoyancy(&mut self.particles, &self.bodies, self.gravity);
 
         // Delete temporary conact constraints
         self.contact_boundary_constraints.clear();
+        self.contact_collider_constraints.clear();
         self.contact_rigid_contact_constraints.clear();
         self.contact_contact_constraints.clear();
+        self.contact_fluid_coupling_constraints.clear();
         self.counts.clear();
 
 
@@ -313,9 +418,129 @@ impl Simulation {
             }
         }
 
+        let emitted_start = self.particles.len();
         for e in self.fluid_emitters.iter_mut() {
             e.tick(&mut self.particles, time_delta, &mut self.global_standard_total_fluid_constraints);
         }
+        self.stabilize.extend(emitted_start..self.particles.len());
+
+        self.apply_phase_transitions();
+    }
+
+    // Heat flows between touching particles and out into the surroundings
+    fn transfer_heat(&mut self, time_delta: f32) {
+        if self.contact_heat_transfer > 0.0 {
+            let rate = f32::min(self.contact_heat_transfer * time_delta, 0.5);
+            let pairs = self.contact_rigid_contact_constraints.0.iter().map(|c| (c.i1, c.i2))
+                .chain(self.contact_contact_constraints.0.iter().map(|c| (c.i1, c.i2)))
+                .chain(self.contact_fluid_coupling_constraints.0.iter().map(|c| (c.fluid, c.solid)));
+            for (i, j) in pairs {
+                let flow = (self.particles[j].temperature - self.particles[i].temperature) * rate;
+                self.particles[i].temperature += flow;
+                self.particles[j].temperature -= flow;
+            }
+        }
+
+        if self.ambient_heat_transfer > 0.0 {
+            let rate = f32::min(self.ambient_heat_transfer * time_delta, 1.0);
+            for p in self.particles.iter_mut() {
+                p.temperature += (self.ambient_temperature - p.temperature) * rate;
+            }
+        }
+    }
+
+    fn apply_phase_transitions(&mut self) {
+        for t in 0..self.phase_transitions.len() {
+            let transition = self.phase_transitions[t];
+            let candidates: Vec<usize> = match transition.from {
+                PhaseOwner::Fluid(f) => self.global_standard_total_fluid_constraints[f].ps.clone(),
+                PhaseOwner::Gas(g) => self.global_standard_gas_constraints.0[g].ps.clone(),
+                PhaseOwner::Body(b) => self.bodies[b].particle_indicies.clone(),
+                PhaseOwner::Static => (0..self.particles.len()).filter(|&i| self.particles[i].imass == 0.0 && self.particles[i].phase == Phase::Solid).collect(),
+            };
+
+            for i in candidates {
+                if !transition.threshold.is_crossed(self.particles[i].temperature) {
+                    continue;
+                }
+
+                // Compressed fluid pushes back against anything that solidifies inside it, so wait for it to settle
+                if let PhaseOwner::Fluid(f) = transition.from {
+                    let lambda = self.global_standard_total_fluid_constraints[f].lambdas.get(&i).copied().unwrap_or(0.0);
+                    if lambda > 0.0 {
+                        continue;
+                    }
+                }
+
+                self.change_phase_owner(i, transition.from, transition.to);
+                if let Some(colour) = transition.colour {
+                    self.particles[i].colour = colour;
+                }
+            }
+        }
+    }
+
+    /// Move a particle from one fluid, gas, rigid body or static to another
+    pub fn change_phase_owner(&mut self, index: usize, from: PhaseOwner, to: PhaseOwner) {
+        self.stabilize.push(index);
+
+        match from {
+            PhaseOwner::Fluid(f) => {
+                let fluid = &mut self.global_standard_total_fluid_constraints[f];
+                if let Some(k) = fluid.ps.iter().position(|&i| i == index) {
+                    fluid.remove_particle(k);
+                }
+            }
+            PhaseOwner::Gas(g) => {
+                let gas = &mut self.global_standard_gas_constraints.0[g];
+                if let Some(k) = gas.ps.iter().position(|&i| i == index) {
+                    gas.remove_particle(k);
+                }
+            }
+            PhaseOwner::Body(b) => self.bodies[b].remove_particle(index, &self.particles),
+            PhaseOwner::Static => {}
+        }
+
+        // Static particles have no inverse mass, so give them back their mass when they start moving again
+        let p = &mut self.particles[index];
+        if to != PhaseOwner::Static && p.imass == 0.0 {
+            let mass = if p.mass > 0.0 { p.mass } else { 1.0 };
+            p.set_mass_2(mass);
+        }
+
+        match to {
+            PhaseOwner::Fluid(f) => {
+                self.particles[index].set_phase(Phase::Fluid).body = -1;
+                self.global_standard_total_fluid_constraints[f].add_particle(index);
+            }
+            PhaseOwner::Gas(g) => {
+                self.particles[index].set_phase(Phase::Gas).body = -1;
+                let p = self.particles[index];
+                self.global_standard_gas_constraints.0[g].add_particle(p, index);
+            }
+            PhaseOwner::Body(b) => {
+                self.particles[index].set_phase(Phase::Solid).body = b as isize;
+
+                // There is no SDF for particles that join later, a negative distance makes contacts fall back to particle normals
+                let sdf = SdfData::new(Vec2::new(0.0, 0.0), -1.0);
+                self.bodies[b].add_particle(index, sdf, &self.particles);
+            }
+            PhaseOwner::Static => {
+                let p = &mut self.particles[index];
+                p.set_phase(Phase::Solid);
+                p.body = -1;
+                p.imass = 0.0;
+                p.tmass = 0.0;
+                p.vel = Vec2::new(0.0, 0.0);
+                p.force = Vec2::new(0.0, 0.0);
+                p.pos_guess = p.pos;
+            }
+        }
+    }
+
+    pub fn add_phase_transition(&mut self, t: PhaseTransition) -> usize {
+        self.phase_transitions.push(t);
+        self.phase_transitions.len() - 1
     }
 
     pub fn create_rigid_body(&mut self, particles: &mut ParticleVec, sdf_data: &Vec<SdfData>) -> usize {
@@ -357,20 +582,15 @@ impl Simulation {
         // return body;
     }
 
-    pub fn create_fluid(&mut self, particles: &ParticleVec, density: f32) -> usize {
+    pub fn create_fluid(&mut self, particles: &ParticleVec, parameters: FluidParameters) -> usize {
         let offset = self.particles.len();
 
-        // todo: BUGGY!? probably want to just assign body id's 
-        //let mut rng = rand::rng();
-        //let r1: f32 = rng.random();
-        let bod = isize::MAX - self.body_count as isize; //(r1 * 100.0) as isize; //self.body_count; //self.global_standard_total_fluid_constraints.len(); //100 * rand::rng().random(); // assign a rnadom body number to this fluid? probably just want to avoid self collisions
-        self.body_count += 1;
-
+        // Fluids aren't bodies, only solids in the same rigid body skip colliding with each other
         let mut indices = vec![];
         for i in 0..particles.len() { //for (int i = 0; i < verts->size(); i++) {
             let mut p = particles[i];
             p.set_phase(Phase::Fluid);
-            p.body = bod as isize;
+            p.body = -1;
             // p->ph = FLUID;
             // p->bod = bod;
 
@@ -383,7 +603,7 @@ impl Simulation {
         }
 
         let idx = self.global_standard_total_fluid_constraints.len();
-        self.global_standard_total_fluid_constraints.push(TotalFluidConstraint::new(density, &indices));
+        self.global_standard_total_fluid_constraints.push(TotalFluidConstraint::from_parameters(&parameters, &indices));
         return idx;
     }
 
@@ -398,14 +618,12 @@ impl Simulation {
     // open = false by default
     pub fn create_gas(&mut self, particles: &ParticleVec, density: f32, open: bool) -> usize {
         let offset = self.particles.len();
-        let bod = self.body_count; //100 * frand();
-        self.body_count += 1;
 
         let mut indices = vec![];
         for i in 0..particles.len() { //for (int i = 0; i < verts->size(); i++) {
             let mut p = particles[i];
             p.set_phase(Phase::Gas);
-            p.body = bod as isize;
+            p.body = -1;
 
             if p.imass == 0.0 {
                 assert!(false, "A gas cannot have a point of infinite mass.");
@@ -436,7 +654,58 @@ impl Simulation {
         self.volume_constraints.0.len() - 1
     }
 
+    pub fn add_collider(&mut self, c: Collider) -> usize {
+        self.colliders.push(c);
+        self.colliders.len() - 1
+    }
+
     pub fn add_particle(&mut self, p: Particle) {
         self.particles.push(p);
     }
+
+    /// Remove particles between steps (i.e. after post_solve). Constraints that lose a particle are disabled rather than removed
+    /// so constraint ids stay valid. Anything else holding particle indices or handles (e.g. entities) needs updating with the returned remap.
+    pub fn remove_particles(&mut self, indices: &[usize]) -> ParticleRemap {
+        debug_assert!(self.contact_contact_constraints.len() == 0 && self.contact_rigid_contact_constraints.len() == 0);
+
+        let mut removed = vec![false; self.particles.len()];
+        for &i in indices {
+            removed[i] = true;
+        }
+
+        // Bodies reshape around the particles they keep, which needs the old indices
+        for body in self.bodies.iter_mut() {
+            let body_removed: Vec<usize> = body.particle_indicies.iter().copied().filter(|&i| removed[i]).collect();
+            if !body_removed.is_empty() {
+                body.remove_particles(&body_removed, &self.particles);
+            }
+        }
+
+        let remap = self.particles.remove(indices);
+
+        for body in self.bodies.iter_mut() {
+            body.remap_particles(&remap);
+        }
+        self.distance_constraints.remap_particles(&remap);
+        self.spring_constraints.remap_particles(&remap);
+        self.global_standard_total_fluid_constraints.remap_particles(&remap);
+        self.global_standard_gas_constraints.remap_particles(&remap);
+        self.volume_constraints.remap_particles(&remap);
+        remap.indices(&mut self.stabilize);
+        self.spatial_hash.clear(); // refilled next step
+
+        remap
+    }
+
+    /// Remove moving particles that have left bounds, e.g. debris that fell off the level or smoke that drifted away. Particles in keep stay wherever they are.
+    /// Returns None if there was nothing to remove, so nothing needs remapping.
+    pub fn remove_particles_outside(&mut self, bounds: &Aabb2d, keep: &[usize]) -> Option<ParticleRemap> {
+        let indices: Vec<usize> = (0..self.particles.len())
+            .filter(|&i| self.particles[i].imass != 0.0 && !bounds.contains_point(self.particles[i].pos) && !keep.contains(&i))
+            .collect();
+        if indices.is_empty() {
+            return None;
+        }
+        Some(self.remove_particles(&indices))
+    }
 }
\ No newline at end of file
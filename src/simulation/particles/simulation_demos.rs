@@ -1,11 +1,42 @@
 use rand::Rng;
 
-use crate::{core::math::{vec2::Vec2, vec4::Vec4}, simulation::{constraints::distance_constraint::DistanceConstraint, particles::{particle::{Particle, Phase}, particle_vec::ParticleVec, sdf_data::SdfData, simulation::Simulation}}};
+use crate::{core::math::{vec2::Vec2, vec4::Vec4}, simulation::{colliders::{collider::Collider, sdf_grid_collider::SdfGridCollider}, constraints::distance_constraint::DistanceConstraint, particles::{fluid_parameters::FluidParameters, particle::{Particle, Phase, COLLISION_LAYER_SOFT_BODY}, particle_vec::ParticleVec, phase_transition::{PhaseOwner, PhaseTransition, TemperatureThreshold}, sdf_data::SdfData, simulation::Simulation}}};
 
 pub struct SimulationDemos {
 }
 
 impl SimulationDemos {
+    /// Names of the demo scenes, as given on the command line or picked from the level select
+    pub const SCENES: &[&str] = &["friction", "granular", "sdf", "boxes", "wall", "pendulum", "rope", "fluid", "fluid_solid", "gas", "water_balloon", "newtons_cradle", "smoke_open", "smoke_closed", "rope_gas", "volcano", "wrecking_ball", "colliders", "droplets", "ice"];
+
+    /// Set up the named demo scene. Returns false if there is no scene of that name.
+    pub fn init_scene(scene: &str, sim: &mut Simulation) -> bool {
+        match scene {
+            "friction" => Self::init_friction(sim),
+            "granular" => Self::init_granular(sim),
+            "sdf" => Self::init_sdf(sim),
+            "boxes" => Self::init_boxes(sim),
+            "wall" => Self::init_wall(sim),
+            "pendulum" => Self::init_pendulum(sim),
+            "rope" => Self::init_rope(sim),
+            "fluid" => Self::init_fluid(sim),
+            "fluid_solid" => Self::init_fluid_solid(sim),
+            "gas" => Self::init_gas(sim),
+            "water_balloon" => Self::init_water_balloon(sim),
+            "newtons_cradle" => Self::init_newtons_cradle(sim),
+            "smoke_open" => Self::init_smoke_open(sim),
+            "smoke_closed" => Self::init_smoke_closed(sim),
+            "rope_gas" => Self::init_rope_gas(sim),
+            "volcano" => Self::init_volcano(sim),
+            "wrecking_ball" => Self::init_wrecking_ball(sim),
+            "colliders" => Self::init_colliders(sim),
+            "droplets" => Self::init_droplets(sim),
+            "ice" => Self::init_ice(sim),
+            _ => return false,
+        }
+        true
+    }
+
     pub fn init_friction(sim: &mut Simulation) {
         sim.x_boundaries = Vec2::new(-20.0,20.0);
         sim.y_boundaries = Vec2::new(0.0,1000000.0);
@@ -271,7 +302,7 @@ impl SimulationDemos {
         let dist = particle_rad;
 
         let mut e1 = *Particle::default().set_radius(particle_rad).set_pos(Vec2::new(sim.x_boundaries.x, top)).set_mass_2(0.0).set_phase(Phase::Solid);
-        e1.body = -2; // -2?!
+        e1.set_collision_layer(COLLISION_LAYER_SOFT_BODY, !COLLISION_LAYER_SOFT_BODY);
         sim.add_particle(e1);
 
         let mut i = sim.x_boundaries.x;
@@ -285,7 +316,7 @@ impl SimulationDemos {
         }
 
         let mut e2 = *Particle::default().set_radius(particle_rad).set_pos(Vec2::new(sim.x_boundaries.y, top)).set_mass_2(0.0).set_phase(Phase::Solid);
-        e2.body = -2;
+        e2.set_collision_layer(COLLISION_LAYER_SOFT_BODY, !COLLISION_LAYER_SOFT_BODY);
         sim.add_particle(e2);
 
         sim.add_distance_constraint(DistanceConstraint::new(dist, sim.particles.len() - 2, sim.particles.len() - 1, false));
@@ -308,7 +339,7 @@ impl SimulationDemos {
 
             x += delta;
         }
-        sim.create_fluid(&particles, 1.75);
+        sim.create_fluid(&particles, FluidParameters::new(1.75));
     }
 
 
@@ -345,7 +376,7 @@ impl SimulationDemos {
 
                 x += delta;
             }
-            sim.create_fluid(&particles, 1.0 + 0.75 * d);
+            sim.create_fluid(&particles, *FluidParameters::new(1.0 + 0.75 * d).set_viscosity(0.01));
             particles.clear();
 
             d += 1.0;
@@ -386,7 +417,7 @@ impl SimulationDemos {
 
                 x += delta;
             }
-            sim.create_fluid(&particles, 1.0 + 1.25 * (d + 1.0));
+            sim.create_fluid(&particles, FluidParameters::new(1.0 + 1.25 * (d + 1.0)));
             particles.clear();
 
             d += 1.0;
@@ -507,7 +538,7 @@ impl SimulationDemos {
                 }
                 x += delta;
             }
-            sim.create_fluid(&particles, 4.0 + 0.75 * (d + 1.0));
+            sim.create_fluid(&particles, FluidParameters::new(4.0 + 0.75 * (d + 1.0)));
             particles.clear();
 
             d += 1.0;
@@ -533,7 +564,7 @@ impl SimulationDemos {
         for i in 0..samples { //for (int i = 0; i < samples; i++) {
             let angle = f32::to_radians(i as f32 * da); //D2R(i * da);
             let mut part = *Particle::default().set_colour(blue).set_radius(particle_rad).set_pos(Vec2::new(f32::sin(angle), f32::cos(angle)) * 3.0).set_mass_2(1.0);
-            part.body = -2; // ???
+            part.set_collision_layer(COLLISION_LAYER_SOFT_BODY, !COLLISION_LAYER_SOFT_BODY); // Prevent self-collision
             let idx = sim.particles.len();
             sim.add_particle(part);
 
@@ -542,12 +573,14 @@ impl SimulationDemos {
             }
         }
         sim.add_distance_constraint(DistanceConstraint::from_particles(0, sim.particles.len() - 1, &sim.particles));
+        // the red balloon gets a layer of its own so the two balloons still hit each other
+        const RED_BALLOON_LAYER: u32 = COLLISION_LAYER_SOFT_BODY << 1;
         let idk = sim.particles.len();
 
         for i in 0..samples { //(int i = 0; i < samples; i++) {
             let angle = f32::to_radians(i as f32 * da);
             let mut part = *Particle::default().set_colour(red).set_radius(particle_rad).set_pos(Vec2::new(f32::sin(angle), f32::cos(angle) + 3.0) * 3.0).set_mass_2(1.0);
-            part.body = -3; // ?? I think this just stops collisions without having a "body" assigned
+            part.set_collision_layer(RED_BALLOON_LAYER, !RED_BALLOON_LAYER); // Prevent self-collision
             let idx = sim.particles.len();
             sim.add_particle(part);
 
@@ -574,7 +607,7 @@ impl SimulationDemos {
             }
             x += delta;
         }
-        sim.create_fluid(&particles, 1.75);
+        sim.create_fluid(&particles, FluidParameters::new(1.75));
 
         particles.clear();
         let mut x = -2.0;
@@ -589,7 +622,7 @@ impl SimulationDemos {
             }
             x += delta;
         }
-        sim.create_fluid(&particles, 1.75);
+        sim.create_fluid(&particles, FluidParameters::new(1.75));
     }
 
 
@@ -710,13 +743,13 @@ impl SimulationDemos {
         let dist = particle_rad;
 
         let mut e1 = *Particle::default().set_radius(particle_rad).set_pos(Vec2::new(0.0, top)).set_mass_2(0.0).set_phase(Phase::Solid);
-        e1.body = -2;
+        e1.set_collision_layer(COLLISION_LAYER_SOFT_BODY, !COLLISION_LAYER_SOFT_BODY);
         sim.add_particle(e1);
 
         let mut i = 0.0 + dist;
         while i < 4.0 * scale - dist { //for (double i = 0 + dist; i < 4*scale - dist; i += dist) {
             let mut part = *Particle::default().set_colour(red).set_radius(particle_rad).set_pos(Vec2::new(i, top)).set_mass_2( 2.0).set_phase(Phase::Solid);
-            part.body = -2;
+            part.set_collision_layer(COLLISION_LAYER_SOFT_BODY, !COLLISION_LAYER_SOFT_BODY);
             sim.add_particle(part);
 
             sim.add_distance_constraint(
@@ -772,6 +805,9 @@ impl SimulationDemos {
         let particle_rad = particle_diam / 2.0;
 
         let red = Vec4::RED;
+        let lava = Vec4::new(1.0, 0.5, 0.0, 1.0);
+        let rock = Vec4::new(0.3, 0.3, 0.3, 1.0);
+        let lava_temperature = 1200.0;
 
         let mut rng = rand::rng();
 
@@ -799,18 +835,23 @@ impl SimulationDemos {
                 let r3: f32 = rng.random();
                 let r4: f32 = rng.random();
 
-                particles.push(*Particle::default().set_radius(particle_rad).set_pos(Vec2::new(x,y) + 0.2 * Vec2::new(r1 - 0.5, r2 - 0.5)).set_mass_2(1.1));
-                particles.push(*Particle::default().set_radius(particle_rad).set_pos(Vec2::new(-x,y) + 0.2 * Vec2::new(r3 - 0.5, r4 - 0.5)).set_mass_2(1.1));
+                particles.push(*Particle::default().set_colour(lava).set_temperature(lava_temperature).set_radius(particle_rad).set_pos(Vec2::new(x,y) + 0.2 * Vec2::new(r1 - 0.5, r2 - 0.5)).set_mass_2(1.1));
+                particles.push(*Particle::default().set_colour(lava).set_temperature(lava_temperature).set_radius(particle_rad).set_pos(Vec2::new(-x,y) + 0.2 * Vec2::new(r3 - 0.5, r4 - 0.5)).set_mass_2(1.1));
 
                 x += delta;
             }
 
             y += delta;
         }
-        let fluid_idx = sim.create_fluid(&particles, 1.0); //TotalFluidConstraint *fs = createFluid(&particles, 1);
+        let fluid_idx = sim.create_fluid(&particles, FluidParameters::new(1.0)); //TotalFluidConstraint *fs = createFluid(&particles, 1);
         particles.clear();
 
         sim.create_fluid_emitter(Vec2::new(0.0,0.0), scale*4.0, fluid_idx);
+        sim.fluid_emitters.last_mut().unwrap().set_temperature(lava_temperature);
+
+        // Lava cools in the air and turns to rock
+        sim.ambient_heat_transfer = 0.3;
+        sim.add_phase_transition(*PhaseTransition::new(PhaseOwner::Fluid(fluid_idx), PhaseOwner::Static, TemperatureThreshold::Below(700.0)).set_colour(rock));
 
     //    double top = scale-.5, dist = PARTICLE_RAD;
 
@@ -919,7 +960,7 @@ impl SimulationDemos {
 
             x += delta;
         }
-        sim.create_fluid(&particles, 2.5);
+        sim.create_fluid(&particles, FluidParameters::new(2.5));
         particles.clear();
 
         let idx = sim.particles.len();
@@ -944,4 +985,128 @@ impl SimulationDemos {
             
 
     }
-}
\ No newline at end of file
+
+    pub fn init_colliders(sim: &mut Simulation) {
+        sim.x_boundaries = Vec2::new(-20.0,20.0);
+        sim.y_boundaries = Vec2::new(-10.0,1000000.0);
+
+        let particle_diam = 0.5;
+        let particle_rad = particle_diam / 2.0;
+
+        // A slippery ramp and a grippy ramp below it
+        let mut ice = Collider::segment(Vec2::new(-15.0, 12.0), Vec2::new(-2.0, 8.0));
+        ice.set_friction(0.0, 0.0).set_colour(Vec4::BLUE);
+        sim.add_collider(ice);
+
+        let mut rubber = Collider::segment(Vec2::new(15.0, 4.0), Vec2::new(2.0, 0.0));
+        rubber.set_friction(1.0, 1.0).set_colour(Vec4::RED);
+        sim.add_collider(rubber);
+
+        // A convex block in the middle
+        let mut block = Collider::polygon(vec![Vec2::new(-1.5, 3.0), Vec2::new(1.5, 3.0), Vec2::new(1.0, 5.0), Vec2::new(-1.0, 5.0)]);
+        block.set_colour(Vec4::GREEN);
+        sim.add_collider(block);
+
+        // A bowl on the floor described by a signed distance field
+        let bowl_centre = Vec2::new(0.0, -2.0);
+        let grid = SdfGridCollider::from_fn(Vec2::new(-10.0, -10.0), 0.25, 81, 41, |p| {
+            let d = (p - bowl_centre).magnitude();
+            let inside_ring = f32::max(d - 8.0, 7.0 - d);
+            f32::max(inside_ring, p.y - bowl_centre.y)
+        });
+        sim.add_collider(Collider::sdf_grid(grid));
+
+        // Granular particles to pour over everything
+        for i in 0..10 {
+            for j in 0..6 {
+                let pos = Vec2::new(-14.0 + (i as f32) * particle_diam, 14.0 + (j as f32) * particle_diam);
+                let mut part = *Particle::default().set_radius(particle_rad).set_pos(pos).set_mass_2(1.0);
+                part.phase = Phase::Solid;
+                part.s_friction = 0.35;
+                part.k_friction = 0.3;
+                sim.add_particle(part);
+            }
+        }
+    }
+
+    pub fn init_droplets(sim: &mut Simulation) {
+        sim.x_boundaries = Vec2::new(-10.0,10.0);
+        sim.y_boundaries = Vec2::new(0.0,1000000.0);
+
+        let particle_diam = 0.5;
+        let particle_rad = particle_diam / 2.0;
+        let delta = 0.6;
+
+        let mut rng = rand::rng();
+
+        // Water on the left holds together in droplets, slime on the right oozes
+        let fluids = [
+            (-5.0, Vec4::BLUE, *FluidParameters::new(1.75).set_surface_tension(5.0).set_viscosity(0.01)),
+            (5.0, Vec4::GREEN, *FluidParameters::new(1.75).set_surface_tension(5.0).set_viscosity(0.5)),
+        ];
+
+        for (centre_x, colour, parameters) in fluids {
+            let mut particles = ParticleVec::new();
+            let mut x = centre_x - 1.5;
+            while x < centre_x + 1.5 {
+                let mut y = 6.0;
+                while y < 9.0 {
+                    let r1: f32 = rng.random();
+                    let r2: f32 = rng.random();
+
+                    particles.push(*Particle::default().set_radius(particle_rad).set_colour(colour).set_pos(Vec2::new(x, y) + 0.2 * Vec2::new(r1 - 0.5, r2 - 0.5)).set_mass_2(1.0));
+                    y += delta;
+                }
+                x += delta;
+            }
+            sim.create_fluid(&particles, parameters);
+        }
+    }
+
+    pub fn init_ice(sim: &mut Simulation) {
+        sim.x_boundaries = Vec2::new(-6.0,6.0);
+        sim.y_boundaries = Vec2::new(0.0,1000000.0);
+
+        let particle_diam = 0.5;
+        let particle_rad = particle_diam / 2.0;
+        let delta = 0.6;
+
+        let mut rng = rand::rng();
+
+        // Warm water
+        let mut particles = ParticleVec::new();
+        let mut x = -5.5;
+        while x < 5.5 {
+            let mut y = particle_rad;
+            while y < 3.0 {
+                let r1: f32 = rng.random();
+                let r2: f32 = rng.random();
+
+                particles.push(*Particle::default().set_colour(Vec4::BLUE).set_temperature(40.0).set_radius(particle_rad).set_pos(Vec2::new(x, y) + 0.2 * Vec2::new(r1 - 0.5, r2 - 0.5)).set_mass_2(1.0));
+                y += delta;
+            }
+            x += delta;
+        }
+        let water = sim.create_fluid(&particles, FluidParameters::new(1.75));
+        particles.clear();
+
+        // A block of ice dropped in to it
+        let root2 = f32::sqrt(2.0);
+        let mut sdf_data = Vec::<SdfData>::new();
+        let dim = 4;
+        for x in 0..dim {
+            for y in 0..dim {
+                let pos = Vec2::new((x as f32 - (dim - 1) as f32 / 2.0) * particle_diam, 6.0 + y as f32 * particle_diam);
+                particles.push(*Particle::default().set_colour(Vec4::WHITE).set_temperature(-10.0).set_radius(particle_rad).set_pos(pos).set_mass_2(0.9));
+
+                let gradient = Vec2::new(x as f32 - (dim - 1) as f32 / 2.0, y as f32 - (dim - 1) as f32 / 2.0);
+                sdf_data.push(SdfData::new(gradient.normalize(), particle_rad * root2));
+            }
+        }
+        let ice = sim.create_rigid_body(&mut particles, &sdf_data);
+
+        // Ice melts into the water as it warms up
+        sim.contact_heat_transfer = 2.0;
+        sim.add_phase_transition(*PhaseTransition::new(PhaseOwner::Body(ice), PhaseOwner::Fluid(water), TemperatureThreshold::Above(0.0)).set_colour(Vec4::BLUE));
+    }
+}